@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use serde_json::Value;
 use tokio::runtime::Runtime;
-use crate::{components::*, send_message, MessageType, UdpSocketResource};
+use crate::{components::*, send_message, FireMode, MessageType, UdpSocketResource};
 use serde_json::json;
 // use crate::components::*;
 // use tokio::runtime::Runtime;
@@ -19,15 +19,72 @@ lazy_static::lazy_static! {
 pub fn shoot_ball(
     mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(&Transform, &Player)>,
+    other_players_query: Query<(&Transform, &OtherPlayer)>,
+    mut inventory: ResMut<WeaponInventory>,
+    effects: Res<ActiveEffects>,
+    mut ball_sequence: ResMut<BallSequence>,
+    maze: Res<Maze>,
+    mut tracers: ResMut<Tracers>,
+    udp_socket_res: Res<UdpSocketResource>,
     mut meshes: ResMut<Assets<Mesh>>, // Récupérer les ressources Mesh
     mut materials: ResMut<Assets<StandardMaterial>>, // Récupérer les ressources Material
 ) {
     if keyboard_input.just_pressed(KeyCode::Space) {
-        if let Ok(player_transform) = player_query.get_single() {
-            let ball_speed = 20.0; // Vitesse de la balle
-            let ball_direction = player_transform.forward().normalize();
-            let ball_spawn_position = player_transform.translation + ball_direction * 2.0; // Lancer la balle devant le joueur
+        if let Ok((player_transform, player)) = player_query.get_single() {
+            if !inventory.can_fire() {
+                // Chargeur vide: recharger automatiquement
+                if inventory.ammo[inventory.current] == 0 && inventory.start_reload() {
+                    send_weapon_message(&udp_socket_res, MessageType::Reload, Value::Null, player.id);
+                }
+                return;
+            }
+            let current = inventory.current;
+            inventory.ammo[current] -= 1;
+            // Le tir rapide double la cadence, comme côté serveur
+            let fire_rate = if effects.rapid_fire.is_some() {
+                inventory.weapon().fire_rate * 2.0
+            } else {
+                inventory.weapon().fire_rate
+            };
+            inventory.cooldown = 1.0 / fire_rate.max(0.01);
+            let weapon = inventory.weapon().clone();
+
+            // La caméra est au centre du joueur
+            let origin = player_transform.translation;
+            let aim = player_transform.forward().normalize();
+
+            if weapon.fire_mode == FireMode::Hitscan {
+                // La dispersion des tirs instantanés est tirée par le serveur
+                let (target, end) = hitscan(&maze, origin, aim, &other_players_query);
+                tracers.add(origin, end);
+                send_weapon_message(
+                    &udp_socket_res,
+                    MessageType::Shoot,
+                    json!({
+                        "origin": origin,
+                        "direction": aim,
+                        "target": target,
+                    }),
+                    player.id,
+                );
+                return;
+            }
+
+            let ball_direction = apply_spread(aim, weapon.spread, &mut rand::thread_rng());
+            let seq = ball_sequence.next();
+            send_weapon_message(
+                &udp_socket_res,
+                MessageType::Shoot,
+                json!({
+                    "origin": origin,
+                    "direction": ball_direction,
+                    "ball_id": seq,
+                }),
+                player.id,
+            );
+
+            let ball_spawn_position = origin + ball_direction * 2.0; // Lancer la balle devant le joueur
             commands.spawn((
                 PbrBundle {
                     mesh: meshes.add(Mesh::from(shape::UVSphere { radius: 0.1, ..default() })), // Utiliser UVSphere
@@ -35,39 +92,191 @@ pub fn shoot_ball(
                     transform: Transform::from_translation(ball_spawn_position),
                     ..default()
                 },
-                Ball,
-            ))
-            .insert(Velo(ball_direction * ball_speed)); // Ajouter une vélocité à la balle
+                Ball { seq },
+                Velo(ball_direction * weapon.projectile_speed), // Ajouter une vélocité à la balle
+                Lifetime(Timer::from_seconds(weapon.projectile_lifetime, TimerMode::Once)),
+                // Sans rebond (0), la balle s'arrête au premier mur
+                Ricochet {
+                    bounces_left: weapon.ricochets,
+                },
+            ));
         }
     }
 }
+
+// Rechargement (R), changement d'arme (1-9) et délais entre les tirs
+pub fn weapon_controls(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut inventory: ResMut<WeaponInventory>,
+    udp_socket_res: Res<UdpSocketResource>,
+) {
+    inventory.cooldown -= time.delta_seconds();
+
+    let finished = match inventory.reload.as_mut() {
+        Some(timer) => timer.tick(time.delta()).finished(),
+        None => false,
+    };
+    if finished {
+        let current = inventory.current;
+        inventory.ammo[current] = inventory.weapon().magazine_size;
+        inventory.reload = None;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::R) && inventory.start_reload() {
+        send_weapon_message(&udp_socket_res, MessageType::Reload, Value::Null, udp_socket_res.id);
+    }
+
+    let keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    for (index, key) in keys.iter().enumerate() {
+        if keyboard_input.just_pressed(*key) && index < inventory.weapons.len() && index != inventory.current {
+            inventory.current = index;
+            inventory.reload = None;
+            send_weapon_message(
+                &udp_socket_res,
+                MessageType::SwitchWeapon,
+                json!({ "weapon": index }),
+                udp_socket_res.id,
+            );
+        }
+    }
+}
+
+pub fn ammo_text_update_system(
+    inventory: Res<WeaponInventory>,
+    effects: Res<ActiveEffects>,
+    mut query: Query<&mut Text, With<AmmoText>>,
+) {
+    let mut effect_lines = String::new();
+    for (name, timer) in [
+        ("Speed", &effects.speed_boost),
+        ("Shield", &effects.shield),
+        ("Rapid fire", &effects.rapid_fire),
+    ] {
+        if let Some(timer) = timer {
+            effect_lines.push_str(&format!("{} {:.0}s\n", name, timer.remaining_secs().ceil()));
+        }
+    }
+    for mut text in query.iter_mut() {
+        let weapon = inventory.weapon();
+        text.sections[0].value = effect_lines.clone() + &match &inventory.reload {
+            Some(timer) => format!("{}  Reloading... {:.1}s", weapon.name, timer.remaining_secs()),
+            None => format!(
+                "{}  {}/{}",
+                weapon.name, inventory.ammo[inventory.current], weapon.magazine_size
+            ),
+        };
+    }
+}
+
+fn send_weapon_message(udp_socket_res: &UdpSocketResource, typ: MessageType, content: Value, id: usize) {
+    let socket = udp_socket_res.socket.clone();
+    let username = udp_socket_res.username.clone();
+    TOKIO_RUNTIME.spawn(async move {
+        if let Err(e) = send_message(&socket, typ, username, content, id).await {
+            eprintln!("Échec de l'envoi du message : {}", e);
+        }
+    });
+}
+
+// Premier joueur touché par le rayon avant un mur, et point d'impact.
+// Le serveur refait le même calcul pour valider le tir.
+fn hitscan(
+    maze: &Maze,
+    origin: Vec3,
+    direction: Vec3,
+    other_players_query: &Query<(&Transform, &OtherPlayer)>,
+) -> (Option<usize>, Vec3) {
+    let end = origin + direction * HITSCAN_RANGE;
+    let mut nearest = maze
+        .raycast(origin, end)
+        .map_or(HITSCAN_RANGE, |hit| hit.distance);
+    let mut target = None;
+    for (transform, other_player) in other_players_query.iter() {
+        if let Some(distance) = ray_sphere(origin, direction, transform.translation, HIT_RADIUS) {
+            if distance < nearest {
+                nearest = distance;
+                target = Some(other_player.id);
+            }
+        }
+    }
+    (target, origin + direction * nearest)
+}
+
+pub fn draw_tracers(time: Res<Time>, mut tracers: ResMut<Tracers>, mut gizmos: Gizmos) {
+    tracers.0.retain_mut(|tracer| {
+        gizmos.line(tracer.from, tracer.to, Color::YELLOW);
+        !tracer.timer.tick(time.delta()).finished()
+    });
+}
        
 // Système pour déplacer les balles
 pub fn move_balls(
     time: Res<Time>,
+    maze: Res<Maze>,
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Transform, &Velo)>,
+    mut query: Query<(Entity, &Ball, &mut Transform, &mut Velo, &mut Ricochet, Option<&mut Lifetime>)>,
     udp_socket_res: Res<UdpSocketResource>,
 
 ) {
-    for (entity, mut transform, velocity) in query.iter_mut() {
-        transform.translation += velocity.0 * time.delta_seconds(); // Déplacer la balle
-        // Vérifier si la balle est hors de l'écran ou entre en collision avec un mur
+    for (entity, ball, mut transform, mut velocity, mut ricochet, lifetime) in query.iter_mut() {
+        let previous = transform.translation;
+        let next = previous + velocity.0 * time.delta_seconds();
+        transform.translation = next; // Déplacer la balle
+
+        let mut destroyed = false;
+        // Vérifier si la balle traverse un mur pendant cette frame
+        if let Some(hit) = maze.raycast(previous, next) {
+            if ricochet.bounces_left > 0 && hit.normal != Vec3::ZERO {
+                // Rebondir sur le mur: réflexion de la vitesse selon la normale
+                ricochet.bounces_left -= 1;
+                velocity.0 -= 2.0 * velocity.0.dot(hit.normal) * hit.normal;
+                transform.translation = hit.point + hit.normal * 0.01;
+            } else {
+                destroyed = true;
+            }
+        }
+
+        // Détruire la balle à la fin de sa durée de vie
+        if let Some(mut lifetime) = lifetime {
+            if lifetime.0.tick(time.delta()).finished() {
+                destroyed = true;
+            }
+        }
+
+        // Vérifier si la balle est hors de l'écran
+        if transform.translation.y < -50.0 || transform.translation.y > 50.0 || transform.translation.x < -50.0 || transform.translation.x > 50.0 {
+            destroyed = true;
+        }
+
         let mut content = json!({
             "ball_movement": transform.translation,
+            "ball_id": ball.seq,
         });
         let socket = udp_socket_res.socket.clone();
         let username = udp_socket_res.username.clone();
+        let id = udp_socket_res.id;
         
-        if transform.translation.y < -50.0 || transform.translation.y > 50.0 || transform.translation.x < -50.0 || transform.translation.x > 50.0 {
+        if destroyed {
             content = json!({
                 "delete_ball": true,
+                "ball_id": ball.seq,
             });
-            commands.entity(entity).despawn(); // Détruire la balle si elle est hors de l'écran
+            commands.entity(entity).despawn(); // Détruire la balle
         }
         
         TOKIO_RUNTIME.spawn(async move {
-            if let Err(e) = send_message(&socket, MessageType::Action, username, content, 0).await {
+            if let Err(e) = send_message(&socket, MessageType::Action, username, content, id).await {
                 eprintln!("Échec de l'envoi du message : {}", e);
             }
         });
@@ -116,37 +325,47 @@ pub fn move_balls(
 
 
 pub fn check_ball_player_collisions(
-    ball_query: Query<(&Transform, Entity), With<Ball>>,
+    ball_query: Query<(&Transform, Entity, &Ball)>,
     mut player_query: Query<(&Transform, &mut OtherPlayer)>,
     mut commands: Commands,
     udp_socket_res: Res<UdpSocketResource>,
 
 ) {
     // println!("message receiver {:?}", message_receiver);
-    for (ball_transform, entity_ball) in ball_query.iter() {
+    for (ball_transform, entity_ball, ball) in ball_query.iter() {
         for (player_transform, other_player) in player_query.iter_mut() {
             let distance = ball_transform.translation.distance(player_transform.translation);
             if distance < 1.0 {
                 let id = other_player.id.clone();
                 let socket = udp_socket_res.socket.clone();
+                // Le serveur identifie le tireur par son nom pour valider la touche
+                let shooter = udp_socket_res.username.clone();
+                let hit = json!({
+                    "ball_id": ball.seq,
+                    "point": ball_transform.translation,
+                });
                 TOKIO_RUNTIME.spawn(async move {
-                    if let Err(e) =  send_message(&socket, MessageType::UpdateLife, "".to_string(), Value::Null, id).await{
+                    if let Err(e) =  send_message(&socket, MessageType::UpdateLife, shooter, hit, id).await{
                         eprintln!("Échec de l'envoi du message : {}", e);
                     }
                 });
 
                 let content = json!({
                     "delete_ball": true,
+                    "ball_id": ball.seq,
                 });
                 let username = udp_socket_res.username.clone();
                 let socket = udp_socket_res.socket.clone();
+                let own_id = udp_socket_res.id;
 
                 TOKIO_RUNTIME.spawn(async move {
-                    if let Err(e) = send_message(&socket, MessageType::Action, username, content, 0).await {
+                    if let Err(e) = send_message(&socket, MessageType::Action, username, content, own_id).await {
                         eprintln!("Échec de l'envoi du message : {}", e);
                     }
                 });
                 commands.entity(entity_ball).despawn();
+                // La balle est détruite: elle ne peut toucher qu'un joueur
+                break;
             }
         }
     }
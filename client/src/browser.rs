@@ -0,0 +1,109 @@
+use serde::Deserialize;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+// Mêmes valeurs que server/src/discovery.rs
+pub const DISCOVERY_PORT: u16 = 8082;
+pub const DISCOVERY_QUERY: &[u8] = b"maze-wars discover";
+// Durée d'écoute des réponses après la requête
+pub const DISCOVERY_WAIT: Duration = Duration::from_millis(700);
+
+/// A server that answered the LAN discovery query.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LanServer {
+    // Adresse à donner à `--server=`
+    pub address: String,
+    pub name: String,
+    pub map: String,
+    pub level: i32,
+    pub players: usize,
+    #[serde(default)]
+    pub bots: usize,
+    pub max_players: usize,
+    #[serde(default)]
+    pub password: bool,
+    // Temps entre la requête et la réponse
+    #[serde(skip)]
+    pub ping: Duration,
+}
+
+/// Broadcasts the discovery query and collects the answers for `DISCOVERY_WAIT`, fastest first.
+pub async fn discover_servers() -> io::Result<Vec<LanServer>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.set_broadcast(true)?;
+    let sent_at = Instant::now();
+    if let Err(e) = socket.send_to(DISCOVERY_QUERY, ("255.255.255.255", DISCOVERY_PORT)).await {
+        eprintln!("Failed to broadcast the discovery query: {}", e);
+    }
+    // Le broadcast ne revient pas toujours vers la machine elle-même
+    socket.send_to(DISCOVERY_QUERY, ("127.0.0.1", DISCOVERY_PORT)).await?;
+
+    let deadline = tokio::time::Instant::now() + DISCOVERY_WAIT;
+    let mut servers: Vec<LanServer> = Vec::new();
+    let mut buf = vec![0; 65536];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let Ok((amt, _)) = received else {
+            continue;
+        };
+        let Ok(mut server) = serde_json::from_slice::<LanServer>(&buf[..amt]) else {
+            continue;
+        };
+        // Un serveur local répond au broadcast et à la boucle locale
+        if servers.iter().any(|known| known.address == server.address) {
+            continue;
+        }
+        server.ping = sent_at.elapsed();
+        servers.push(server);
+    }
+    servers.sort_by_key(|server| server.ping);
+    Ok(servers)
+}
+
+/// Une ligne par serveur, numérotée pour le choix dans le terminal.
+pub fn server_table(servers: &[LanServer]) -> String {
+    let mut lines = vec![format!(
+        "{:>3}  {:<24} {:<21} {:<12} {:>5} {:>7} {:>6}",
+        "#", "name", "address", "map", "level", "players", "ping"
+    )];
+    for (index, server) in servers.iter().enumerate() {
+        let mut notes = String::new();
+        if server.bots > 0 {
+            notes.push_str(&format!("  {} bots", server.bots));
+        }
+        if server.password {
+            notes.push_str("  password");
+        }
+        let players = format!("{}/{}", server.players, server.max_players);
+        lines.push(format!(
+            "{:>3}  {:<24} {:<21} {:<12} {:>5} {:>7} {:>4}ms{}",
+            index + 1,
+            server.name,
+            server.address,
+            server.map,
+            server.level,
+            players,
+            server.ping.as_millis(),
+            notes
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_are_listed_with_their_ping() {
+        let answer = br#"{"name":"LAN party","map":"medium maze","level":2,"players":3,"bots":2,"max_players":10,"password":true,"address":"192.168.1.20:8081"}"#;
+        let mut server: LanServer = serde_json::from_slice(answer).unwrap();
+        server.ping = Duration::from_millis(4);
+        let table = server_table(&[server]);
+        let row = table.lines().nth(1).unwrap();
+        assert!(row.starts_with("  1  LAN party"));
+        assert!(row.contains("192.168.1.20:8081"));
+        assert!(row.contains("3/10"));
+        assert!(row.contains("4ms  2 bots  password"));
+    }
+}
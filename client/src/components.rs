@@ -1,5 +1,8 @@
 use bevy::prelude::*;
-use std::collections::HashMap;
+use crate::message::{ItemKind, Weapon};
+use std::collections::{HashMap, VecDeque};
+
+pub use crate::geometry::*;
 
 #[allow(dead_code)]
 #[derive(Component, Debug, Clone)]
@@ -8,12 +11,21 @@ pub struct Player {
     pub life: i64,
 }
 
-#[allow(dead_code)]
+// Durée d'un effet visuel du HUD (flash, indicateur, hitmarker)
+#[derive(Component)]
 pub struct FlashTimer(pub Timer);
 
 impl FlashTimer {
-    pub fn new(duration: f32) -> Self {
-        FlashTimer(Timer::from_seconds(duration, TimerMode::Once))
+    // Déjà écoulé: l'effet reste invisible jusqu'au prochain `reset`
+    pub fn idle(duration: f32) -> Self {
+        let mut timer = Timer::from_seconds(duration, TimerMode::Once);
+        timer.tick(timer.duration());
+        FlashTimer(timer)
+    }
+
+    // Opacité restante de l'effet, de 1 à 0
+    pub fn fade(&self) -> f32 {
+        1.0 - self.0.percent()
     }
 }
 
@@ -24,61 +36,305 @@ pub struct OtherPlayer {
     pub life: i64,
 }
 
-#[allow(dead_code)]
+// Balle d'un autre joueur, identifiée par (propriétaire, numéro de séquence)
 #[derive(Component)]
 pub struct OtherBall {
     pub id: usize,
+    pub seq: u64,
 }
 
 #[derive(Component)]
 pub struct Wall;
 
-#[derive(Resource)]
-pub struct Maze {
-    pub layout: Vec<Vec<u8>>,
-    pub cell_size: f32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinimapVisibility {
+    /// Every remote player is drawn.
+    All,
+    /// Only remote players the local player can see through the maze.
+    LineOfSight,
+    /// Only remote players on the local player's team.
+    Teammates,
+}
+
+impl MinimapVisibility {
+    pub fn shows(&self, maze: &Maze, viewer: (Vec3, usize), target: (Vec3, usize)) -> bool {
+        match self {
+            MinimapVisibility::All => true,
+            MinimapVisibility::LineOfSight => maze.line_of_sight(viewer.0, target.0),
+            MinimapVisibility::Teammates => team_of(viewer.1) == team_of(target.1),
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct MinimapSettings {
+    pub visibility: MinimapVisibility,
+    pub show_balls: bool,
+    /// Only reveal the cells the player has already seen.
+    pub fog_of_war: bool,
+}
+
+impl MinimapSettings {
+    pub fn for_level(level: i32) -> Self {
+        match level {
+            2 => MinimapSettings {
+                visibility: MinimapVisibility::LineOfSight,
+                show_balls: true,
+                fog_of_war: true,
+            },
+            3 => MinimapSettings {
+                visibility: MinimapVisibility::Teammates,
+                show_balls: false,
+                fog_of_war: true,
+            },
+            _ => MinimapSettings {
+                visibility: MinimapVisibility::All,
+                show_balls: true,
+                fog_of_war: false,
+            },
+        }
+    }
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        Self::for_level(1)
+    }
 }
 
 #[derive(Component)]
 pub struct Minimap;
 
 #[derive(Component)]
-pub struct MinimapTile;
+pub struct MinimapTile {
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Component)]
+pub struct MinimapPivot;
+
+#[derive(Component)]
+pub struct MinimapMap;
+
+#[derive(Component)]
+pub struct MinimapFacing;
+
+// Nombre de cellules visibles en largeur dans le mode centré sur le joueur
+pub const MINIMAP_ROTATING_CELLS: f32 = 9.0;
+pub const MINIMAP_MIN_ZOOM: f32 = 0.5;
+pub const MINIMAP_MAX_ZOOM: f32 = 3.0;
+
+#[derive(Resource, Debug, Clone)]
+pub struct MinimapView {
+    pub tile_size: f32,
+    pub zoom: f32,
+    /// Player-centered mode: the map turns so the player always faces up.
+    pub rotating: bool,
+}
+
+impl Default for MinimapView {
+    fn default() -> Self {
+        Self {
+            tile_size: 0.0,
+            zoom: 1.0,
+            rotating: false,
+        }
+    }
+}
+
+/// Angle of a marker pointing along `forward`, in UI space where the maze's +Z is down.
+pub fn minimap_facing_angle(forward: Vec3) -> f32 {
+    forward.x.atan2(-forward.z)
+}
 
 #[derive(Component)]
 pub struct MinimapPlayer;
 
+#[derive(Component)]
+pub struct MinimapOtherPlayer;
+
+#[derive(Component)]
+pub struct MinimapBall;
+
+#[derive(Component)]
+pub struct MinimapItem;
+
 #[derive(Resource, Clone)]
 pub struct MinimapEntities {
+    pub root: Option<Entity>,
+    pub pivot: Option<Entity>,
+    // Noeud contenant les cases et les marqueurs
+    pub map: Option<Entity>,
     pub tiles: Vec<Entity>,
     pub player: Option<Entity>,
-    // pub other_players: HashMap<u32, Entity>,
+    pub other_players: HashMap<usize, Entity>,
+    // Clé: entité de la balle (locale ou distante), valeur: son marqueur
+    pub balls: HashMap<Entity, Entity>,
+    // Clé: id de l'objet
+    pub items: HashMap<usize, Entity>,
 }
 
 impl Default for MinimapEntities {
     fn default() -> Self {
         Self {
+            root: None,
+            pivot: None,
+            map: None,
             tiles: Vec::new(),
             player: None,
-            // other_players: HashMap::new(),
+            other_players: HashMap::new(),
+            balls: HashMap::new(),
+            items: HashMap::new(),
         }
     }
 }
 
+// Distance maximale (en cases) à laquelle le joueur découvre le labyrinthe
+pub const FOG_SIGHT_RADIUS: i32 = 8;
+
+// Cases déjà vues par le joueur, conservées pendant toute sa vie
+#[derive(Resource, Debug, Default)]
+pub struct ExploredCells {
+    pub cells: Vec<Vec<bool>>,
+    pub last_cell: Option<(usize, usize)>,
+}
+
+impl ExploredCells {
+    pub fn new(layout: &[Vec<u8>]) -> Self {
+        Self {
+            cells: layout.iter().map(|row| vec![false; row.len()]).collect(),
+            last_cell: None,
+        }
+    }
+
+    pub fn is_explored(&self, row: usize, col: usize) -> bool {
+        self.cells
+            .get(row)
+            .and_then(|r| r.get(col))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+// Balle tirée par le joueur local; `seq` est unique parmi ses tirs
 #[derive(Component)]
-pub struct Ball;
+pub struct Ball {
+    pub seq: u64,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct BallSequence(pub u64);
+
+impl BallSequence {
+    pub fn next(&mut self) -> u64 {
+        let seq = self.0;
+        self.0 += 1;
+        seq
+    }
+}
+
+// Une balle distante sans nouvelles depuis ce délai (secondes) est supprimée
+pub const REMOTE_BALL_TIMEOUT: f32 = 0.5;
 
 #[derive(Component)]
 pub struct Velo(pub Vec3);
 
+// Durée de vie restante d'une balle
+#[derive(Component)]
+pub struct Lifetime(pub Timer);
+
+#[derive(Resource, Debug)]
+pub struct WeaponInventory {
+    pub weapons: Vec<Weapon>,
+    pub current: usize,
+    pub ammo: Vec<u32>,
+    // Secondes avant de pouvoir tirer à nouveau
+    pub cooldown: f32,
+    pub reload: Option<Timer>,
+}
+
+impl WeaponInventory {
+    pub fn new(weapons: Vec<Weapon>) -> Self {
+        let weapons = if weapons.is_empty() {
+            vec![Weapon::default()]
+        } else {
+            weapons
+        };
+        Self {
+            ammo: weapons.iter().map(|weapon| weapon.magazine_size).collect(),
+            weapons,
+            current: 0,
+            cooldown: 0.0,
+            reload: None,
+        }
+    }
+
+    pub fn weapon(&self) -> &Weapon {
+        &self.weapons[self.current]
+    }
+
+    pub fn can_fire(&self) -> bool {
+        self.reload.is_none() && self.cooldown <= 0.0 && self.ammo[self.current] > 0
+    }
+
+    pub fn start_reload(&mut self) -> bool {
+        if self.reload.is_some() || self.ammo[self.current] == self.weapon().magazine_size {
+            return false;
+        }
+        self.reload = Some(Timer::from_seconds(self.weapon().reload_time, TimerMode::Once));
+        true
+    }
+}
+
+impl Default for WeaponInventory {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+#[derive(Component)]
+pub struct AmmoText;
+
+pub const HIT_RADIUS: f32 = 1.0;
+pub const HITSCAN_RANGE: f32 = 100.0;
+
+// Traces des tirs instantanés, affichées quelques instants
+pub struct Tracer {
+    pub from: Vec3,
+    pub to: Vec3,
+    pub timer: Timer,
+}
+
+#[derive(Resource, Default)]
+pub struct Tracers(pub Vec<Tracer>);
+
+impl Tracers {
+    pub fn add(&mut self, from: Vec3, to: Vec3) {
+        self.0.push(Tracer {
+            from,
+            to,
+            timer: Timer::from_seconds(0.15, TimerMode::Once),
+        });
+    }
+}
+
+// Nombre de rebonds restants sur les murs avant que la balle disparaisse.
+// Chaque balle tirée en a un, à 0 pour les armes sans ricochet.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Ricochet {
+    pub bounces_left: u32,
+}
+
 #[derive(Resource, Default, Debug)]
 pub struct OtherPlayersMap(pub HashMap<usize, Entity>);
 
+// Clé: (id du propriétaire, numéro de séquence de la balle)
 #[derive(Resource, Default, Debug)]
-pub struct OtherBallMap(pub HashMap<usize, Entity>);
+pub struct OtherBallMap(pub HashMap<(usize, u64), Entity>);
 
 impl OtherBallMap {
-    pub fn remove(&mut self, key: usize) {
+    pub fn remove(&mut self, key: (usize, u64)) {
         self.0.remove(&key);
     }
 }
@@ -93,11 +349,91 @@ pub struct FpsRoot;
 #[derive(Component)]
 pub struct FpsText;
 
-pub fn get_val(value: f64) -> f64 {
-    if value < 49.0 {
-        50.0 + (fastrand::f64() * 10.0)
-    } else {
-        value
+#[derive(Component)]
+pub struct PerfRoot;
+
+#[derive(Component)]
+pub struct PerfText;
+
+#[derive(Component)]
+pub struct PerfGraphBar(pub usize);
+
+// Nombre de frames gardées pour les statistiques et le graphe
+pub const FRAME_HISTORY_LEN: usize = 120;
+// Hauteur du graphe en pixels et durée de frame (ms) correspondant au sommet
+pub const PERF_GRAPH_HEIGHT: f32 = 60.0;
+pub const PERF_GRAPH_MAX_MS: f32 = 50.0;
+
+#[derive(Resource, Default, Debug)]
+pub struct FrameTimeHistory(pub VecDeque<f32>);
+
+impl FrameTimeHistory {
+    /// Returns (min, avg, max) frame time in milliseconds.
+    pub fn summary(&self) -> Option<(f32, f32, f32)> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let min = self.0.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = self.0.iter().copied().fold(0.0, f32::max);
+        let avg = self.0.iter().sum::<f32>() / self.0.len() as f32;
+        Some((min, avg, max))
+    }
+}
+
+// Un ping non répondu après ce délai (en secondes) est compté comme perdu
+pub const PING_TIMEOUT: f64 = 2.0;
+
+#[derive(Resource, Debug)]
+pub struct NetworkStats {
+    pub ping_timer: Timer,
+    pub next_seq: u64,
+    // Numéro de séquence -> instant d'envoi (Time::elapsed_seconds_f64)
+    pub pending_pings: HashMap<u64, f64>,
+    pub rtt_ms: Option<f32>,
+    pub answered_pings: u32,
+    pub lost_pings: u32,
+    pub packets_in_per_sec: f32,
+    pub packets_out_per_sec: f32,
+    pub last_packets_in: u64,
+    pub last_packets_out: u64,
+}
+
+impl Default for NetworkStats {
+    fn default() -> Self {
+        Self {
+            ping_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            next_seq: 0,
+            pending_pings: HashMap::new(),
+            rtt_ms: None,
+            answered_pings: 0,
+            lost_pings: 0,
+            packets_in_per_sec: 0.0,
+            packets_out_per_sec: 0.0,
+            last_packets_in: 0,
+            last_packets_out: 0,
+        }
+    }
+}
+
+impl NetworkStats {
+    pub fn loss_percent(&self) -> f32 {
+        let total = self.answered_pings + self.lost_pings;
+        if total == 0 {
+            0.0
+        } else {
+            self.lost_pings as f32 * 100.0 / total as f32
+        }
+    }
+
+    pub fn record_pong(&mut self, seq: u64, now: f64) {
+        if let Some(sent_at) = self.pending_pings.remove(&seq) {
+            let rtt = ((now - sent_at) * 1000.0) as f32;
+            self.rtt_ms = Some(match self.rtt_ms {
+                Some(previous) => previous * 0.8 + rtt * 0.2,
+                None => rtt,
+            });
+            self.answered_pings += 1;
+        }
     }
 }
 
@@ -105,3 +441,133 @@ pub fn get_val(value: f64) -> f64 {
 pub struct PlayerState {
     pub is_dead: bool,
 }
+
+#[derive(Component, Debug)]
+pub struct Item {
+    pub id: usize,
+    pub kind: ItemKind,
+    // Position envoyée par le serveur, l'animation oscille autour
+    pub position: Vec3,
+}
+
+// Id de l'objet -> entité 3D
+#[derive(Resource, Default)]
+pub struct ItemMap(pub HashMap<usize, Entity>);
+
+pub const SPEED_BOOST_FACTOR: f32 = 1.5;
+
+pub fn item_color(kind: ItemKind) -> Color {
+    match kind {
+        ItemKind::HealthPack => Color::GREEN,
+        ItemKind::SpeedBoost => Color::CYAN,
+        ItemKind::Shield => Color::BLUE,
+        ItemKind::RapidFire => Color::ORANGE_RED,
+    }
+}
+
+/// Effets temporaires accordés par le serveur lors d'un ramassage.
+#[derive(Resource, Default)]
+pub struct ActiveEffects {
+    pub speed_boost: Option<Timer>,
+    pub shield: Option<Timer>,
+    pub rapid_fire: Option<Timer>,
+}
+
+impl ActiveEffects {
+    pub fn start(&mut self, kind: ItemKind, duration: f32) {
+        let timer = Some(Timer::from_seconds(duration, TimerMode::Once));
+        match kind {
+            ItemKind::SpeedBoost => self.speed_boost = timer,
+            ItemKind::Shield => self.shield = timer,
+            ItemKind::RapidFire => self.rapid_fire = timer,
+            ItemKind::HealthPack => {}
+        }
+    }
+
+    pub fn tick(&mut self, delta: std::time::Duration) {
+        for effect in [&mut self.speed_boost, &mut self.shield, &mut self.rapid_fire] {
+            if effect.as_mut().is_some_and(|timer| timer.tick(delta).finished()) {
+                *effect = None;
+            }
+        }
+    }
+}
+
+pub const MAX_LIFE: i64 = 10;
+pub const DAMAGE_FLASH_DURATION: f32 = 0.3;
+pub const DAMAGE_INDICATOR_DURATION: f32 = 1.0;
+pub const HIT_MARKER_DURATION: f32 = 0.25;
+pub const KILL_FEED_LEN: usize = 5;
+pub const KILL_FEED_DURATION: f32 = 6.0;
+
+#[derive(Component)]
+pub struct HealthBarFill;
+
+#[derive(Component)]
+pub struct HealthText;
+
+// Voile rouge plein écran quand on est touché
+#[derive(Component)]
+pub struct DamageFlash;
+
+// Pivot au centre de l'écran, tourné vers l'attaquant
+#[derive(Component)]
+pub struct DamageIndicator;
+
+#[derive(Component)]
+pub struct HitMarker;
+
+#[derive(Component)]
+pub struct KillFeedText;
+
+/// Dernières éliminations, les plus anciennes en premier.
+#[derive(Resource, Default)]
+pub struct KillFeed {
+    pub entries: VecDeque<(String, Timer)>,
+}
+
+impl KillFeed {
+    pub fn push(&mut self, line: String) {
+        if self.entries.len() == KILL_FEED_LEN {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((line, Timer::from_seconds(KILL_FEED_DURATION, TimerMode::Once)));
+    }
+}
+
+pub const CHAT_LOG_LEN: usize = 8;
+pub const MAX_CHAT_LEN: usize = 200;
+
+/// Saisie en cours: Entrée ouvre la boîte, Tab change de canal.
+#[derive(Resource, Default)]
+pub struct ChatState {
+    pub open: bool,
+    pub team: bool,
+    pub input: String,
+}
+
+pub fn chat_closed(chat: Res<ChatState>) -> bool {
+    !chat.open
+}
+
+/// Messages des joueurs et du serveur (connexions, départs, éliminations).
+#[derive(Resource, Default)]
+pub struct ChatLog {
+    pub lines: VecDeque<(String, Color)>,
+}
+
+impl ChatLog {
+    pub fn push(&mut self, line: String, color: Color) {
+        if self.lines.len() == CHAT_LOG_LEN {
+            self.lines.pop_front();
+        }
+        self.lines.push_back((line, color));
+    }
+}
+
+#[derive(Component)]
+pub struct ChatLogText;
+
+#[derive(Component)]
+pub struct ChatInputText;
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, RigidBody};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+
+use crate::components::*;
+use crate::message::*;
+
+// Saut en arrière ou en avant avec `,` et `.`
+const SEEK_STEP: f64 = 5.0;
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 8.0;
+const FREE_CAMERA_SPEED: f32 = 6.0;
+
+/// One line of a demo file: a message and when it was seen, in seconds since the recording started.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DemoRecord {
+    pub t: f64,
+    pub message: MessageRecieve,
+}
+
+struct Recorder {
+    file: LineWriter<File>,
+    started: Instant,
+}
+
+// Un seul enregistrement par client, partagé par la tâche réseau et `send_message`
+static RECORDER: OnceLock<Mutex<Recorder>> = OnceLock::new();
+
+/// Starts writing every received message, and our own movements, to `path` (`--record=<file>`).
+pub fn start_recording(path: &str) -> io::Result<()> {
+    let recorder = Recorder {
+        file: LineWriter::new(File::create(path)?),
+        started: Instant::now(),
+    };
+    if RECORDER.set(Mutex::new(recorder)).is_err() {
+        return Err(io::Error::other("already recording"));
+    }
+    println!("Recording demo to {}", path);
+    Ok(())
+}
+
+pub fn record(message: &MessageRecieve) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let Ok(mut recorder) = recorder.lock() else {
+        return;
+    };
+    let record = DemoRecord {
+        t: recorder.started.elapsed().as_secs_f64(),
+        message: message.clone(),
+    };
+    // Une ligne JSON par message; `LineWriter` vide le tampon à chaque ligne
+    if let Ok(line) = serde_json::to_string(&record) {
+        if let Err(e) = writeln!(recorder.file, "{}", line) {
+            eprintln!("Failed to write demo: {}", e);
+        }
+    }
+}
+
+// Le serveur ne nous renvoie pas nos propres mouvements: on les enregistre à l'envoi
+pub fn record_sent(message: &MessageSended) {
+    if !matches!(message.message_type, MessageType::Action) || RECORDER.get().is_none() {
+        return;
+    }
+    record(&MessageRecieve {
+        message_type: message.message_type.clone(),
+        player_name: message.player_name.clone(),
+        content: message.content.clone(),
+        id_player: message.id_player,
+        player_life: message.player_life,
+        level: message.level,
+        canconnect: true,
+    });
+}
+
+/// Parses a demo file, one JSON record per line. The first record must be the connection answer.
+pub fn parse_demo(text: &str) -> Result<Vec<DemoRecord>, String> {
+    let records = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str::<DemoRecord>(line).map_err(|e| format!("line {}: {}", number + 1, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match records.first() {
+        Some(first) if matches!(first.message.message_type, MessageType::ConnectSuccessfull) => Ok(records),
+        Some(_) => Err("the demo does not start with a connection answer".to_string()),
+        None => Err("the demo is empty".to_string()),
+    }
+}
+
+// Messages rejoués; ceux qui ne concernent que le client en direct sont ignorés
+fn replayable(message: &MessageRecieve) -> Vec<MessageRecieve> {
+    match message.message_type {
+        MessageType::ConnectSuccessfull
+        | MessageType::PlayerDeath
+        | MessageType::Pong
+        | MessageType::WeaponState
+        | MessageType::Kicked => vec![],
+        // Notre tir instantané n'était tracé que localement: on le retrace depuis la confirmation
+        MessageType::HitConfirm if message.content.get("end").is_some() => {
+            let mut trace = message.clone();
+            trace.message_type = MessageType::Shoot;
+            vec![message.clone(), trace]
+        }
+        _ => vec![message.clone()],
+    }
+}
+
+/// Lecture d'une démo (`--replay=<fichier>`), sans serveur.
+#[derive(Resource)]
+pub struct Replay {
+    records: Vec<DemoRecord>,
+    next: usize,
+    pub clock: f64,
+    pub speed: f64,
+    pub paused: bool,
+    // Disponibilité des objets au début de la démo, rétablie quand on revient en arrière
+    initial_items: HashMap<usize, bool>,
+    sender: Sender<MessageRecieve>,
+}
+
+impl Replay {
+    pub fn new(records: Vec<DemoRecord>, initial_items: HashMap<usize, bool>, sender: Sender<MessageRecieve>) -> Self {
+        Replay {
+            // La réponse de connexion sert à `setup`, pas à la lecture
+            next: 1,
+            records,
+            clock: 0.0,
+            speed: 1.0,
+            paused: false,
+            initial_items,
+            sender,
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.records.last().map(|record| record.t).unwrap_or(0.0)
+    }
+
+    // Messages dont l'heure est passée, dans l'ordre
+    fn due(&mut self) -> Vec<MessageRecieve> {
+        let mut due = Vec::new();
+        while let Some(record) = self.records.get(self.next) {
+            if record.t > self.clock {
+                break;
+            }
+            due.extend(replayable(&record.message));
+            self.next += 1;
+        }
+        due
+    }
+
+    /// Moves the clock to `t`. Returns true when going backwards: the world must then be
+    /// cleared and rebuilt from the start of the demo.
+    fn seek(&mut self, t: f64) -> bool {
+        let t = t.clamp(0.0, self.duration());
+        let rewind = t < self.clock;
+        self.clock = t;
+        if rewind {
+            self.next = 1;
+        }
+        rewind
+    }
+}
+
+#[derive(Component)]
+pub struct ReplayText;
+
+// Fait avancer l'horloge de la démo et envoie les messages dus à `handle_server_messages`
+pub fn replay_playback(time: Res<Time>, mut replay: ResMut<Replay>) {
+    if !replay.paused {
+        let clock = replay.clock + time.delta_seconds_f64() * replay.speed;
+        replay.clock = clock.min(replay.duration());
+    }
+    for message in replay.due() {
+        if let Err(e) = replay.sender.try_send(message) {
+            eprintln!("Failed to replay message: {}", e);
+        }
+    }
+}
+
+// Espace: pause, `,`/`.`: reculer/avancer, `[`/`]`: vitesse, Retour arrière: recommencer
+pub fn replay_controls(
+    keys: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    mut other_players_map: ResMut<OtherPlayersMap>,
+    mut other_ball_map: ResMut<OtherBallMap>,
+    remote_query: Query<Entity, Or<(With<OtherPlayer>, With<OtherBall>)>>,
+    mut item_query: Query<(&Item, &mut Visibility)>,
+    mut tracers: ResMut<Tracers>,
+    mut kill_feed: ResMut<KillFeed>,
+    mut chat_log: ResMut<ChatLog>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        replay.paused = !replay.paused;
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        replay.speed = (replay.speed * 2.0).min(MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        replay.speed = (replay.speed / 2.0).max(MIN_SPEED);
+    }
+
+    let target = if keys.just_pressed(KeyCode::Back) {
+        Some(0.0)
+    } else if keys.just_pressed(KeyCode::Comma) {
+        Some(replay.clock - SEEK_STEP)
+    } else if keys.just_pressed(KeyCode::Period) {
+        Some(replay.clock + SEEK_STEP)
+    } else {
+        None
+    };
+    let Some(target) = target else {
+        return;
+    };
+    if !replay.seek(target) {
+        // En avant, les messages sautés sont rejoués d'un coup à la prochaine frame
+        return;
+    }
+
+    // En arrière, on repart d'un monde vide et on rejoue tout jusqu'à la cible
+    for entity in remote_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    other_players_map.0.clear();
+    other_ball_map.0.clear();
+    for (item, mut visibility) in item_query.iter_mut() {
+        *visibility = if replay.initial_items.get(&item.id).copied().unwrap_or(true) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    tracers.0.clear();
+    kill_feed.entries.clear();
+    chat_log.lines.clear();
+}
+
+// Caméra libre: flèches pour avancer et tourner, Page haut/bas pour monter, Maj pour aller vite
+pub fn free_camera(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, Option<&RigidBody>), With<Player>>,
+) {
+    let Ok((entity, mut transform, body)) = query.get_single_mut() else {
+        return;
+    };
+    // La caméra traverse les murs: plus de physique pour le joueur
+    if body.is_some() {
+        commands.entity(entity).remove::<(RigidBody, Collider)>();
+    }
+
+    let forward = Vec3::new(transform.forward().x, 0.0, transform.forward().z).normalize_or_zero();
+    let mut movement = Vec3::ZERO;
+    if keys.pressed(KeyCode::Up) { movement += forward; }
+    if keys.pressed(KeyCode::Down) { movement -= forward; }
+    if keys.pressed(KeyCode::PageUp) { movement += Vec3::Y; }
+    if keys.pressed(KeyCode::PageDown) { movement -= Vec3::Y; }
+    let mut rotation = 0.0;
+    if keys.pressed(KeyCode::Left) { rotation += 1.3; }
+    if keys.pressed(KeyCode::Right) { rotation -= 1.3; }
+
+    let speed = if keys.pressed(KeyCode::ShiftLeft) {
+        FREE_CAMERA_SPEED * 3.0
+    } else {
+        FREE_CAMERA_SPEED
+    };
+    transform.translation += movement.normalize_or_zero() * speed * time.delta_seconds();
+    transform.rotate_y(rotation * time.delta_seconds());
+}
+
+pub fn setup_replay_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Percent(40.0),
+            ..default()
+        }),
+        ReplayText,
+    ));
+}
+
+pub fn replay_text_update_system(replay: Res<Replay>, mut query: Query<&mut Text, With<ReplayText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "REPLAY {} / {}  x{}{}",
+            format_clock(replay.clock),
+            format_clock(replay.duration()),
+            replay.speed,
+            if replay.paused { "  (paused)" } else { "" },
+        );
+    }
+}
+
+fn format_clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn line(t: f64, message_type: &str, content: Value) -> String {
+        json!({
+            "t": t,
+            "message": {
+                "message_type": message_type,
+                "player_name": "alice",
+                "content": content,
+                "id_player": 1,
+                "player_life": 5,
+                "level": 1,
+                "canconnect": true,
+            },
+        })
+        .to_string()
+    }
+
+    fn demo() -> Vec<DemoRecord> {
+        let text = [
+            line(0.0, "ConnectSuccessfull", Value::Null),
+            line(1.0, "Action", json!({ "movement": [1.0, 1.0, 1.0] })),
+            line(2.0, "Pong", json!({ "seq": 1 })),
+            line(3.0, "HitConfirm", json!({ "origin": [0.0, 1.0, 0.0], "end": [4.0, 1.0, 0.0] })),
+            line(4.0, "Action", json!({ "movement": [2.0, 1.0, 1.0] })),
+        ]
+        .join("\n");
+        parse_demo(&text).unwrap()
+    }
+
+    #[test]
+    fn demos_must_start_with_the_connection_answer() {
+        assert_eq!(demo().len(), 5);
+        assert_eq!(parse_demo("").unwrap_err(), "the demo is empty");
+        assert!(parse_demo(&line(0.0, "Action", Value::Null)).is_err());
+        assert!(parse_demo("{").unwrap_err().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn playback_skips_live_only_messages_and_retraces_our_shots() {
+        let (sender, _receiver) = tokio::sync::mpsc::channel(8);
+        let mut replay = Replay::new(demo(), HashMap::new(), sender);
+        assert!(replay.due().is_empty());
+
+        replay.clock = 3.0;
+        let types: Vec<String> = replay.due().iter().map(|m| format!("{:?}", m.message_type)).collect();
+        assert_eq!(types, ["Action", "HitConfirm", "Shoot"]);
+        assert!(replay.due().is_empty());
+    }
+
+    #[test]
+    fn seeking_backwards_replays_from_the_start() {
+        let (sender, _receiver) = tokio::sync::mpsc::channel(8);
+        let mut replay = Replay::new(demo(), HashMap::new(), sender);
+        assert!(!replay.seek(10.0));
+        assert_eq!(replay.clock, 4.0);
+        assert_eq!(replay.due().len(), 4);
+
+        assert!(replay.seek(1.5));
+        assert_eq!(replay.due().len(), 1);
+    }
+}
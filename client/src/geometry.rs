@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+// Géométrie que le serveur calcule aussi (server/src/maze.rs, hitscan.rs, weapon.rs et chat.rs).
+// Ce fichier n'utilise que bevy, rand et serde_json: server/tests/client_parity.rs l'inclut
+// pour vérifier que les deux copies donnent les mêmes résultats.
+
+#[derive(Resource)]
+pub struct Maze {
+    pub layout: Vec<Vec<u8>>,
+    pub cell_size: f32,
+}
+
+// Point où un rayon touche un mur du labyrinthe
+#[derive(Debug, Clone, Copy)]
+pub struct WallHit {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Maze {
+    /// Returns the (row, column) of the cell containing `position`, if it is inside the maze.
+    pub fn cell_at(&self, position: Vec3) -> Option<(usize, usize)> {
+        let col = (position.x / self.cell_size + 0.5).floor();
+        let row = (position.z / self.cell_size + 0.5).floor();
+        if col < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (row, col) = (row as usize, col as usize);
+        if row < self.layout.len() && col < self.layout[row].len() {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+
+    /// Cells outside the layout count as walls so rays never leave the maze.
+    pub fn is_wall(&self, row: i32, col: i32) -> bool {
+        if row < 0 || col < 0 {
+            return true;
+        }
+        self.layout
+            .get(row as usize)
+            .and_then(|r| r.get(col as usize))
+            .is_none_or(|&cell| cell == 1)
+    }
+
+    /// Walks the grid from `from` to `to` on the XZ plane and returns the first wall crossed.
+    pub fn raycast(&self, from: Vec3, to: Vec3) -> Option<WallHit> {
+        let start = Vec2::new(from.x, from.z) / self.cell_size + Vec2::splat(0.5);
+        let end = Vec2::new(to.x, to.z) / self.cell_size + Vec2::splat(0.5);
+        let delta = end - start;
+        let length = (to - from).length();
+
+        let mut cell = IVec2::new(start.x.floor() as i32, start.y.floor() as i32);
+        let end_cell = IVec2::new(end.x.floor() as i32, end.y.floor() as i32);
+        let step = IVec2::new(
+            if delta.x > 0.0 { 1 } else if delta.x < 0.0 { -1 } else { 0 },
+            if delta.y > 0.0 { 1 } else if delta.y < 0.0 { -1 } else { 0 },
+        );
+        let t_delta = Vec2::new(
+            if step.x != 0 { 1.0 / delta.x.abs() } else { f32::INFINITY },
+            if step.y != 0 { 1.0 / delta.y.abs() } else { f32::INFINITY },
+        );
+        let mut t_max = Vec2::new(
+            match step.x {
+                1 => (cell.x as f32 + 1.0 - start.x) / delta.x,
+                -1 => (start.x - cell.x as f32) / -delta.x,
+                _ => f32::INFINITY,
+            },
+            match step.y {
+                1 => (cell.y as f32 + 1.0 - start.y) / delta.y,
+                -1 => (start.y - cell.y as f32) / -delta.y,
+                _ => f32::INFINITY,
+            },
+        );
+
+        let mut t = 0.0;
+        let mut normal = Vec3::ZERO;
+        loop {
+            if self.is_wall(cell.y, cell.x) {
+                return Some(WallHit {
+                    point: from.lerp(to, t),
+                    normal,
+                    distance: length * t,
+                });
+            }
+            if cell == end_cell {
+                return None;
+            }
+            if t_max.x < t_max.y {
+                t = t_max.x;
+                cell.x += step.x;
+                t_max.x += t_delta.x;
+                normal = Vec3::new(-step.x as f32, 0.0, 0.0);
+            } else {
+                t = t_max.y;
+                cell.y += step.y;
+                t_max.y += t_delta.y;
+                normal = Vec3::new(0.0, 0.0, -step.y as f32);
+            }
+            if t > 1.0 {
+                return None;
+            }
+        }
+    }
+
+    pub fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        self.raycast(from, to).is_none()
+    }
+}
+
+/// Distance along a normalized ray to the first intersection with a sphere.
+pub fn ray_sphere(origin: Vec3, direction: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+    let offset = origin - center;
+    let b = offset.dot(direction);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    if -b - sqrt >= 0.0 {
+        Some(-b - sqrt)
+    } else if -b + sqrt >= 0.0 {
+        // L'origine est dans la sphère
+        Some(0.0)
+    } else {
+        None
+    }
+}
+
+/// Team of a player: ids alternate between two teams in connection order.
+/// The server gives no team; it uses the same `id % 2` rule (`team_of` in server/src/chat.rs)
+/// for team chat, so both sides agree as long as this convention is kept.
+pub fn team_of(id: usize) -> usize {
+    id % 2
+}
+
+pub fn parse_vec3(value: &serde_json::Value) -> Option<Vec3> {
+    let array = value.as_array()?;
+    if array.len() != 3 {
+        return None;
+    }
+    Some(Vec3::new(
+        array[0].as_f64()? as f32,
+        array[1].as_f64()? as f32,
+        array[2].as_f64()? as f32,
+    ))
+}
+
+/// Deviates `direction` by a random angle up to `spread` radians, in yaw and pitch.
+pub fn apply_spread(direction: Vec3, spread: f32, rng: &mut impl Rng) -> Vec3 {
+    if spread <= 0.0 {
+        return direction;
+    }
+    let yaw = rng.gen_range(-spread..=spread);
+    let pitch = rng.gen_range(-spread..=spread);
+    let right = direction.cross(Vec3::Y).normalize_or_zero();
+    (Quat::from_rotation_y(yaw) * Quat::from_axis_angle(right, pitch) * direction).normalize()
+}
@@ -0,0 +1,300 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::components::*;
+use crate::labyrinte::{parse_item, SpawnInfo};
+use crate::LastSentTransform;
+
+/// Marque le mode sans rendu (`--headless`): pas de fenêtre, pas de scène à charger.
+#[derive(Resource, Debug, Default)]
+pub struct Headless;
+
+// Script par défaut: avancer, tourner et tirer en boucle
+pub const DEFAULT_SCRIPT: &str = "\
+# secondes  touches
+1.5 up
+0.1 space
+0.6 left
+1.0 up
+0.1 space
+0.6 right
+0.5
+";
+
+/// Keys held for `seconds`; no key means idle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputStep {
+    pub seconds: f32,
+    pub keys: Vec<KeyCode>,
+}
+
+/// Entrées injectées à la place du clavier, une étape après l'autre.
+#[derive(Resource, Debug)]
+pub struct InputScript {
+    steps: Vec<InputStep>,
+    current: Option<usize>,
+    timer: Timer,
+    looping: bool,
+}
+
+impl InputScript {
+    pub fn new(steps: Vec<InputStep>, looping: bool) -> Self {
+        InputScript {
+            steps,
+            current: None,
+            timer: Timer::from_seconds(0.0, TimerMode::Once),
+            looping,
+        }
+    }
+
+    fn next_step(&self) -> Option<usize> {
+        match self.current {
+            None if !self.steps.is_empty() => Some(0),
+            Some(index) if index + 1 < self.steps.len() => Some(index + 1),
+            Some(_) if self.looping => Some(0),
+            _ => None,
+        }
+    }
+}
+
+// Nom de touche utilisé dans les scripts
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    let key = match name.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "space" => KeyCode::Space,
+        "r" => KeyCode::R,
+        "m" => KeyCode::M,
+        "1" => KeyCode::Key1,
+        "2" => KeyCode::Key2,
+        "3" => KeyCode::Key3,
+        "4" => KeyCode::Key4,
+        "5" => KeyCode::Key5,
+        "6" => KeyCode::Key6,
+        "7" => KeyCode::Key7,
+        "8" => KeyCode::Key8,
+        "9" => KeyCode::Key9,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Parses one step per line: a duration in seconds followed by the held keys,
+/// e.g. `1.5 up left`. Empty lines and `#` comments are skipped.
+pub fn parse_script(text: &str) -> Result<Vec<InputStep>, String> {
+    let mut steps = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let seconds = words
+            .next()
+            .and_then(|seconds| seconds.parse::<f32>().ok())
+            .filter(|seconds| *seconds > 0.0)
+            .ok_or_else(|| format!("line {}: expected a duration in seconds", number + 1))?;
+        let keys = words
+            .map(|name| key_from_name(name).ok_or_else(|| format!("line {}: unknown key `{}`", number + 1, name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        steps.push(InputStep { seconds, keys });
+    }
+    Ok(steps)
+}
+
+// Remplace le clavier: appuie sur les touches de l'étape courante
+pub fn play_input_script(time: Res<Time>, mut script: ResMut<InputScript>, mut keys: ResMut<Input<KeyCode>>) {
+    // `just_pressed` ne dure qu'une frame, comme avec l'InputPlugin
+    keys.clear();
+
+    let step_over = if script.current.is_some() {
+        script.timer.tick(time.delta()).finished()
+    } else {
+        true
+    };
+    if !step_over {
+        return;
+    }
+    keys.release_all();
+    let Some(index) = script.next_step() else {
+        return;
+    };
+    let step = script.steps[index].clone();
+    script.current = Some(index);
+    script.timer = Timer::from_seconds(step.seconds, TimerMode::Once);
+    for key in step.keys {
+        keys.press(key);
+    }
+}
+
+/// Quitte après la durée donnée par `--duration=<secondes>`.
+#[derive(Resource, Debug)]
+pub struct ExitAfter(pub Timer);
+
+pub fn exit_after(time: Res<Time>, mut exit_after: ResMut<ExitAfter>, mut exit: EventWriter<bevy::app::AppExit>) {
+    if exit_after.0.tick(time.delta()).just_finished() {
+        println!("Headless run finished");
+        exit.send(bevy::app::AppExit);
+    }
+}
+
+// Sans Rapier, la vitesse du joueur est appliquée ici; les murs bloquent le déplacement
+pub fn apply_velocity(
+    time: Res<Time>,
+    maze: Res<Maze>,
+    mut query: Query<(&Velocity, &mut Transform), With<Player>>,
+) {
+    for (velocity, mut transform) in query.iter_mut() {
+        let next = transform.translation + velocity.linvel * time.delta_seconds();
+        if maze.raycast(transform.translation, next).is_none() {
+            transform.translation = next;
+        }
+    }
+}
+
+// Même monde que `setup`, sans maillage, texture, interface ni caméra
+pub fn setup_headless(mut commands: Commands, info: Res<SpawnInfo>) {
+    commands.spawn((
+        TransformBundle::from_transform(Transform::from_translation(info.start_position)),
+        Velocity::default(),
+        Player {
+            id: info.id_player,
+            life: info.player_life,
+        },
+        LastSentTransform {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+        },
+    ));
+
+    commands.insert_resource(Maze {
+        layout: info.maze_layout.clone(),
+        cell_size: 2.0,
+    });
+    commands.insert_resource(WeaponInventory::new(info.weapons.clone()));
+
+    let mut item_map = ItemMap::default();
+    for (id, kind, position, available) in info.items.iter().filter_map(parse_item) {
+        let entity = commands
+            .spawn((
+                TransformBundle::from_transform(Transform::from_translation(position)),
+                if available {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                Item { id, kind, position },
+            ))
+            .id();
+        item_map.0.insert(id, entity);
+    }
+    commands.insert_resource(item_map);
+    println!("Headless client ready as player {}", info.id_player);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::level_layout;
+    use crate::message::UdpSocketResource;
+    use crate::netsim::Transport;
+    use bevy::time::TimeUpdateStrategy;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_steps_comments_and_idle_lines() {
+        let steps = parse_script("# avancer\n1.5 up left\n\n0.5\n0.1 SPACE # tir").unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                InputStep { seconds: 1.5, keys: vec![KeyCode::Up, KeyCode::Left] },
+                InputStep { seconds: 0.5, keys: vec![] },
+                InputStep { seconds: 0.1, keys: vec![KeyCode::Space] },
+            ]
+        );
+        assert!(parse_script(DEFAULT_SCRIPT).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_durations_and_unknown_keys() {
+        assert_eq!(parse_script("up").unwrap_err(), "line 1: expected a duration in seconds");
+        assert_eq!(parse_script("1 up\n-1 up").unwrap_err(), "line 2: expected a duration in seconds");
+        assert_eq!(parse_script("1 jump").unwrap_err(), "line 1: unknown key `jump`");
+    }
+
+    #[test]
+    fn script_steps_advance_and_loop() {
+        let steps = parse_script("1 up\n1 space").unwrap();
+        let mut script = InputScript::new(steps.clone(), true);
+        assert_eq!(script.next_step(), Some(0));
+        script.current = Some(1);
+        assert_eq!(script.next_step(), Some(0));
+
+        let mut once = InputScript::new(steps, false);
+        once.current = Some(1);
+        assert_eq!(once.next_step(), None);
+    }
+
+    #[test]
+    fn scripted_player_walks_forward_until_a_wall() {
+        // Faux serveur: une socket locale qui reçoit les messages du client sans répondre
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let client = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(server.local_addr().unwrap()).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let socket = {
+            let _guard = runtime.enter();
+            tokio::net::UdpSocket::from_std(client).unwrap()
+        };
+        let (_tx, rx) = tokio::sync::mpsc::channel(32);
+        let udp_socket_resource = UdpSocketResource {
+            socket: Arc::new(Transport::new(Arc::new(socket))),
+            username: "headless".to_string(),
+            id: 0,
+        };
+
+        // Colonne 1 du niveau 1: couloir des lignes 1 à 4, mur à la ligne 0 devant le joueur (-Z)
+        let info = SpawnInfo {
+            id_player: 0,
+            player_life: 10,
+            start_position: Vec3::new(2.0, 1.0, 8.0),
+            level: 1,
+            weapons: Vec::new(),
+            items: Vec::new(),
+            maze_layout: level_layout(1),
+        };
+        let mut app = App::new();
+        crate::add_world_systems(&mut app, rx, udp_socket_resource);
+        crate::add_game_systems(&mut app);
+        crate::add_headless_systems(&mut app, info, InputScript::new(parse_script("30 up").unwrap(), false));
+        // Pas de `run`: le test fait avancer le temps lui-même, 50 ms par frame
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(50)));
+        app.finish();
+        app.cleanup();
+
+        let mut position = || {
+            app.update();
+            let mut query = app.world.query_filtered::<&Transform, With<Player>>();
+            query.single(&app.world).translation
+        };
+        let start = position();
+        for _ in 0..10 {
+            position();
+        }
+        let moved = position();
+        assert!(moved.z < start.z - 0.5, "the player did not move: {} -> {}", start, moved);
+        assert!((moved.x - start.x).abs() < 1e-4);
+
+        for _ in 0..100 {
+            position();
+        }
+        let stopped = position();
+        assert_eq!(position(), stopped);
+        // Arrêté devant la face du mur (z = 1), sans la traverser
+        assert!(stopped.z >= 1.0 && stopped.z < 1.5, "the player stopped at {}", stopped);
+    }
+}
@@ -1,18 +1,20 @@
 use crate::{
     components::*,
-    message::MessageType,
+    headless::Headless,
+    message::{ItemKind, MessageRecieve, MessageType, Weapon},
     LastSentTransform, MessageReceiver, ServerMessageReceived,
 };
 use bevy::{
     diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
 };
 use bevy_rapier3d::prelude::*;
 
+// Les tailles sont calculées à chaque frame par `update_minimap_view`,
+// à partir de la fenêtre et des dimensions du labyrinthe.
 pub fn create_minimap(
     commands: &mut Commands,
-    minimap_size: f32,
-    tile_size: f32,
     maze_layout: &Vec<Vec<u8>>,
     mut minimap_entities: ResMut<MinimapEntities>,
     player_entity: Entity,
@@ -21,11 +23,10 @@ pub fn create_minimap(
         .spawn((
             NodeBundle {
                 style: Style {
-                    width: Val::Px(minimap_size),
-                    height: Val::Px(minimap_size),
                     position_type: PositionType::Absolute,
                     left: Val::Px(10.0),
                     bottom: Val::Px(10.0),
+                    overflow: Overflow::clip(),
                     ..default()
                 },
                 background_color: Color::rgba(0.1, 0.1, 0.1, 0.8).into(),
@@ -33,6 +34,35 @@ pub fn create_minimap(
             },
             Minimap,
         ))
+        .id();
+
+    // Le pivot porte la rotation du mode centré sur le joueur
+    let pivot_entity = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(0.0),
+                    height: Val::Px(0.0),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            MinimapPivot,
+        ))
+        .id();
+
+    let map_entity = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            MinimapMap,
+        ))
         .with_children(|parent| {
             for (i, row) in maze_layout.iter().enumerate() {
                 for (j, &cell) in row.iter().enumerate() {
@@ -40,11 +70,7 @@ pub fn create_minimap(
                         .spawn((
                             NodeBundle {
                                 style: Style {
-                                    width: Val::Px(tile_size),
-                                    height: Val::Px(tile_size),
                                     position_type: PositionType::Absolute,
-                                    left: Val::Px(j as f32 * tile_size),
-                                    top: Val::Px(i as f32 * tile_size),
                                     ..default()
                                 },
                                 background_color: if cell == 1 {
@@ -55,7 +81,7 @@ pub fn create_minimap(
                                 .into(),
                                 ..default()
                             },
-                            MinimapTile,
+                            MinimapTile { row: i, col: j },
                         ))
                         .id();
                     minimap_entities.tiles.push(entity);
@@ -67,8 +93,6 @@ pub fn create_minimap(
                 .spawn((
                     NodeBundle {
                         style: Style {
-                            width: Val::Px(tile_size),
-                            height: Val::Px(tile_size),
                             position_type: PositionType::Absolute,
                             ..default()
                         },
@@ -78,72 +102,438 @@ pub fn create_minimap(
                     MinimapPlayer,
                     MinimapPlayerMarker(player_entity),
                 ))
+                .with_children(|marker| {
+                    // Indique la direction du regard
+                    marker.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                        MinimapFacing,
+                    ));
+                })
                 .id();
             minimap_entities.player = Some(player_marker);
         })
         .id();
+
+    commands.entity(pivot_entity).add_child(map_entity);
+    commands.entity(minimap_entity).add_child(pivot_entity);
+
+    minimap_entities.root = Some(minimap_entity);
+    minimap_entities.pivot = Some(pivot_entity);
+    minimap_entities.map = Some(map_entity);
     minimap_entities.tiles.push(minimap_entity);
 }
 
-pub fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    asset_server: Res<AssetServer>,
-    minimap_entities: ResMut<MinimapEntities>,
-    mut message_receiver: ResMut<MessageReceiver>,
-) {
-    let mut maze_layout = vec![
-        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
-        vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 0, 1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
-        vec![1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 0, 1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 0, 1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
-        vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
-    ];
-    let maze_layout_v1 = vec![
-        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
-        vec![1, 0, 0, 0, 1, 1, 1, 0, 1, 0, 0, 0, 0, 1],
-        vec![1, 1, 1, 0, 1, 0, 0, 0, 1, 0, 1, 1, 0, 1],
-        vec![1, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 1],
-        vec![1, 0, 1, 1, 1, 0, 1, 0, 0, 0, 0, 1, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1],
-        vec![1, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 1, 0, 1],
-        vec![1, 0, 0, 1, 0, 0, 0, 0, 1, 0, 1, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
-        vec![1, 0, 0, 1, 0, 1, 0, 0, 1, 1, 0, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 1, 1, 0, 1, 0, 1, 0, 1],
-        vec![1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 1, 0, 1, 1, 1, 1, 0, 1],
-        vec![1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 0, 0, 0, 1],
-        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
-    ];
-    let maze_layout_v2 = vec![
-        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
-        vec![1, 0, 0, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 1],
-        vec![1, 1, 0, 1, 1, 1, 0, 1, 1, 0, 0, 1, 0, 1],
-        vec![1, 0, 0, 1, 0, 0, 0, 0, 1, 1, 0, 0, 0, 1],
-        vec![1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
-        vec![1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1],
-        vec![1, 1, 1, 0, 1, 0, 1, 0, 0, 0, 1, 0, 0, 1],
-        vec![1, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
-        vec![1, 0, 0, 1, 0, 0, 0, 0, 1, 1, 0, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
-        vec![1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 1, 0, 1, 0, 1, 0, 0, 1],
-        vec![1, 0, 1, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 1],
-        vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+// Nombre de cases par côté d'un morceau de mesh de murs
+const WALL_CHUNK_SIZE: usize = 8;
+const WALL_HEIGHT: f32 = 2.0;
+
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    // `u` et `v` sont les demi-côtés de la face, avec u × v = normale
+    fn add_face(&mut self, center: Vec3, u: Vec3, v: Vec3) {
+        let normal = u.cross(v).normalize();
+        let base = self.positions.len() as u32;
+        for (corner, uv) in [
+            (center - u - v, [0.0, 1.0]),
+            (center + u - v, [1.0, 1.0]),
+            (center + u + v, [1.0, 0.0]),
+            (center - u + v, [0.0, 0.0]),
+        ] {
+            self.positions.push(corner.to_array());
+            self.normals.push(normal.to_array());
+            self.uvs.push(uv);
+        }
+        self.indices
+            .extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
+
+// Ajoute les faces visibles d'un mur: on saute celles collées à un autre mur
+fn add_wall_faces(builder: &mut MeshBuilder, maze: &Maze, row: usize, col: usize) {
+    let half = maze.cell_size / 2.0;
+    let half_height = WALL_HEIGHT / 2.0;
+    let center = Vec3::new(col as f32 * maze.cell_size, half_height, row as f32 * maze.cell_size);
+    let (row, col) = (row as i32, col as i32);
+
+    let sides = [
+        (Vec3::X, Vec3::NEG_Z, row, col + 1),
+        (Vec3::NEG_X, Vec3::Z, row, col - 1),
+        (Vec3::Z, Vec3::X, row + 1, col),
+        (Vec3::NEG_Z, Vec3::NEG_X, row - 1, col),
     ];
+    for (normal, u, neighbour_row, neighbour_col) in sides {
+        // Les cases hors du labyrinthe comptent comme des murs
+        if maze.is_wall(neighbour_row, neighbour_col) {
+            continue;
+        }
+        builder.add_face(center + normal * half, u * half, Vec3::Y * half_height);
+    }
+    builder.add_face(center + Vec3::Y * half_height, Vec3::X * half, Vec3::NEG_Z * half);
+}
+
+// Regroupe les murs en rectangles (ligne, colonne, hauteur, largeur) pour limiter les colliders
+fn merge_wall_rects(maze: &Maze) -> Vec<(usize, usize, usize, usize)> {
+    let layout = &maze.layout;
+    let is_wall = |row: usize, col: usize| layout.get(row).and_then(|r| r.get(col)) == Some(&1);
+    let mut used: Vec<Vec<bool>> = layout.iter().map(|row| vec![false; row.len()]).collect();
+    let mut rects = Vec::new();
+
+    for row in 0..layout.len() {
+        for col in 0..layout[row].len() {
+            if !is_wall(row, col) || used[row][col] {
+                continue;
+            }
+            let mut width = 1;
+            while is_wall(row, col + width) && !used[row][col + width] {
+                width += 1;
+            }
+            let mut height = 1;
+            'grow: while row + height < layout.len() {
+                for c in col..col + width {
+                    if !is_wall(row + height, c) || used[row + height][c] {
+                        break 'grow;
+                    }
+                }
+                height += 1;
+            }
+            for r in row..row + height {
+                for c in col..col + width {
+                    used[r][c] = true;
+                }
+            }
+            rects.push((row, col, height, width));
+        }
+    }
+    rects
+}
+
+// Construit le sol, les murs par morceaux et un collider composé.
+// Retourne le nombre d'entités créées.
+fn spawn_maze_geometry(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    maze: &Maze,
+    wall_material: Handle<StandardMaterial>,
+) -> usize {
+    let rows = maze.layout.len();
+    let cols = maze.layout.iter().map(|row| row.len()).max().unwrap_or(0);
+    let cell_size = maze.cell_size;
+    let mut spawned = 0;
+
+    // Un seul sol pour tout le labyrinthe
+    let width = cols as f32 * cell_size;
+    let depth = rows as f32 * cell_size;
+    let floor_center = Vec3::new(
+        (cols as f32 - 1.0) * cell_size / 2.0,
+        0.0,
+        (rows as f32 - 1.0) * cell_size / 2.0,
+    );
+    let mut floor = MeshBuilder::default();
+    floor.add_face(Vec3::ZERO, Vec3::X * width / 2.0, Vec3::NEG_Z * depth / 2.0);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(floor.build()),
+            material: materials.add(Color::rgb(0., 0., 0.).into()),
+            transform: Transform::from_translation(floor_center),
+            ..default()
+        },
+        Collider::cuboid(width / 2.0, 0.1, depth / 2.0),
+    ));
+    spawned += 1;
+
+    // Murs: un mesh par morceau de WALL_CHUNK_SIZE x WALL_CHUNK_SIZE cases
+    for chunk_row in (0..rows).step_by(WALL_CHUNK_SIZE) {
+        for chunk_col in (0..cols).step_by(WALL_CHUNK_SIZE) {
+            let mut builder = MeshBuilder::default();
+            for row in chunk_row..(chunk_row + WALL_CHUNK_SIZE).min(rows) {
+                for col in chunk_col..(chunk_col + WALL_CHUNK_SIZE).min(maze.layout[row].len()) {
+                    if maze.layout[row][col] == 1 {
+                        add_wall_faces(&mut builder, maze, row, col);
+                    }
+                }
+            }
+            if builder.is_empty() {
+                continue;
+            }
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(builder.build()),
+                    material: wall_material.clone(),
+                    ..default()
+                },
+                Wall,
+            ));
+            spawned += 1;
+        }
+    }
+
+    let shapes: Vec<(Vect, Rot, Collider)> = merge_wall_rects(maze)
+        .into_iter()
+        .map(|(row, col, height, width)| {
+            let center = Vec3::new(
+                (col as f32 + (width as f32 - 1.0) / 2.0) * cell_size,
+                WALL_HEIGHT / 2.0,
+                (row as f32 + (height as f32 - 1.0) / 2.0) * cell_size,
+            );
+            let collider = Collider::cuboid(
+                width as f32 * cell_size / 2.0,
+                WALL_HEIGHT / 2.0,
+                height as f32 * cell_size / 2.0,
+            );
+            (center, Quat::IDENTITY, collider)
+        })
+        .collect();
+    if !shapes.is_empty() {
+        commands.spawn((
+            TransformBundle::default(),
+            RigidBody::Fixed,
+            Collider::compound(shapes),
+        ));
+        spawned += 1;
+    }
+
+    spawned
+}
+
+// Conteneur plein écran qui centre son contenu
+fn spawn_centered(commands: &mut Commands) -> Entity {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id()
+}
+
+// HUD: barre de vie, viseur, hitmarker, flash et indicateur de dégâts, fil des éliminations
+fn create_hud(commands: &mut Commands, player_life: i64) {
+    // Barre de vie, en bas au centre
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.0),
+                bottom: Val::Px(10.0),
+                width: Val::Px(220.0),
+                height: Val::Px(22.0),
+                margin: UiRect::left(Val::Px(-110.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.1, 0.1, 0.1, 0.8).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        width: Val::Percent(health_percent(player_life)),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.8, 0.1, 0.1).into(),
+                    ..default()
+                },
+                HealthBarFill,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    format!("{} / {}", player_life, MAX_LIFE),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                HealthText,
+            ));
+        });
+
+    // Voile rouge, invisible tant que le timer est écoulé
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        },
+        DamageFlash,
+        FlashTimer::idle(DAMAGE_FLASH_DURATION),
+    ));
+
+    // Viseur
+    let crosshair = spawn_centered(commands);
+    commands.entity(crosshair).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "+",
+            TextStyle {
+                font_size: 28.0,
+                color: Color::WHITE.with_a(0.8),
+                ..default()
+            },
+        ));
+    });
+
+    // Hitmarker, superposé au viseur
+    let hit_marker = spawn_centered(commands);
+    commands.entity(hit_marker).with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section(
+                "x",
+                TextStyle {
+                    font_size: 36.0,
+                    color: Color::NONE,
+                    ..default()
+                },
+            ),
+            HitMarker,
+            FlashTimer::idle(HIT_MARKER_DURATION),
+        ));
+    });
+
+    // Indicateur de direction des dégâts: une barre qui tourne autour du centre
+    let indicator = spawn_centered(commands);
+    commands.entity(indicator).with_children(|parent| {
+        parent
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(0.0),
+                        height: Val::Px(0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                DamageIndicator,
+                FlashTimer::idle(DAMAGE_INDICATOR_DURATION),
+            ))
+            .with_children(|pivot| {
+                pivot.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(-20.0),
+                        top: Val::Px(-130.0),
+                        width: Val::Px(40.0),
+                        height: Val::Px(8.0),
+                        ..default()
+                    },
+                    background_color: Color::RED.into(),
+                    ..default()
+                });
+            });
+    });
+
+    // Fil des éliminations, en haut à gauche
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            ..default()
+        }),
+        KillFeedText,
+    ));
+
+    // Chat, sous le fil des éliminations
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(130.0),
+                width: Val::Px(420.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((TextBundle::default(), ChatLogText));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::BLACK.with_a(0.5)),
+                ChatInputText,
+            ));
+        });
+}
+
+fn health_percent(life: i64) -> f32 {
+    life.clamp(0, MAX_LIFE) as f32 * 100.0 / MAX_LIFE as f32
+}
+
+/// Ce que la réponse de connexion du serveur apprend au client avant de créer le monde.
+#[derive(Resource, Debug, Clone)]
+pub struct SpawnInfo {
+    pub id_player: usize,
+    pub player_life: i64,
+    pub start_position: Vec3,
+    pub level: i32,
+    pub weapons: Vec<Weapon>,
+    pub items: Vec<serde_json::Value>,
+    pub maze_layout: Vec<Vec<u8>>,
+}
+
+// Lit les messages reçus avant le démarrage: niveau, point de départ, armes et objets
+pub fn spawn_info(messages: impl Iterator<Item = MessageRecieve>) -> SpawnInfo {
+    let mut maze_layout = level_layout(1);
     let start_positions = vec![
         //Lab1
         Vec3::new(2.0, 1.0, 1.5),    //1
@@ -182,25 +572,77 @@ pub fn setup(
     let mut id_player = 0;
     let mut player_life = 0;
     let mut start_position = Vec3::new(2.0, 1.0, 1.5);
+    let mut level = 1;
+    let mut weapons: Vec<Weapon> = Vec::new();
+    let mut items: Vec<serde_json::Value> = Vec::new();
  let mut x=0;
-    while let Ok(message) = message_receiver.0.try_recv() {
-        start_position = if message.id_player < start_positions.len() {
-            
-            if message.level == 2{
-                maze_layout = maze_layout_v1.clone();
-                x=10;
-            }else if  message.level == 3{
-                maze_layout = maze_layout_v2.clone();
-                x=20;
-            }
-            start_positions[message.id_player + x]
-        } else {
-            Vec3::new(2.0, 1.0, 1.5)
-        };
+    for message in messages {
+        level = message.level;
+        maze_layout = level_layout(message.level);
+        if let Some(list) = message
+            .content
+            .get("weapons")
+            .and_then(|list| serde_json::from_value(list.clone()).ok())
+        {
+            weapons = list;
+        }
+        if let Some(list) = message.content.get("items").and_then(|list| list.as_array()) {
+            items = list.clone();
+        }
+        if message.level == 2{
+            x=10;
+        }else if  message.level == 3{
+            x=20;
+        }
+        // Dix positions de départ par niveau: les ids suivants réutilisent les mêmes
+        start_position = start_positions
+            .get(x + message.id_player % 10)
+            .copied()
+            .unwrap_or(Vec3::new(2.0, 1.0, 1.5));
         id_player = message.id_player;
         player_life = message.player_life;
     }
 
+    SpawnInfo {
+        id_player,
+        player_life,
+        start_position,
+        level,
+        weapons,
+        items,
+        maze_layout,
+    }
+}
+
+// Un objet de la liste envoyée par le serveur: (id, type, position, disponible)
+pub fn parse_item(item: &serde_json::Value) -> Option<(usize, ItemKind, Vec3, bool)> {
+    let id = item.get("id").and_then(|id| id.as_u64())?;
+    let kind = item
+        .get("kind")
+        .and_then(|kind| serde_json::from_value::<ItemKind>(kind.clone()).ok())?;
+    let position = item.get("position").and_then(parse_vec3)?;
+    let available = item.get("available").and_then(|a| a.as_bool()).unwrap_or(true);
+    Some((id as usize, kind, position, available))
+}
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+    minimap_entities: ResMut<MinimapEntities>,
+    mut message_receiver: ResMut<MessageReceiver>,
+) {
+    let SpawnInfo {
+        id_player,
+        player_life,
+        start_position,
+        level,
+        weapons,
+        items,
+        maze_layout,
+    } = spawn_info(std::iter::from_fn(|| message_receiver.0.try_recv().ok()));
+
     let cell_size = 2.0;
 
     // Charger les textures
@@ -244,54 +686,60 @@ pub fn setup(
         })
         .id();
 
+    let maze = Maze {
+        layout: maze_layout.clone(),
+        cell_size,
+    };
+
     // Générer le labyrinthe
-    for (i, row) in maze_layout.iter().enumerate() {
-        for (j, &cell) in row.iter().enumerate() {
-            let x = j as f32 * cell_size;
-            let z = i as f32 * cell_size;
+    let spawn_started = std::time::Instant::now();
+    let spawned = spawn_maze_geometry(&mut commands, &mut meshes, &mut materials, &maze, wall_material);
+    let cell_count: usize = maze_layout.iter().map(|row| row.len()).sum();
+    let wall_count = maze_layout.iter().flatten().filter(|&&cell| cell == 1).count();
+    info!(
+        "Maze spawned in {:?}: {} entities ({} with one entity per cell and wall)",
+        spawn_started.elapsed(),
+        spawned,
+        cell_count + wall_count,
+    );
 
-            // Créer le sol pour toutes les cellules
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(shape::Plane::from_size(cell_size).into()),
-                    material: materials.add(Color::rgb(0., 0., 0.).into()),
-                    transform: Transform::from_xyz(x, 0.0, z),
-                    ..default()
-                },
-                Collider::cuboid(cell_size / 2.0, 0.1, cell_size / 2.0),
-            ));
+    // Insérer la ressource Maze
+    commands.insert_resource(maze);
+    commands.insert_resource(MinimapSettings::for_level(level));
+    commands.insert_resource(WeaponInventory::new(weapons));
+    commands.insert_resource(ExploredCells::new(&maze_layout));
 
-            // Créer un mur si la cellule est 1
-            if cell == 1 {
-                commands.spawn((
-                    PbrBundle {
-                        mesh: meshes.add(shape::Box::new(cell_size, 2.0, cell_size).into()),
-                        material: wall_material.clone(),
-                        transform: Transform::from_xyz(x, 1.0, z),
+    // Objets ramassables envoyés par le serveur
+    let item_mesh = meshes.add(shape::Cube { size: 0.4 }.into());
+    let mut item_map = ItemMap::default();
+    for (id, kind, position, available) in items.iter().filter_map(parse_item) {
+        let entity = commands
+            .spawn((
+                PbrBundle {
+                    mesh: item_mesh.clone(),
+                    material: materials.add(StandardMaterial {
+                        base_color: item_color(kind),
+                        emissive: item_color(kind) * 0.5,
                         ..default()
+                    }),
+                    transform: Transform::from_translation(position),
+                    visibility: if available {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
                     },
-                    RigidBody::Fixed,
-                    Collider::cuboid(cell_size / 2.0, 1.0, cell_size / 2.0),
-                    Wall,
-                ));
-            }
-        }
+                    ..default()
+                },
+                Item { id, kind, position },
+            ))
+            .id();
+        item_map.0.insert(id, entity);
     }
-
-    // Insérer la ressource Maze
-    commands.insert_resource(Maze {
-        layout: maze_layout.clone(),
-        cell_size,
-    });
+    commands.insert_resource(item_map);
 
     // Setup de la minimap
-    let minimap_size = 800. * 0.3;
-    let tile_size = minimap_size / maze_layout[0].len() as f32;
-
     create_minimap(
         &mut commands,
-        minimap_size,
-        tile_size,
         &maze_layout,
         minimap_entities,
         player_entity,
@@ -347,6 +795,249 @@ pub fn setup(
         .id();
 
     commands.entity(root).push_children(&[text_fps]);
+
+    // Munitions de l'arme courante
+    commands.spawn((
+        AmmoText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            ..Default::default()
+        }),
+    ));
+
+    create_hud(&mut commands, player_life);
+
+    // Overlay de performances (F11): temps de frame, graphe et réseau
+    commands
+        .spawn((
+            PerfRoot,
+            NodeBundle {
+                background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
+                z_index: ZIndex::Global(i32::MAX),
+                visibility: Visibility::Hidden,
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Percent(1.),
+                    top: Val::Px(40.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    row_gap: Val::Px(4.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                PerfText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(FRAME_HISTORY_LEN as f32 * 2.0),
+                        height: Val::Px(PERF_GRAPH_HEIGHT),
+                        align_items: AlignItems::FlexEnd,
+                        ..Default::default()
+                    },
+                    background_color: BackgroundColor(Color::rgba(0.2, 0.2, 0.2, 0.5)),
+                    ..Default::default()
+                })
+                .with_children(|graph| {
+                    for i in 0..FRAME_HISTORY_LEN {
+                        graph.spawn((
+                            PerfGraphBar(i),
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(2.0),
+                                    height: Val::Px(0.0),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            },
+                        ));
+                    }
+                });
+        });
+}
+
+// Les objets tournent sur eux-mêmes et flottent légèrement
+pub fn animate_items(time: Res<Time>, mut query: Query<(&mut Transform, &Item)>) {
+    let t = time.elapsed_seconds();
+    for (mut transform, item) in query.iter_mut() {
+        transform.rotation = Quat::from_rotation_y(t * 1.5);
+        transform.translation = item.position + Vec3::Y * ((t * 2.0 + item.id as f32).sin() * 0.15);
+    }
+}
+
+// Dégâts reçus, coups confirmés et éliminations envoyés par le serveur
+pub fn react_to_server_hud(
+    mut server_message_events: EventReader<ServerMessageReceived>,
+    mut player_query: Query<(&Transform, &mut Player)>,
+    mut flash_query: Query<&mut FlashTimer, (With<DamageFlash>, Without<DamageIndicator>, Without<HitMarker>)>,
+    mut indicator_query: Query<
+        (&mut Transform, &mut FlashTimer),
+        (With<DamageIndicator>, Without<Player>, Without<DamageFlash>, Without<HitMarker>),
+    >,
+    mut hit_marker_query: Query<(&mut Text, &mut FlashTimer), (With<HitMarker>, Without<DamageFlash>, Without<DamageIndicator>)>,
+    mut kill_feed: ResMut<KillFeed>,
+    mut chat_log: ResMut<ChatLog>,
+) {
+    for event in server_message_events.read() {
+        let message = &event.0;
+        match message.message_type {
+            MessageType::Damage => {
+                let Ok((player_transform, mut player)) = player_query.get_single_mut() else {
+                    continue;
+                };
+                player.life = message.player_life;
+                for mut timer in flash_query.iter_mut() {
+                    timer.0.reset();
+                }
+                if let Some(from) = message.content.get("from").and_then(parse_vec3) {
+                    let to_attacker = from - player_transform.translation;
+                    let angle = minimap_facing_angle(to_attacker)
+                        - minimap_facing_angle(player_transform.forward());
+                    for (mut transform, mut timer) in indicator_query.iter_mut() {
+                        transform.rotation = Quat::from_rotation_z(angle);
+                        timer.0.reset();
+                    }
+                }
+            }
+            MessageType::HitConfirm => {
+                if message.content.get("target").is_none_or(|t| t.is_null()) {
+                    continue;
+                }
+                let killed = message.content.get("killed").and_then(|k| k.as_bool()).unwrap_or(false);
+                for (mut text, mut timer) in hit_marker_query.iter_mut() {
+                    // Rouge pour une élimination
+                    text.sections[0].style.color = if killed { Color::RED } else { Color::WHITE };
+                    timer.0.reset();
+                }
+            }
+            MessageType::Kill => {
+                let field = |key: &str| {
+                    message
+                        .content
+                        .get(key)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("?")
+                        .to_string()
+                };
+                kill_feed.push(format!("{} [{}] {}", field("killer"), field("weapon"), field("victim")));
+                chat_log.push(
+                    format!("{} killed {} with {}", field("killer"), field("victim"), field("weapon")),
+                    Color::YELLOW,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn hud_update_system(
+    time: Res<Time>,
+    player_query: Query<&Player>,
+    mut kill_feed: ResMut<KillFeed>,
+    mut fill_query: Query<&mut Style, With<HealthBarFill>>,
+    mut health_text_query: Query<&mut Text, (With<HealthText>, Without<HitMarker>, Without<KillFeedText>)>,
+    mut flash_query: Query<(&mut BackgroundColor, &mut FlashTimer), (With<DamageFlash>, Without<DamageIndicator>)>,
+    mut indicator_query: Query<(&mut Visibility, &mut FlashTimer), (With<DamageIndicator>, Without<DamageFlash>, Without<HitMarker>)>,
+    mut hit_marker_query: Query<(&mut Text, &mut FlashTimer), (With<HitMarker>, Without<DamageFlash>, Without<DamageIndicator>)>,
+    mut kill_feed_query: Query<&mut Text, (With<KillFeedText>, Without<HitMarker>, Without<HealthText>)>,
+) {
+    if let Ok(player) = player_query.get_single() {
+        for mut style in fill_query.iter_mut() {
+            style.width = Val::Percent(health_percent(player.life));
+        }
+        for mut text in health_text_query.iter_mut() {
+            text.sections[0].value = format!("{} / {}", player.life.max(0), MAX_LIFE);
+        }
+    }
+
+    for (mut color, mut timer) in flash_query.iter_mut() {
+        timer.0.tick(time.delta());
+        color.0 = Color::rgba(0.8, 0.0, 0.0, 0.35 * timer.fade());
+    }
+    for (mut visibility, mut timer) in indicator_query.iter_mut() {
+        timer.0.tick(time.delta());
+        *visibility = if timer.0.finished() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+    for (mut text, mut timer) in hit_marker_query.iter_mut() {
+        timer.0.tick(time.delta());
+        let fade = timer.fade();
+        let color = text.sections[0].style.color;
+        text.sections[0].style.color = color.with_a(fade);
+    }
+
+    for (_, timer) in kill_feed.entries.iter_mut() {
+        timer.tick(time.delta());
+    }
+    kill_feed.entries.retain(|(_, timer)| !timer.finished());
+    for mut text in kill_feed_query.iter_mut() {
+        text.sections[0].value = kill_feed
+            .entries
+            .iter()
+            .map(|(line, _)| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+pub fn chat_text_update_system(
+    chat: Res<ChatState>,
+    chat_log: Res<ChatLog>,
+    mut log_query: Query<&mut Text, (With<ChatLogText>, Without<ChatInputText>)>,
+    mut input_query: Query<(&mut Text, &mut Visibility), With<ChatInputText>>,
+) {
+    if chat_log.is_changed() {
+        for mut text in log_query.iter_mut() {
+            text.sections = chat_log
+                .lines
+                .iter()
+                .map(|(line, color)| {
+                    TextSection::new(
+                        format!("{}\n", line),
+                        TextStyle {
+                            font_size: 16.0,
+                            color: *color,
+                            ..default()
+                        },
+                    )
+                })
+                .collect();
+        }
+    }
+    for (mut text, mut visibility) in input_query.iter_mut() {
+        let channel = if chat.team { "Team" } else { "All" };
+        text.sections[0].value = format!("[{}] {}_", channel, chat.input);
+        *visibility = if chat.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
 }
 
 pub fn fps_text_update_system(
@@ -358,11 +1049,9 @@ pub fn fps_text_update_system(
             .get(FrameTimeDiagnosticsPlugin::FPS)
             .and_then(|fps| fps.smoothed())
         {
-            let result = get_val(value);
-
-            text.sections[1].value = format!("{result:>4.0}");
+            text.sections[1].value = format!("{value:>4.0}");
 
-            text.sections[1].style.color = get_fps_color(result);
+            text.sections[1].style.color = get_fps_color(value);
         } else {
             text.sections[1].value = " N/A".into();
             text.sections[1].style.color = Color::WHITE;
@@ -392,12 +1081,82 @@ pub fn fps_counter_showhide(
     }
 }
 
+pub fn perf_overlay_showhide(
+    mut q: Query<&mut Visibility, With<PerfRoot>>,
+    kbd: Res<Input<KeyCode>>,
+) {
+    if kbd.just_pressed(KeyCode::F11) {
+        let mut vis = q.single_mut();
+        *vis = match *vis {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+pub fn record_frame_time(time: Res<Time>, mut history: ResMut<FrameTimeHistory>) {
+    history.0.push_back(time.delta_seconds() * 1000.0);
+    while history.0.len() > FRAME_HISTORY_LEN {
+        history.0.pop_front();
+    }
+}
+
+pub fn perf_overlay_update_system(
+    history: Res<FrameTimeHistory>,
+    network_stats: Res<NetworkStats>,
+    root_query: Query<&Visibility, With<PerfRoot>>,
+    mut text_query: Query<&mut Text, With<PerfText>>,
+    mut bar_query: Query<(&PerfGraphBar, &mut Style, &mut BackgroundColor)>,
+) {
+    // Rien à mettre à jour quand l'overlay est caché
+    if matches!(root_query.get_single(), Ok(Visibility::Hidden) | Err(_)) {
+        return;
+    }
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        let frame_times = match history.summary() {
+            Some((min, avg, max)) => {
+                format!("Frame: min {min:.1} / avg {avg:.1} / max {max:.1} ms")
+            }
+            None => "Frame: N/A".to_string(),
+        };
+        let rtt = match network_stats.rtt_ms {
+            Some(rtt) => format!("{rtt:.0} ms"),
+            None => "N/A".to_string(),
+        };
+        text.sections[0].value = format!(
+            "{frame_times}\nRTT: {rtt}\nPackets in: {:.0}/s  out: {:.0}/s\nLoss: {:.1}%",
+            network_stats.packets_in_per_sec,
+            network_stats.packets_out_per_sec,
+            network_stats.loss_percent(),
+        );
+    }
+
+    // Les barres les plus récentes sont à droite
+    let offset = FRAME_HISTORY_LEN - history.0.len();
+    for (bar, mut style, mut color) in bar_query.iter_mut() {
+        let frame_time = if bar.0 >= offset {
+            history.0.get(bar.0 - offset).copied().unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        style.height = Val::Px((frame_time / PERF_GRAPH_MAX_MS).min(1.0) * PERF_GRAPH_HEIGHT);
+        // Au-delà de 20 ms on passe sous les 50 fps
+        *color = if frame_time > 20.0 {
+            Color::RED.into()
+        } else {
+            Color::GREEN.into()
+        };
+    }
+}
+
 pub fn react_to_server_messages(
     mut commands: Commands,
     mut server_message_events: EventReader<ServerMessageReceived>,
     mut query: Query<&mut Transform, With<OtherPlayer>>,
     mut other_players_map: ResMut<OtherPlayersMap>,
     ass: Res<AssetServer>,
+    headless: Option<Res<Headless>>,
 ) {
     for event in server_message_events.read() {
         if let MessageType::Action = event.0.message_type {
@@ -416,6 +1175,19 @@ pub fn react_to_server_messages(
                         if let Ok(mut transform) = query.get_mut(entity) {
                             transform.translation = position;
                         }
+                    } else if headless.is_some() {
+                        // Sans rendu, le joueur distant n'est qu'une position
+                        let new_entity = commands
+                            .spawn((
+                                TransformBundle::from_transform(Transform::from_translation(position)),
+                                OtherPlayer {
+                                    id: player_id,
+                                    life: player_life,
+                                },
+                            ))
+                            .id();
+
+                        other_players_map.0.insert(player_id, new_entity);
                     } else {
                         // let player_
                         let my_gltf = ass.load("eye.gltf#Scene0");
@@ -449,13 +1221,22 @@ pub fn react_to_server_ball(
     mut commands: Commands,
     mut server_message_events: EventReader<ServerMessageReceived>,
     mut other_ball_map: ResMut<OtherBallMap>,
-    mut ball_query: Query<&mut Transform, With<OtherBall>>,
+    mut ball_query: Query<(&mut Transform, &mut Lifetime), With<OtherBall>>,
     mut meshes: ResMut<Assets<Mesh>>, // Récupérer les ressources Mesh
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     for event in server_message_events.read() {
         if let MessageType::Action = event.0.message_type {
-            // println!("content jjdjdjdjdjdj{}", event.0.content);
+            // Chaque balle est identifiée par son propriétaire et son numéro de séquence
+            let player_id = event.0.id_player;
+            let seq = event
+                .0
+                .content
+                .get("ball_id")
+                .and_then(|id| id.as_u64())
+                .unwrap_or(0);
+            let key = (player_id, seq);
+
             if let Some(ball_movement) = event
                 .0
                 .content
@@ -469,11 +1250,10 @@ pub fn react_to_server_ball(
                         ball_movement[2].as_f64().unwrap_or(0.0) as f32,
                     );
 
-                    let player_id = event.0.id_player;
-
-                    if let Some(&entity) = other_ball_map.0.get(&player_id) {
-                        if let Ok(mut transform) = ball_query.get_mut(entity) {
+                    if let Some(&entity) = other_ball_map.0.get(&key) {
+                        if let Ok((mut transform, mut lifetime)) = ball_query.get_mut(entity) {
                             transform.translation = position;
+                            lifetime.0.reset();
                         }
                     } else {
                         let new_entity = commands.spawn((
@@ -486,22 +1266,154 @@ pub fn react_to_server_ball(
                                 transform: Transform::from_translation(position),
                                 ..default()
                             },
-                            OtherBall { id: player_id },
+                            OtherBall { id: player_id, seq },
+                            Lifetime(Timer::from_seconds(REMOTE_BALL_TIMEOUT, TimerMode::Once)),
                         ));
 
-                        other_ball_map.0.insert(player_id, new_entity.id());
+                        other_ball_map.0.insert(key, new_entity.id());
                     }
                 }
             }
 
             if let Some(_) = event.0.content.get("delete_ball") {
-                let player_id = event.0.id_player;
-
-                if let Some(&entity) = other_ball_map.0.get(&player_id) {
+                if let Some(&entity) = other_ball_map.0.get(&key) {
                     commands.entity(entity).despawn();
-                    other_ball_map.remove(player_id)
+                    other_ball_map.remove(key)
+                }
+            }
+        }
+    }
+}
+
+// Supprime les balles distantes dont le message de destruction s'est perdu
+pub fn expire_other_balls(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut other_ball_map: ResMut<OtherBallMap>,
+    mut ball_query: Query<(Entity, &OtherBall, &mut Lifetime)>,
+) {
+    for (entity, ball, mut lifetime) in ball_query.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            other_ball_map.remove((ball.id, ball.seq));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::level_layout;
+    use bevy::ecs::system::CommandQueue;
+    use std::time::{Duration, Instant};
+
+    // Ancienne construction, gardée pour la comparaison: un sol par case et un cube par mur,
+    // chacun avec son propre collider
+    fn spawn_maze_per_cell(
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        maze: &Maze,
+        wall_material: Handle<StandardMaterial>,
+    ) -> usize {
+        let cell_size = maze.cell_size;
+        let mut spawned = 0;
+        for (i, row) in maze.layout.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                let (x, z) = (j as f32 * cell_size, i as f32 * cell_size);
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(shape::Plane::from_size(cell_size).into()),
+                        material: materials.add(Color::rgb(0., 0., 0.).into()),
+                        transform: Transform::from_xyz(x, 0.0, z),
+                        ..default()
+                    },
+                    Collider::cuboid(cell_size / 2.0, 0.1, cell_size / 2.0),
+                ));
+                spawned += 1;
+                if cell == 1 {
+                    commands.spawn((
+                        PbrBundle {
+                            mesh: meshes.add(shape::Box::new(cell_size, WALL_HEIGHT, cell_size).into()),
+                            material: wall_material.clone(),
+                            transform: Transform::from_xyz(x, 1.0, z),
+                            ..default()
+                        },
+                        RigidBody::Fixed,
+                        Collider::cuboid(cell_size / 2.0, 1.0, cell_size / 2.0),
+                        Wall,
+                    ));
+                    spawned += 1;
                 }
             }
         }
+        spawned
+    }
+
+    struct SpawnReport {
+        time: Duration,
+        entities: usize,
+        colliders: usize,
+        meshes: usize,
+    }
+
+    // Construit le labyrinthe dans un monde vide et compte ce qui a été créé
+    fn measure(
+        maze: &Maze,
+        spawn: fn(&mut Commands, &mut Assets<Mesh>, &mut Assets<StandardMaterial>, &Maze, Handle<StandardMaterial>) -> usize,
+    ) -> SpawnReport {
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut meshes = Assets::<Mesh>::default();
+        let mut materials = Assets::<StandardMaterial>::default();
+        let started = Instant::now();
+        let spawned = {
+            let mut commands = Commands::new(&mut queue, &world);
+            spawn(&mut commands, &mut meshes, &mut materials, maze, Handle::default())
+        };
+        queue.apply(&mut world);
+        let time = started.elapsed();
+
+        let entities = world.entities().len() as usize;
+        assert_eq!(spawned, entities);
+        let colliders = world.query::<&Collider>().iter(&world).count();
+        SpawnReport { time, entities, colliders, meshes: meshes.len() }
+    }
+
+    // `cargo test --release maze_spawn -- --nocapture` affiche les mesures de chaque niveau
+    #[test]
+    fn maze_spawn_benchmark_against_one_entity_per_cell() {
+        for level in 1..=3 {
+            let maze = Maze { layout: level_layout(level), cell_size: 2.0 };
+            let cells: usize = maze.layout.iter().map(|row| row.len()).sum();
+            let walls = maze.layout.iter().flatten().filter(|&&cell| cell == 1).count();
+
+            let merged = measure(&maze, spawn_maze_geometry);
+            let per_cell = measure(&maze, spawn_maze_per_cell);
+            let shapes = merge_wall_rects(&maze).len();
+            println!(
+                "level {}: merged {:?}, {} entities, {} colliders ({} wall shapes), {} meshes | per cell {:?}, {} entities, {} colliders, {} meshes",
+                level,
+                merged.time,
+                merged.entities,
+                merged.colliders,
+                shapes,
+                merged.meshes,
+                per_cell.time,
+                per_cell.entities,
+                per_cell.colliders,
+                per_cell.meshes,
+            );
+
+            assert_eq!(per_cell.entities, cells + walls);
+            assert_eq!(per_cell.colliders, cells + walls);
+            // Sol + compound des murs
+            assert_eq!(merged.colliders, 2);
+            assert!(shapes < walls);
+            // Sol + au plus un mesh par morceau de WALL_CHUNK_SIZE x WALL_CHUNK_SIZE cases
+            let chunks = maze.layout.len().div_ceil(WALL_CHUNK_SIZE) * maze.layout[0].len().div_ceil(WALL_CHUNK_SIZE);
+            assert!(merged.entities <= 1 + chunks + 1);
+            assert!(merged.entities * 10 < per_cell.entities);
+        }
     }
 }
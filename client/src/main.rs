@@ -1,13 +1,21 @@
 mod arms;
+mod browser;
 mod components;
+mod demo;
+mod geometry;
+mod headless;
 mod labyrinte;
+mod levels;
 mod message;
+mod netsim;
 mod players;
+mod secure;
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 
-use serde_json::{Error, Value};
+use serde_json::{json, Error, Value};
 use std::io::{self, Write};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::UdpSocket;
 use tokio::runtime::Runtime;
 use tokio::signal;
@@ -15,10 +23,15 @@ use tokio::sync::mpsc;
 use tokio::sync::mpsc::Receiver;
 
 use crate::arms::*;
+use crate::browser::*;
 use crate::components::*;
+use crate::demo::*;
+use crate::headless::*;
 use crate::labyrinte::*;
 use crate::message::*;
+use crate::netsim::*;
 use crate::players::*;
+use crate::secure::{from_hex, to_hex};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
@@ -44,25 +57,73 @@ lazy_static::lazy_static! {
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
-    print!("Enter Server IP Address (e.g., 11.11.90.13:1234): ");
-    io::stdout().flush()?;
-    let mut server_ip = String::new();
-    io::stdin().read_line(&mut server_ip)?;
-    let server_ip = server_ip.trim();
-
-    print!("Enter Your Name: ");
-    io::stdout().flush()?;
-    let mut username = String::new();
-    io::stdin().read_line(&mut username)?;
-    let username = username.trim().to_string();
+    // `--headless` lance le client sans rendu, avec des entrées scriptées
+    let headless = std::env::args().any(|arg| arg == "--headless");
+    // `--replay=<fichier>` rejoue une démo enregistrée, sans serveur
+    if let Some(path) = arg_value("--replay=") {
+        return run_replay(&path).await;
+    }
+    // `--servers` affiche les serveurs du réseau local et quitte
+    if std::env::args().any(|arg| arg == "--servers") {
+        match discover_servers().await? {
+            servers if servers.is_empty() => println!("No server found on the local network."),
+            servers => println!("{}", server_table(&servers)),
+        }
+        return Ok(());
+    }
+    let server_ip = match arg_value("--server=") {
+        Some(server_ip) => server_ip,
+        None => choose_server().await?,
+    };
+    let server_ip = server_ip.as_str();
+    let username = match arg_value("--name=") {
+        Some(username) => username,
+        None => prompt("Enter Your Name: ")?,
+    };
 
     println!("Data sent to server. Waiting for response...");
     // Créez un socket UDP pour le client
     let socket = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
 
     let _ = socket.connect(server_ip).await;
+    // Réseau simulé pour tester le netcode en local: `--netsim=latency=80ms,jitter=20ms,loss=5%`
+    let conditions = match arg_value("--netsim=").map(|spec| NetConditions::parse(&spec)) {
+        Some(Ok(conditions)) => conditions,
+        Some(Err(e)) => {
+            eprintln!("Invalid --netsim: {}", e);
+            return Ok(());
+        }
+        None => NetConditions::default(),
+    };
+    if !conditions.is_perfect() {
+        println!("Simulating network conditions: {}", conditions);
+    }
+    let socket = Arc::new(Transport::with_conditions(socket, conditions, rand::random()));
 
-    let udp_socket_resource = UdpSocketResource {
+    // Canal chiffré: `--encrypt`, ou `--server-key=<clé>` pour refuser tout autre serveur
+    let pinned = match arg_value("--server-key=").map(|key| from_hex(&key).and_then(|key| <[u8; 32]>::try_from(key).ok())) {
+        Some(Some(key)) => Some(key),
+        Some(None) => {
+            eprintln!("Invalid --server-key: expected 64 hexadecimal characters");
+            return Ok(());
+        }
+        None => None,
+    };
+    if pinned.is_some() || std::env::args().any(|arg| arg == "--encrypt") {
+        match socket.handshake(pinned).await {
+            Ok(server_key) => println!("Encrypted channel with server key {}", to_hex(&server_key)),
+            Err(e) => {
+                eprintln!("Encryption handshake failed: {}", e);
+                return Ok(());
+            }
+        }
+    }
+
+    if let Some(path) = arg_value("--record=") {
+        start_recording(&path)?;
+    }
+
+    let mut udp_socket_resource = UdpSocketResource {
         socket: socket.clone(),
         username: username.clone(),
         id: 0,
@@ -72,7 +133,7 @@ async fn main() -> io::Result<()> {
         &socket,
         MessageType::Connect,
         username.clone(),
-        Value::Null,
+        connect_content(),
         0,
     )
     .await?;
@@ -95,18 +156,21 @@ async fn main() -> io::Result<()> {
         std::process::exit(1);
     });
 
-    let (tx, rx) = mpsc::channel(32);
+    let (tx, mut rx) = mpsc::channel(32);
 
     // Tâche pour recevoir des messages
     let socket_clone = socket.clone();
     tokio::spawn(async move {
-        let mut buf = vec![0; 1024];
+        // Taille maximale d'un datagramme UDP
+        let mut buf = vec![0; 65536];
         loop {
             match socket_clone.recv_from(&mut buf).await {
                 Ok((amt, _src)) => {
+                    PACKETS_RECEIVED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                     let response: Result<MessageRecieve, Error> =
                         serde_json::from_slice(&buf[..amt]);
                     if let Ok(message) = response {
+                        record(&message);
                         let message_clone = message.clone();
                         if let Err(e) = tx.send(message).await {
                             eprintln!("Failed to send message to channel: {}", e);
@@ -114,9 +178,17 @@ async fn main() -> io::Result<()> {
                         // println!("$$$$$$: {:?}", message_clone);
 
                         if !message_clone.canconnect {
-                            println!(" Username already taken. Please choose another one or \n The number of connected players exceeds 10");
+                            match message_clone.content.as_str() {
+                                Some(reason) => println!(" {}", reason),
+                                None => println!(" Username already taken. Please choose another one or \n The number of connected players exceeds 10"),
+                            }
                             std::process::exit(1);
                         }
+                        // Expulsé par la console d'administration du serveur
+                        if let MessageType::Kicked = message_clone.message_type {
+                            println!(" {}", message_clone.content.as_str().unwrap_or("Kicked by the server"));
+                            std::process::exit(0);
+                        }
                     } else {
                         eprintln!("Failed to deserialize response");
                     }
@@ -128,26 +200,128 @@ async fn main() -> io::Result<()> {
         }
     });
 
+    // Sans rendu, le monde est créé à partir de la réponse de connexion: on l'attend ici
+    let spawn = if headless {
+        let Ok(Some(answer)) = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await else {
+            eprintln!("No answer from the server");
+            return Ok(());
+        };
+        let info = spawn_info(std::iter::once(answer));
+        udp_socket_resource.id = info.id_player;
+        Some(info)
+    } else {
+        None
+    };
+
     // Exécuter Bevy sur le thread principal
-    App::new()
-        .add_plugins((DefaultPlugins, RapierPhysicsPlugin::<NoUserData>::default()))
-        .init_resource::<MinimapEntities>()
+    let mut app = App::new();
+    add_world_systems(&mut app, rx, udp_socket_resource);
+    add_game_systems(&mut app);
+
+    if let Some(info) = spawn {
+        // Script donné par `--script=`, sinon le script par défaut qui tourne en boucle
+        let steps = match arg_value("--script=") {
+            Some(path) => std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| parse_script(&text)),
+            None => parse_script(DEFAULT_SCRIPT),
+        };
+        let steps = match steps {
+            Ok(steps) => steps,
+            Err(e) => {
+                eprintln!("Invalid input script: {}", e);
+                return Ok(());
+            }
+        };
+        let looping = std::env::args().any(|arg| arg == "--loop") || arg_value("--script=").is_none();
+        add_headless_systems(&mut app, info, InputScript::new(steps, looping));
+        if let Some(seconds) = arg_value("--duration=").and_then(|s| s.parse::<f32>().ok()) {
+            app.insert_resource(ExitAfter(Timer::from_seconds(seconds, TimerMode::Once)))
+                .add_systems(Update, exit_after);
+        }
+        app.run();
+        return Ok(());
+    }
+
+    add_rendered_systems(&mut app);
+    app.add_systems(Update, chat_input.in_set(GameSet::PlayerInput))
+        .run();
+
+    Ok(())
+}
+
+// Lit une démo enregistrée avec `--record` et la rejoue sans se connecter
+async fn run_replay(path: &str) -> io::Result<()> {
+    let records = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_demo(&text))
+    {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Invalid demo {}: {}", path, e);
+            return Ok(());
+        }
+    };
+    let answer = records[0].message.clone();
+    let initial_items = spawn_info(std::iter::once(answer.clone()))
+        .items
+        .iter()
+        .filter_map(parse_item)
+        .map(|(id, _, _, available)| (id, available))
+        .collect();
+
+    // Assez de place pour toute la démo: la lecture n'attend jamais
+    let (tx, rx) = mpsc::channel(2 * records.len() + 1);
+    // `setup` construit le monde à partir de la réponse de connexion enregistrée
+    let _ = tx.try_send(answer.clone());
+    // Les systèmes qui parlent au serveur ne tournent pas: ce socket ne sert jamais
+    let udp_socket_resource = UdpSocketResource {
+        socket: Arc::new(Transport::new(Arc::new(UdpSocket::bind("127.0.0.1:0").await?))),
+        username: answer.player_name.clone(),
+        id: answer.id_player,
+    };
+    println!("Replaying {} ({} messages)", path, records.len());
+
+    let mut app = App::new();
+    add_world_systems(&mut app, rx, udp_socket_resource);
+    add_rendered_systems(&mut app);
+    app.insert_resource(Replay::new(records, initial_items, tx))
+        .add_systems(Startup, setup_replay_text)
+        .add_systems(
+            Update,
+            (replay_controls, replay_playback)
+                .chain()
+                .before(GameSet::NetworkInput),
+        )
+        .add_systems(Update, free_camera.in_set(GameSet::PlayerInput))
+        .add_systems(Update, replay_text_update_system.in_set(GameSet::UI))
+        .run();
+
+    Ok(())
+}
+
+// Ressources et systèmes qui reproduisent le monde décrit par les messages du serveur
+fn add_world_systems(app: &mut App, rx: Receiver<MessageRecieve>, udp_socket_resource: UdpSocketResource) {
+    app.init_resource::<MinimapEntities>()
+        .init_resource::<MinimapSettings>()
+        .init_resource::<MinimapView>()
+        .init_resource::<ExploredCells>()
+        .init_resource::<FrameTimeHistory>()
+        .init_resource::<NetworkStats>()
+        .init_resource::<WeaponInventory>()
+        .init_resource::<Tracers>()
         .init_resource::<OtherPlayersMap>()
         .insert_resource(PlayerState { is_dead: false })
         .init_resource::<OtherBallMap>()
+        .init_resource::<BallSequence>()
+        .init_resource::<ItemMap>()
+        .init_resource::<ActiveEffects>()
+        .init_resource::<KillFeed>()
+        .init_resource::<ChatState>()
+        .init_resource::<ChatLog>()
         .add_event::<ServerMessageReceived>()
-        .insert_resource(AmbientLight {
-            color: Color::WHITE,
-            brightness: 0.6,
-        })
-        .insert_resource(RapierConfiguration {
-            gravity: Vec3::ZERO,
-            ..default()
-        })
         .insert_resource(MessageReceiver(rx))
         .insert_resource(udp_socket_resource)
-        .add_plugins(FrameTimeDiagnosticsPlugin::default())
-        .add_systems(Startup, setup)
         .configure_sets(
             Update,
             (
@@ -161,34 +335,154 @@ async fn main() -> io::Result<()> {
                 .chain(),
         )
         .add_systems(Update, handle_server_messages.in_set(GameSet::NetworkInput))
+        .add_systems(Update, react_to_server_messages.in_set(GameSet::NetworkOutput))
+        .add_systems(
+            Update,
+            (react_to_server_ball, expire_other_balls)
+                .chain()
+                .in_set(GameSet::NetworkOutput),
+        );
+}
+
+// Systèmes de jeu, communs au client graphique et au mode sans rendu
+fn add_game_systems(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            player_movement_and_rotation,
+            (weapon_controls, shoot_ball).chain().run_if(chat_closed),
+            check_player_death,
+        )
+            .in_set(GameSet::PlayerInput),
+    )
+    .add_systems(Update, move_balls.in_set(GameSet::Movement))
+    .add_systems(
+        Update,
+        check_ball_player_collisions.in_set(GameSet::Collision),
+    )
+    // .add_systems(Update, handle_player_life_update.in_set(GameSet::UpdateLife))
+    .add_systems(Update, send_ping.in_set(GameSet::NetworkOutput));
+}
+
+// Pas de fenêtre ni de GPU: boucle à 60 Hz, monde créé depuis `info` et clavier remplacé par `script`
+fn add_headless_systems(app: &mut App, info: SpawnInfo, script: InputScript) {
+    app.add_plugins((
+        MinimalPlugins.set(bevy::app::ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / 60.0))),
+        bevy::asset::AssetPlugin::default(),
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_resource::<Input<KeyCode>>()
+    .init_resource::<Headless>()
+    .insert_resource(info)
+    .insert_resource(script)
+    .add_systems(Startup, setup_headless)
+    .add_systems(Update, play_input_script.before(GameSet::PlayerInput))
+    .add_systems(Update, apply_velocity.in_set(GameSet::Movement));
+}
+
+// Fenêtre, physique, scène et interface du client graphique
+fn add_rendered_systems(app: &mut App) {
+    app.add_plugins((DefaultPlugins, RapierPhysicsPlugin::<NoUserData>::default()))
+        .insert_resource(AmbientLight {
+            color: Color::WHITE,
+            brightness: 0.6,
+        })
+        .insert_resource(RapierConfiguration {
+            gravity: Vec3::ZERO,
+            ..default()
+        })
+        .add_plugins(FrameTimeDiagnosticsPlugin::default())
+        .add_systems(Startup, setup)
         .add_systems(
             Update,
             (
-                player_movement_and_rotation,
-                shoot_ball,
+                minimap_controls.run_if(chat_closed),
                 fps_counter_showhide,
-                check_player_death,
+                perf_overlay_showhide,
             )
                 .in_set(GameSet::PlayerInput),
         )
-        .add_systems(Update, move_balls.in_set(GameSet::Movement))
-        .add_systems(
-            Update,
-            check_ball_player_collisions.in_set(GameSet::Collision),
-        )
-        // .add_systems(Update, handle_player_life_update.in_set(GameSet::UpdateLife))
+        .add_systems(Update, (draw_tracers, animate_items).in_set(GameSet::Movement))
         .add_systems(
             Update,
-            (update_minimap_player, fps_text_update_system).in_set(GameSet::UI),
-        )
-        .add_systems(
-            Update,
-            react_to_server_messages.in_set(GameSet::NetworkOutput),
+            (
+                (
+                    update_minimap_view,
+                    (explore_maze, update_minimap_fog).chain(),
+                    (
+                        update_minimap_player,
+                        update_minimap_other_players,
+                        update_minimap_balls,
+                        update_minimap_items,
+                    ),
+                )
+                    .chain(),
+                fps_text_update_system,
+                ammo_text_update_system,
+                hud_update_system,
+                chat_text_update_system,
+                (record_frame_time, perf_overlay_update_system).chain(),
+            )
+                .in_set(GameSet::UI),
         )
-        .add_systems(Update, react_to_server_ball.in_set(GameSet::NetworkOutput))
-        .run();
+        .add_systems(Update, react_to_server_hud.in_set(GameSet::NetworkOutput));
+}
 
-    Ok(())
+// Valeur d'un argument `--nom=valeur`
+fn arg_value(prefix: &str) -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(str::to_string))
+}
+
+// Mots de passe pour la connexion: `--server-password=`, `--password=` (compte) et `--register`
+fn connect_content() -> Value {
+    let mut content = serde_json::Map::new();
+    if let Some(password) = arg_value("--server-password=") {
+        content.insert("server_password".to_string(), Value::String(password));
+    }
+    if let Some(password) = arg_value("--password=") {
+        content.insert("password".to_string(), Value::String(password));
+    }
+    if std::env::args().any(|arg| arg == "--register") {
+        content.insert("register".to_string(), Value::Bool(true));
+    }
+    if content.is_empty() {
+        Value::Null
+    } else {
+        Value::Object(content)
+    }
+}
+
+// Liste les serveurs du réseau local et demande lequel rejoindre, ou une adresse
+async fn choose_server() -> io::Result<String> {
+    loop {
+        let servers = discover_servers().await.unwrap_or_else(|e| {
+            eprintln!("LAN discovery failed: {}", e);
+            Vec::new()
+        });
+        if servers.is_empty() {
+            println!("No server found on the local network.");
+        } else {
+            println!("{}", server_table(&servers));
+        }
+        let answer = prompt("Enter a server number, a Server IP Address (e.g., 11.11.90.13:1234), or nothing to refresh: ")?;
+        if answer.is_empty() {
+            continue;
+        }
+        match answer.parse::<usize>() {
+            Ok(number) if (1..=servers.len()).contains(&number) => return Ok(servers[number - 1].address.clone()),
+            Ok(number) => println!("No server number {}.", number),
+            Err(_) => return Ok(answer),
+        }
+    }
+}
+
+fn prompt(question: &str) -> io::Result<String> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
 }
 
 // Système Bevy pour traiter les messages du serveur
@@ -199,6 +493,15 @@ fn handle_server_messages(
     mut server_message_events: EventWriter<ServerMessageReceived>,
     mut player_state: ResMut<PlayerState>,
     mut other_players_map: ResMut<OtherPlayersMap>,
+    mut network_stats: ResMut<NetworkStats>,
+    mut tracers: ResMut<Tracers>,
+    mut inventory: ResMut<WeaponInventory>,
+    mut effects: ResMut<ActiveEffects>,
+    item_map: Res<ItemMap>,
+    mut item_query: Query<&mut Visibility, With<Item>>,
+    mut player_query: Query<&mut Player>,
+    mut chat_log: ResMut<ChatLog>,
+    time: Res<Time>,
 ) {
     while let Ok(message) = message_receiver.0.try_recv() {
         info!("Message from server: {:?}", message);
@@ -217,11 +520,13 @@ fn handle_server_messages(
                 player_state.is_dead = true;
 
                 let socket = udp_socket_resource.socket.clone();
+                // Le serveur n'accepte un message qu'au nom du joueur connecté depuis cette adresse
+                let username = udp_socket_resource.username.clone();
                 TOKIO_RUNTIME.spawn(async move {
                     if let Err(e) = send_message(
                         &socket,
                         MessageType::OtherDeadPlayer,
-                        "".to_string(),
+                        username,
                         Value::Null,
                         message.id_player,
                     )
@@ -239,6 +544,104 @@ fn handle_server_messages(
                     other_players_map.0.remove(&message.id_player);
                 }
             }
+            // Le tireur a déjà tracé son propre tir localement, seul le HUD réagit
+            MessageType::HitConfirm | MessageType::Damage | MessageType::Kill => {
+                server_message_events.send(ServerMessageReceived(message.clone()));
+            }
+            MessageType::Shoot => {
+                let origin = message.content.get("origin").and_then(parse_vec3);
+                let end = message.content.get("end").and_then(parse_vec3);
+                if let (Some(origin), Some(end)) = (origin, end) {
+                    tracers.add(origin, end);
+                }
+            }
+            MessageType::WeaponState => {
+                // Le serveur corrige la prédiction locale
+                let content = &message.content;
+                if let Some(index) = content.get("weapon").and_then(|w| w.as_u64()) {
+                    let index = index as usize;
+                    if index < inventory.weapons.len() {
+                        inventory.current = index;
+                        if let Some(ammo) = content.get("ammo").and_then(|a| a.as_u64()) {
+                            inventory.ammo[index] = ammo as u32;
+                        }
+                        let remaining = content
+                            .get("reload_remaining")
+                            .and_then(|r| r.as_f64())
+                            .unwrap_or(0.0) as f32;
+                        inventory.reload = if remaining > 0.0 {
+                            Some(Timer::from_seconds(remaining, TimerMode::Once))
+                        } else {
+                            None
+                        };
+                    }
+                }
+            }
+            MessageType::ItemState => {
+                let id = message.content.get("id").and_then(|id| id.as_u64());
+                let available = message.content.get("available").and_then(|a| a.as_bool());
+                if let (Some(id), Some(available)) = (id, available) {
+                    if let Some(&entity) = item_map.0.get(&(id as usize)) {
+                        if let Ok(mut visibility) = item_query.get_mut(entity) {
+                            *visibility = if available {
+                                Visibility::Inherited
+                            } else {
+                                Visibility::Hidden
+                            };
+                        }
+                    }
+                }
+            }
+            MessageType::ItemPickup => {
+                // Le serveur a validé le ramassage et renvoie notre nouvelle vie
+                if let Ok(mut player) = player_query.get_single_mut() {
+                    player.life = message.player_life;
+                }
+                let kind = message
+                    .content
+                    .get("kind")
+                    .and_then(|kind| serde_json::from_value::<ItemKind>(kind.clone()).ok());
+                let duration = message
+                    .content
+                    .get("duration")
+                    .and_then(|d| d.as_f64())
+                    .unwrap_or(0.0) as f32;
+                if let Some(kind) = kind {
+                    println!("Picked up {:?}", kind);
+                    effects.start(kind, duration);
+                }
+            }
+            MessageType::RoundRestart => {
+                if let Ok(mut player) = player_query.get_single_mut() {
+                    player.life = message.player_life;
+                }
+                *effects = ActiveEffects::default();
+            }
+            MessageType::MatchSummary => {
+                // Le client quitte à sa mort: le bilan s'affiche aussi dans le terminal
+                for (label, key) in [("This match", "match"), ("Career", "career")] {
+                    if let Some(stats) = message.content.get(key) {
+                        let line = format!("{}: {}", label, stats_summary(stats));
+                        println!("{}", line);
+                        chat_log.push(line, Color::YELLOW);
+                    }
+                }
+            }
+            MessageType::Chat => {
+                let text = message.content.get("text").and_then(|t| t.as_str()).unwrap_or_default();
+                if message.content.get("system").and_then(|s| s.as_bool()).unwrap_or(false) {
+                    chat_log.push(text.to_string(), Color::YELLOW);
+                } else if message.content.get("team").and_then(|t| t.as_bool()).unwrap_or(false) {
+                    chat_log.push(format!("[Team] {}: {}", message.player_name, text), Color::GREEN);
+                } else {
+                    chat_log.push(format!("{}: {}", message.player_name, text), Color::WHITE);
+                }
+            }
+            MessageType::Pong => {
+                if let Some(seq) = message.content.get("seq").and_then(|s| s.as_u64()) {
+                    network_stats.record_pong(seq, time.elapsed_seconds_f64());
+                }
+            }
 
             _ => {
                 println!("Unhandled message type: {:?}", message.message_type);
@@ -247,6 +650,64 @@ fn handle_server_messages(
     }
 }
 
+// Bilan d'une partie envoyé par le serveur: kills, morts, précision et temps de jeu
+fn stats_summary(stats: &Value) -> String {
+    let count = |key: &str| stats.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let accuracy = stats.get("accuracy").and_then(|a| a.as_f64()).unwrap_or(0.0);
+    let seconds = count("seconds_played");
+    format!(
+        "{} kills, {} deaths, {} matches, accuracy {:.0}%, played {}m{:02}s",
+        count("kills"),
+        count("deaths"),
+        count("matches"),
+        accuracy * 100.0,
+        seconds / 60,
+        seconds % 60
+    )
+}
+
+// Envoie un ping par seconde et met à jour les compteurs réseau
+fn send_ping(
+    time: Res<Time>,
+    mut network_stats: ResMut<NetworkStats>,
+    udp_socket_resource: Res<UdpSocketResource>,
+) {
+    if !network_stats.ping_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let now = time.elapsed_seconds_f64();
+    let elapsed = network_stats.ping_timer.duration().as_secs_f32();
+
+    let packets_in = PACKETS_RECEIVED.load(std::sync::atomic::Ordering::Relaxed);
+    let packets_out = PACKETS_SENT.load(std::sync::atomic::Ordering::Relaxed);
+    network_stats.packets_in_per_sec = (packets_in - network_stats.last_packets_in) as f32 / elapsed;
+    network_stats.packets_out_per_sec =
+        (packets_out - network_stats.last_packets_out) as f32 / elapsed;
+    network_stats.last_packets_in = packets_in;
+    network_stats.last_packets_out = packets_out;
+
+    let before = network_stats.pending_pings.len();
+    network_stats
+        .pending_pings
+        .retain(|_, sent_at| now - *sent_at < PING_TIMEOUT);
+    network_stats.lost_pings += (before - network_stats.pending_pings.len()) as u32;
+
+    let seq = network_stats.next_seq;
+    network_stats.next_seq += 1;
+    network_stats.pending_pings.insert(seq, now);
+
+    // Le serveur utilise notre RTT pour la compensation de latence
+    let content = json!({ "seq": seq, "rtt_ms": network_stats.rtt_ms });
+    let socket = udp_socket_resource.socket.clone();
+    let username = udp_socket_resource.username.clone();
+    let id = udp_socket_resource.id;
+    TOKIO_RUNTIME.spawn(async move {
+        if let Err(e) = send_message(&socket, MessageType::Ping, username, content, id).await {
+            eprintln!("Échec de l'envoi du message : {}", e);
+        }
+    });
+}
+
 fn check_player_death(player_state: Res<PlayerState>, mut exit: EventWriter<bevy::app::AppExit>) {
     if player_state.is_dead {
         println!("Player is dead, disconnecting...");
@@ -1,9 +1,16 @@
-use std::{io, sync::Arc};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::net::UdpSocket;
 use bevy::prelude::Resource;
 
+use crate::netsim::Transport;
+
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum MessageType {
@@ -17,12 +24,81 @@ pub enum MessageType {
     PlayerDeath,
     OtherDeadPlayer,
     DeletePlayer,
+    Ping,
+    Pong,
+    Shoot,
+    HitConfirm,
+    Reload,
+    SwitchWeapon,
+    WeaponState,
+    ItemState,
+    ItemPickup,
+    Damage,
+    Kill,
+    Chat,
+    Kicked,
+    RoundRestart,
+    MatchSummary,
+}
 
+// Objets ramassables, placés par le serveur
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    HealthPack,
+    SpeedBoost,
+    Shield,
+    RapidFire,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FireMode {
+    Projectile,
+    Hitscan,
+}
+
+// Définition d'une arme, envoyée par le serveur dans la réponse ConnectSuccessfull
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub name: String,
+    pub fire_mode: FireMode,
+    pub fire_rate: f32,
+    pub projectile_speed: f32,
+    pub damage: i64,
+    pub spread: f32,
+    pub magazine_size: u32,
+    pub reload_time: f32,
+    pub projectile_lifetime: f32,
+    #[serde(default)]
+    pub ricochets: u32,
+}
+
+impl Default for Weapon {
+    // L'ancienne balle, utilisée si le serveur n'envoie pas d'armes
+    fn default() -> Self {
+        Weapon {
+            name: "Blaster".to_string(),
+            fire_mode: FireMode::Projectile,
+            fire_rate: 2.0,
+            projectile_speed: 20.0,
+            damage: 1,
+            spread: 0.0,
+            magazine_size: 10,
+            reload_time: 1.5,
+            projectile_lifetime: 3.0,
+            ricochets: 0,
+        }
+    }
+}
+
+// Compteurs de paquets pour l'overlay de performances
+pub static PACKETS_SENT: AtomicU64 = AtomicU64::new(0);
+pub static PACKETS_RECEIVED: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Resource, Debug, Clone)]
 pub struct UdpSocketResource {
-    pub socket: Arc<UdpSocket>,
+    pub socket: Arc<Transport>,
     pub username: String,
     pub id: usize
 }
@@ -54,7 +130,7 @@ pub struct MessageInfo{
     pub content: Value, 
 }
 
-pub async fn send_message(socket: &Arc<UdpSocket>, typ: MessageType, username: String, content: Value, id_player: usize) -> io::Result<()> {
+pub async fn send_message(socket: &Arc<Transport>, typ: MessageType, username: String, content: Value, id_player: usize) -> io::Result<()> {
     let connect_msg = MessageSended {
         message_type: typ,
         player_name: username,
@@ -63,8 +139,10 @@ pub async fn send_message(socket: &Arc<UdpSocket>, typ: MessageType, username: S
         player_life: 0,
         level: 0,
     };
+    crate::demo::record_sent(&connect_msg);
     let message_data = serde_json::to_vec(&connect_msg)?;
     socket.send(&message_data).await?;  
+    PACKETS_SENT.fetch_add(1, Ordering::Relaxed);
 
     Ok(())
 }
@@ -0,0 +1,288 @@
+// Même simulateur que celui du serveur (server/src/netsim.rs), côté client.
+// server/tests/client_parity.rs inclut ce fichier et le compare à celui du serveur.
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+use crate::secure::{ClientHandshake, Session, HANDSHAKE_ATTEMPTS, HANDSHAKE_TIMEOUT, HELLO_REPLY, SEALED};
+
+// Retard ajouté à un datagramme « réordonné »: les suivants le doublent
+pub const REORDER_DELAY: Duration = Duration::from_millis(40);
+
+/// Simulated network conditions, e.g. `latency=80ms,jitter=20ms,loss=5%,dup=1%,reorder=2%`.
+/// Each direction of a [`Transport`] goes through them on its own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetConditions {
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub loss: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+}
+
+impl NetConditions {
+    /// Parses comma separated `key=value` settings; `off` or an empty string is a perfect network.
+    pub fn parse(spec: &str) -> Result<NetConditions, String> {
+        let mut conditions = NetConditions::default();
+        let spec = spec.trim();
+        if spec.is_empty() || spec == "off" {
+            return Ok(conditions);
+        }
+        for setting in spec.split(',') {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got `{}`", setting.trim()))?;
+            let value = value.trim();
+            match key.trim() {
+                "latency" => conditions.latency = parse_millis(value)?,
+                "jitter" => conditions.jitter = parse_millis(value)?,
+                "loss" => conditions.loss = parse_percent(value)?,
+                "dup" | "duplicate" => conditions.duplicate = parse_percent(value)?,
+                "reorder" => conditions.reorder = parse_percent(value)?,
+                other => return Err(format!("unknown setting `{}`", other)),
+            }
+        }
+        Ok(conditions)
+    }
+
+    pub fn is_perfect(&self) -> bool {
+        *self == NetConditions::default()
+    }
+
+    /// Délais des copies d'un datagramme: aucune s'il est perdu, deux s'il est dupliqué.
+    pub fn fate(&self, rng: &mut impl Rng) -> Vec<Duration> {
+        if rng.gen_bool(self.loss) {
+            return vec![];
+        }
+        let mut delay = self.latency;
+        if !self.jitter.is_zero() {
+            let jitter = self.jitter.as_secs_f64();
+            let offset = rng.gen_range(-jitter..=jitter);
+            delay = Duration::from_secs_f64((self.latency.as_secs_f64() + offset).max(0.0));
+        }
+        if rng.gen_bool(self.reorder) {
+            delay += REORDER_DELAY;
+        }
+        let mut delays = vec![delay];
+        if rng.gen_bool(self.duplicate) {
+            delays.push(delay + Duration::from_millis(rng.gen_range(0..=10)));
+        }
+        delays
+    }
+}
+
+impl fmt::Display for NetConditions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_perfect() {
+            return write!(f, "off");
+        }
+        write!(
+            f,
+            "latency={}ms,jitter={}ms,loss={}%,dup={}%,reorder={}%",
+            self.latency.as_millis(),
+            self.jitter.as_millis(),
+            self.loss * 100.0,
+            self.duplicate * 100.0,
+            self.reorder * 100.0
+        )
+    }
+}
+
+fn parse_millis(value: &str) -> Result<Duration, String> {
+    value
+        .strip_suffix("ms")
+        .unwrap_or(value)
+        .trim()
+        .parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("invalid duration `{}`, expected milliseconds", value))
+}
+
+fn parse_percent(value: &str) -> Result<f64, String> {
+    match value.strip_suffix('%').unwrap_or(value).trim().parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent / 100.0),
+        _ => Err(format!("invalid percentage `{}`", value)),
+    }
+}
+
+// Datagramme reçu en attente de livraison: (échéance, numéro d'arrivée, source, données)
+type Delayed = Reverse<(Instant, u64, SocketAddr, Vec<u8>)>;
+
+/// UDP socket wrapper that delays, drops, duplicates and reorders datagrams in both
+/// directions. With the default conditions it behaves exactly like the socket.
+pub struct Transport {
+    socket: Arc<UdpSocket>,
+    conditions: Mutex<NetConditions>,
+    rng: Mutex<StdRng>,
+    incoming: Mutex<BinaryHeap<Delayed>>,
+    arrivals: AtomicU64,
+    // Session chiffrée, après `handshake`
+    session: Mutex<Option<Session>>,
+}
+
+impl Transport {
+    pub fn new(socket: Arc<UdpSocket>) -> Self {
+        Transport::with_conditions(socket, NetConditions::default(), rand::random())
+    }
+
+    pub fn with_conditions(socket: Arc<UdpSocket>, conditions: NetConditions, seed: u64) -> Self {
+        Transport {
+            socket,
+            conditions: Mutex::new(conditions),
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            incoming: Mutex::new(BinaryHeap::new()),
+            arrivals: AtomicU64::new(0),
+            session: Mutex::new(None),
+        }
+    }
+
+    fn fate(&self) -> Vec<Duration> {
+        let conditions = self.conditions.lock().unwrap();
+        if conditions.is_perfect() {
+            return vec![Duration::ZERO];
+        }
+        conditions.fate(&mut *self.rng.lock().unwrap())
+    }
+
+    /// Negotiates the encrypted session with the server, before `Connect`. With a pinned key,
+    /// a server that answers with another key is refused. Returns the server's key.
+    pub async fn handshake(&self, pinned: Option<[u8; 32]>) -> io::Result<[u8; 32]> {
+        let handshake = ClientHandshake::new();
+        let hello = handshake.hello();
+        let mut buf = vec![0; 65536];
+        // Le même HELLO est renvoyé si la réponse se perd: le serveur répond pareil
+        for _ in 0..HANDSHAKE_ATTEMPTS {
+            self.send_simulated(&hello).await?;
+            let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+            while let Ok(received) = tokio::time::timeout_at(deadline, self.recv_simulated(&mut buf)).await {
+                let (len, _) = received?;
+                if buf[..len].first() != Some(&HELLO_REPLY) {
+                    continue;
+                }
+                let (session, server_key) = handshake
+                    .finish(&buf[..len], pinned.as_ref())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                *self.session.lock().unwrap() = Some(session);
+                return Ok(server_key);
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::TimedOut, "the server did not answer the encryption handshake"))
+    }
+
+    /// Sends to the server the socket is connected to, sealed once a session exists.
+    pub async fn send(&self, data: &[u8]) -> io::Result<usize> {
+        let sealed = self.session.lock().unwrap().as_mut().map(|session| session.seal(data));
+        self.send_simulated(sealed.as_deref().unwrap_or(data)).await?;
+        Ok(data.len())
+    }
+
+    async fn send_simulated(&self, data: &[u8]) -> io::Result<()> {
+        // Un datagramme perdu passe pour envoyé, comme sur un vrai réseau
+        for delay in self.fate() {
+            if delay.is_zero() {
+                self.socket.send(data).await?;
+                continue;
+            }
+            let socket = self.socket.clone();
+            let data = data.to_vec();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if let Err(e) = socket.send(&data).await {
+                    eprintln!("Failed to send delayed datagram: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Receives the next datagram, decrypted once a session exists. Forged, replayed and,
+    /// with a session, plaintext datagrams are dropped.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        loop {
+            let (len, from) = self.recv_simulated(buf).await?;
+            let opened = match self.session.lock().unwrap().as_mut() {
+                None => return Ok((len, from)),
+                Some(session) if buf[..len].first() == Some(&SEALED) => session.open(&buf[..len]),
+                Some(_) => None,
+            };
+            match opened {
+                Some(data) => {
+                    let len = data.len().min(buf.len());
+                    buf[..len].copy_from_slice(&data[..len]);
+                    return Ok((len, from));
+                }
+                None => eprintln!("Dropped an invalid or replayed datagram from {}", from),
+            }
+        }
+    }
+
+    // Prochain datagramme dont le délai simulé est écoulé
+    async fn recv_simulated(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        loop {
+            let next_due = {
+                let mut incoming = self.incoming.lock().unwrap();
+                match incoming.peek().map(|Reverse((due, ..))| *due) {
+                    Some(due) if due <= Instant::now() => {
+                        let Reverse((_, _, from, data)) = incoming.pop().unwrap();
+                        let len = data.len().min(buf.len());
+                        buf[..len].copy_from_slice(&data[..len]);
+                        return Ok((len, from));
+                    }
+                    due => due,
+                }
+            };
+
+            let received = match next_due {
+                Some(due) => tokio::select! {
+                    received = self.socket.recv_from(buf) => Some(received?),
+                    _ = tokio::time::sleep_until(due) => None,
+                },
+                None => Some(self.socket.recv_from(buf).await?),
+            };
+            let Some((len, from)) = received else {
+                continue;
+            };
+            let delays = self.fate();
+            // Réseau parfait et rien en attente: livré tout de suite
+            if next_due.is_none() && delays == [Duration::ZERO] {
+                return Ok((len, from));
+            }
+            let now = Instant::now();
+            let mut incoming = self.incoming.lock().unwrap();
+            for delay in delays {
+                let arrival = self.arrivals.fetch_add(1, Ordering::Relaxed);
+                incoming.push(Reverse((now + delay, arrival, from, buf[..len].to_vec())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_and_prints_them_back() {
+        let conditions = NetConditions::parse("latency=80ms, jitter=20, loss=5%,dup=1%,reorder=2").unwrap();
+        assert_eq!(conditions.latency, Duration::from_millis(80));
+        assert_eq!(conditions.jitter, Duration::from_millis(20));
+        assert_eq!(conditions.loss, 0.05);
+        assert_eq!(conditions.duplicate, 0.01);
+        assert_eq!(conditions.reorder, 0.02);
+        assert_eq!(NetConditions::parse(&conditions.to_string()).unwrap(), conditions);
+        assert!(NetConditions::parse("off").unwrap().is_perfect());
+
+        assert!(NetConditions::parse("loss=150%").is_err());
+        assert!(NetConditions::parse("latency=fast").is_err());
+        assert_eq!(NetConditions::parse("speed=1").unwrap_err(), "unknown setting `speed`");
+    }
+}
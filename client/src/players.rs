@@ -1,6 +1,9 @@
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use bevy_rapier3d::prelude::Velocity;
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 use tokio::runtime::Runtime;
 use crate::components::*;
 use crate::message::{MessageType, UdpSocketResource, send_message};
@@ -20,7 +23,11 @@ pub fn player_movement_and_rotation(
     time: Res<Time>,
     mut query: Query<(&mut Velocity, &mut Transform, &mut LastSentTransform, &Player)>,
     udp_socket_res: Res<UdpSocketResource>,
+    mut effects: ResMut<ActiveEffects>,
+    chat: Res<ChatState>,
 ) {
+    effects.tick(time.delta());
+
     // Destructure and clone necessary components
     let (mut velocity, mut transform, mut last_sent, player) = query.single_mut();
     let player_id = player.id;
@@ -30,10 +37,13 @@ pub fn player_movement_and_rotation(
 
     // Calculate forward vector
     let forward = Vec3::new(transform.forward().x, 0.0, transform.forward().z).normalize();
-    if keyboard_input.pressed(KeyCode::Up) { movement += forward; }
-    if keyboard_input.pressed(KeyCode::Down) { movement -= forward; }
-    if keyboard_input.pressed(KeyCode::Left) { rotation += 1.3; }
-    if keyboard_input.pressed(KeyCode::Right) { rotation -= 1.3; }
+    // Les touches servent à la saisie quand le chat est ouvert
+    if !chat.open {
+        if keyboard_input.pressed(KeyCode::Up) { movement += forward; }
+        if keyboard_input.pressed(KeyCode::Down) { movement -= forward; }
+        if keyboard_input.pressed(KeyCode::Left) { rotation += 1.3; }
+        if keyboard_input.pressed(KeyCode::Right) { rotation -= 1.3; }
+    }
 
     // Apply movement
     if movement.length() > 0.0 {
@@ -41,7 +51,11 @@ pub fn player_movement_and_rotation(
     } else {
         movement = Vec3::ZERO; // Stop movement if no key is pressed
     }
-    let speed = 3.0;
+    let speed = if effects.speed_boost.is_some() {
+        3.0 * SPEED_BOOST_FACTOR
+    } else {
+        3.0
+    };
     velocity.linvel = movement * speed;
 
     // Apply rotation
@@ -82,23 +96,475 @@ pub fn player_movement_and_rotation(
     }
 }
 
+pub fn minimap_controls(keyboard_input: Res<Input<KeyCode>>, mut view: ResMut<MinimapView>) {
+    if keyboard_input.just_pressed(KeyCode::M) {
+        view.rotating = !view.rotating;
+    }
+    if keyboard_input.just_pressed(KeyCode::Equals) {
+        view.zoom = (view.zoom * 1.25).min(MINIMAP_MAX_ZOOM);
+    }
+    if keyboard_input.just_pressed(KeyCode::Minus) {
+        view.zoom = (view.zoom / 1.25).max(MINIMAP_MIN_ZOOM);
+    }
+}
+
+pub fn chat_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut chat: ResMut<ChatState>,
+    udp_socket_res: Res<UdpSocketResource>,
+) {
+    if !chat.open {
+        characters.clear();
+        if keyboard_input.just_pressed(KeyCode::Return) {
+            chat.open = true;
+        }
+        return;
+    }
+
+    for event in characters.read() {
+        if !event.char.is_control() && chat.input.chars().count() < MAX_CHAT_LEN {
+            chat.input.push(event.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        chat.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        chat.team = !chat.team;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        chat.open = false;
+        chat.input.clear();
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        chat.open = false;
+        let text = std::mem::take(&mut chat.input);
+        if text.trim().is_empty() {
+            return;
+        }
+        // Le serveur renvoie le message à tous, nous compris
+        let content = json!({ "text": text, "team": chat.team });
+        let socket = udp_socket_res.socket.clone();
+        let username = udp_socket_res.username.clone();
+        let id = udp_socket_res.id;
+        TOKIO_RUNTIME.spawn(async move {
+            if let Err(e) = send_message(&socket, MessageType::Chat, username, content, id).await {
+                eprintln!("Échec de l'envoi du message : {}", e);
+            }
+        });
+    }
+}
+
+pub fn update_minimap_view(
+    maze: Res<Maze>,
+    minimap_entities: Res<MinimapEntities>,
+    mut view: ResMut<MinimapView>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut style_query: Query<&mut Style>,
+    mut pivot_query: Query<&mut Transform, (With<MinimapPivot>, Without<Player>)>,
+    tile_query: Query<(Entity, &MinimapTile)>,
+) {
+    let (Some(root), Some(pivot), Some(map)) = (
+        minimap_entities.root,
+        minimap_entities.pivot,
+        minimap_entities.map,
+    ) else {
+        return;
+    };
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let rows = maze.layout.len() as f32;
+    let cols = maze.layout.iter().map(|row| row.len()).max().unwrap_or(0) as f32;
+    if rows == 0.0 || cols == 0.0 {
+        return;
+    }
+
+    let base_size = window.width().min(window.height()) * 0.3;
+    let (tile_size, root_size) = if view.rotating {
+        let tile_size = base_size / MINIMAP_ROTATING_CELLS * view.zoom;
+        (tile_size, Vec2::splat(base_size))
+    } else {
+        let tile_size = base_size / rows.max(cols) * view.zoom;
+        (tile_size, Vec2::new(cols * tile_size, rows * tile_size))
+    };
+
+    if let Ok(mut style) = style_query.get_mut(root) {
+        style.width = Val::Px(root_size.x);
+        style.height = Val::Px(root_size.y);
+    }
+
+    let player_position = Vec2::new(
+        (player_transform.translation.x / maze.cell_size + 0.5) * tile_size,
+        (player_transform.translation.z / maze.cell_size + 0.5) * tile_size,
+    );
+    let (pivot_position, pivot_rotation, map_position) = if view.rotating {
+        (
+            root_size / 2.0,
+            Quat::from_rotation_z(-minimap_facing_angle(player_transform.forward())),
+            -player_position,
+        )
+    } else {
+        (Vec2::ZERO, Quat::IDENTITY, Vec2::ZERO)
+    };
+
+    if let Ok(mut style) = style_query.get_mut(pivot) {
+        style.left = Val::Px(pivot_position.x);
+        style.top = Val::Px(pivot_position.y);
+    }
+    if let Ok(mut transform) = pivot_query.get_mut(pivot) {
+        transform.rotation = pivot_rotation;
+    }
+    if let Ok(mut style) = style_query.get_mut(map) {
+        style.left = Val::Px(map_position.x);
+        style.top = Val::Px(map_position.y);
+        style.width = Val::Px(cols * tile_size);
+        style.height = Val::Px(rows * tile_size);
+    }
+
+    // Les cases ne bougent que lorsque leur taille change
+    if (view.tile_size - tile_size).abs() > f32::EPSILON {
+        for (entity, tile) in tile_query.iter() {
+            if let Ok(mut style) = style_query.get_mut(entity) {
+                style.width = Val::Px(tile_size);
+                style.height = Val::Px(tile_size);
+                style.left = Val::Px(tile.col as f32 * tile_size);
+                style.top = Val::Px(tile.row as f32 * tile_size);
+            }
+        }
+        view.tile_size = tile_size;
+    }
+}
+
+pub fn explore_maze(
+    maze: Res<Maze>,
+    settings: Res<MinimapSettings>,
+    mut explored: ResMut<ExploredCells>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    if !settings.fog_of_war {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let eye = player_transform.translation;
+    let Some((player_row, player_col)) = maze.cell_at(eye) else {
+        return;
+    };
+    // La vue ne dépend que de la case occupée
+    if explored.last_cell == Some((player_row, player_col)) {
+        return;
+    }
+    explored.last_cell = Some((player_row, player_col));
+
+    for row in (player_row as i32 - FOG_SIGHT_RADIUS)..=(player_row as i32 + FOG_SIGHT_RADIUS) {
+        for col in (player_col as i32 - FOG_SIGHT_RADIUS)..=(player_col as i32 + FOG_SIGHT_RADIUS) {
+            if row < 0 || col < 0 {
+                continue;
+            }
+            let (row, col) = (row as usize, col as usize);
+            if row >= explored.cells.len() || col >= explored.cells[row].len() {
+                continue;
+            }
+            if explored.cells[row][col] {
+                continue;
+            }
+            let center = Vec3::new(
+                col as f32 * maze.cell_size,
+                eye.y,
+                row as f32 * maze.cell_size,
+            );
+            // Un mur est vu si le rayon s'arrête sur lui
+            let seen = match maze.raycast(eye, center) {
+                None => true,
+                Some(hit) => maze.cell_at(hit.point - hit.normal * 0.01) == Some((row, col)),
+            };
+            if seen {
+                explored.cells[row][col] = true;
+            }
+        }
+    }
+}
+
+pub fn update_minimap_fog(
+    settings: Res<MinimapSettings>,
+    explored: Res<ExploredCells>,
+    mut tile_query: Query<(&MinimapTile, &mut Visibility)>,
+) {
+    if !explored.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for (tile, mut visibility) in tile_query.iter_mut() {
+        *visibility = if !settings.fog_of_war || explored.is_explored(tile.row, tile.col) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
 pub fn update_minimap_player(
     maze: Res<Maze>,
+    view: Res<MinimapView>,
     minimap_entities: Res<MinimapEntities>,
     player_query: Query<&Transform, With<Player>>,
-    mut minimap_player_query: Query<&mut Style, With<MinimapPlayer>>,
+    mut minimap_player_query: Query<(&mut Style, &mut Transform), (With<MinimapPlayer>, Without<Player>)>,
+    mut facing_query: Query<&mut Style, (With<MinimapFacing>, Without<MinimapPlayer>)>,
 ) {
     if let Ok(player_transform) = player_query.get_single() {
         if let Some(minimap_player) = minimap_entities.player {
-            if let Ok(mut style) = minimap_player_query.get_mut(minimap_player) {
-                let window_width = 800.0;
-                let minimap_size = window_width * 0.3;
-                let tile_size = minimap_size / maze.layout[0].len() as f32;
-                let x = player_transform.translation.x / maze.cell_size;
-                let z = player_transform.translation.z / maze.cell_size;
-                style.left = Val::Px(x * tile_size);
-                style.top = Val::Px(z * tile_size);
+            if let Ok((mut style, mut transform)) = minimap_player_query.get_mut(minimap_player) {
+                let tile_size = view.tile_size;
+                let position = minimap_position(&maze, player_transform.translation, tile_size, tile_size);
+                style.width = Val::Px(tile_size);
+                style.height = Val::Px(tile_size);
+                style.left = Val::Px(position.0);
+                style.top = Val::Px(position.1);
+                transform.rotation =
+                    Quat::from_rotation_z(minimap_facing_angle(player_transform.forward()));
+
+                for mut facing_style in facing_query.iter_mut() {
+                    let width = tile_size * 0.3;
+                    let height = tile_size * 0.6;
+                    facing_style.width = Val::Px(width);
+                    facing_style.height = Val::Px(height);
+                    facing_style.left = Val::Px((tile_size - width) / 2.0);
+                    facing_style.top = Val::Px(-height / 2.0);
+                }
+            }
+        }
+    }
+}
+
+// Taille, position, couleur et visibilité d'un marqueur de la minimap pour cette frame
+struct MarkerLook {
+    size: f32,
+    position: (f32, f32),
+    color: Color,
+    visible: bool,
+}
+
+impl MarkerLook {
+    fn visibility(&self) -> Visibility {
+        if self.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    }
+}
+
+fn spawn_minimap_marker(commands: &mut Commands, map: Entity, look: &MarkerLook, marker: impl Component) -> Entity {
+    let entity = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(look.size),
+                    height: Val::Px(look.size),
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(look.position.0),
+                    top: Val::Px(look.position.1),
+                    ..default()
+                },
+                background_color: look.color.into(),
+                visibility: look.visibility(),
+                ..default()
+            },
+            marker,
+        ))
+        .id();
+    commands.entity(map).add_child(entity);
+    entity
+}
+
+// Met à jour le marqueur de `key`, ou le crée s'il n'existe pas encore
+fn place_minimap_marker<K: Eq + Hash, M: Component>(
+    commands: &mut Commands,
+    map: Entity,
+    markers: &mut HashMap<K, Entity>,
+    key: K,
+    marker_query: &mut Query<(&mut Style, &mut Visibility), With<M>>,
+    look: MarkerLook,
+    marker: M,
+) {
+    match markers.get(&key) {
+        Some(&entity) => {
+            if let Ok((mut style, mut visibility)) = marker_query.get_mut(entity) {
+                style.width = Val::Px(look.size);
+                style.height = Val::Px(look.size);
+                style.left = Val::Px(look.position.0);
+                style.top = Val::Px(look.position.1);
+                *visibility = look.visibility();
             }
         }
+        None => {
+            let entity = spawn_minimap_marker(commands, map, &look, marker);
+            markers.insert(key, entity);
+        }
+    }
+}
+
+// Supprime les marqueurs des joueurs ou des balles qui ont disparu
+fn despawn_missing_markers<K: Eq + Hash>(commands: &mut Commands, markers: &mut HashMap<K, Entity>, present: &HashSet<K>) {
+    markers.retain(|key, marker| {
+        if present.contains(key) {
+            true
+        } else {
+            commands.entity(*marker).despawn_recursive();
+            false
+        }
+    });
+}
+
+// Position (left, top) d'un marqueur centré sur `position` dans la minimap
+fn minimap_position(maze: &Maze, position: Vec3, tile_size: f32, marker_size: f32) -> (f32, f32) {
+    let x = position.x / maze.cell_size + 0.5;
+    let z = position.z / maze.cell_size + 0.5;
+    (x * tile_size - marker_size / 2.0, z * tile_size - marker_size / 2.0)
+}
+
+pub fn update_minimap_other_players(
+    mut commands: Commands,
+    maze: Res<Maze>,
+    settings: Res<MinimapSettings>,
+    view: Res<MinimapView>,
+    mut minimap_entities: ResMut<MinimapEntities>,
+    player_query: Query<(&Transform, &Player)>,
+    other_players_query: Query<(&Transform, &OtherPlayer)>,
+    mut marker_query: Query<(&mut Style, &mut Visibility), With<MinimapOtherPlayer>>,
+) {
+    let Some(map) = minimap_entities.map else {
+        return;
+    };
+    let Ok((player_transform, player)) = player_query.get_single() else {
+        return;
+    };
+    let tile_size = view.tile_size;
+
+    let mut present = HashSet::new();
+    for (transform, other_player) in other_players_query.iter() {
+        present.insert(other_player.id);
+        let visible = settings.visibility.shows(
+            &maze,
+            (player_transform.translation, player.id),
+            (transform.translation, other_player.id),
+        );
+        let look = MarkerLook {
+            size: tile_size,
+            position: minimap_position(&maze, transform.translation, tile_size, tile_size),
+            color: Color::BLUE,
+            visible,
+        };
+        place_minimap_marker(
+            &mut commands,
+            map,
+            &mut minimap_entities.other_players,
+            other_player.id,
+            &mut marker_query,
+            look,
+            MinimapOtherPlayer,
+        );
+    }
+
+    // Supprimer les marqueurs des joueurs qui ont quitté la partie
+    despawn_missing_markers(&mut commands, &mut minimap_entities.other_players, &present);
+}
+
+pub fn update_minimap_balls(
+    mut commands: Commands,
+    maze: Res<Maze>,
+    settings: Res<MinimapSettings>,
+    view: Res<MinimapView>,
+    mut minimap_entities: ResMut<MinimapEntities>,
+    player_query: Query<(&Transform, &Player)>,
+    ball_query: Query<(Entity, &Transform, Option<&OtherBall>), Or<(With<Ball>, With<OtherBall>)>>,
+    mut marker_query: Query<(&mut Style, &mut Visibility), With<MinimapBall>>,
+) {
+    let Some(map) = minimap_entities.map else {
+        return;
+    };
+    let Ok((player_transform, player)) = player_query.get_single() else {
+        return;
+    };
+    let tile_size = view.tile_size;
+    let ball_size = tile_size * 0.4;
+
+    let mut present = HashSet::new();
+    for (ball_entity, transform, other_ball) in ball_query.iter() {
+        present.insert(ball_entity);
+        // Nos propres balles sont toujours visibles
+        let visible = settings.show_balls
+            && other_ball.is_none_or(|other_ball| {
+                settings.visibility.shows(
+                    &maze,
+                    (player_transform.translation, player.id),
+                    (transform.translation, other_ball.id),
+                )
+            });
+        let look = MarkerLook {
+            size: ball_size,
+            position: minimap_position(&maze, transform.translation, tile_size, ball_size),
+            color: Color::YELLOW,
+            visible,
+        };
+        place_minimap_marker(
+            &mut commands,
+            map,
+            &mut minimap_entities.balls,
+            ball_entity,
+            &mut marker_query,
+            look,
+            MinimapBall,
+        );
+    }
+
+    despawn_missing_markers(&mut commands, &mut minimap_entities.balls, &present);
+}
+
+// Les objets sont fixes: ils suivent le brouillard de guerre mais pas la ligne de vue
+pub fn update_minimap_items(
+    mut commands: Commands,
+    maze: Res<Maze>,
+    settings: Res<MinimapSettings>,
+    view: Res<MinimapView>,
+    explored: Res<ExploredCells>,
+    mut minimap_entities: ResMut<MinimapEntities>,
+    item_query: Query<(&Item, &Visibility), Without<MinimapItem>>,
+    mut marker_query: Query<(&mut Style, &mut Visibility), With<MinimapItem>>,
+) {
+    let Some(map) = minimap_entities.map else {
+        return;
+    };
+    let tile_size = view.tile_size;
+    let item_size = tile_size * 0.5;
+
+    for (item, item_visibility) in item_query.iter() {
+        let discovered = !settings.fog_of_war
+            || maze
+                .cell_at(item.position)
+                .is_some_and(|(row, col)| explored.is_explored(row, col));
+        let visible = discovered && *item_visibility != Visibility::Hidden;
+        let look = MarkerLook {
+            size: item_size,
+            position: minimap_position(&maze, item.position, tile_size, item_size),
+            color: item_color(item.kind),
+            visible,
+        };
+        place_minimap_marker(
+            &mut commands,
+            map,
+            &mut minimap_entities.items,
+            item.id,
+            &mut marker_query,
+            look,
+            MinimapItem,
+        );
     }
 }
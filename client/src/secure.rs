@@ -0,0 +1,201 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use std::time::Duration;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+// Copie côté client de server/src/secure.rs: poignée de main et paquets scellés.
+// server/tests/client_parity.rs inclut ce fichier et le fait dialoguer avec le serveur.
+pub const HELLO: u8 = 0xE1;
+pub const HELLO_REPLY: u8 = 0xE2;
+pub const SEALED: u8 = 0xE3;
+// Type, puis compteur du paquet qui sert de nonce
+const SEALED_HEADER: usize = 1 + 8;
+const KEY_INFO: &[u8] = b"maze-wars transport v1";
+// Attente de la réponse à un HELLO avant de le renvoyer
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+pub const HANDSHAKE_ATTEMPTS: u32 = 3;
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Fenêtre glissante de 64 paquets: chaque compteur n'est accepté qu'une fois.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayWindow {
+    highest: Option<u64>,
+    // Bit n: le paquet `highest - n` a déjà été reçu
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// Returns true and remembers `counter` if it is new and not too old.
+    pub fn accept(&mut self, counter: u64) -> bool {
+        let Some(highest) = self.highest else {
+            self.highest = Some(counter);
+            self.seen = 1;
+            return true;
+        };
+        if counter > highest {
+            let shift = counter - highest;
+            self.seen = if shift >= 64 { 0 } else { self.seen << shift };
+            self.seen |= 1;
+            self.highest = Some(counter);
+            return true;
+        }
+        let age = highest - counter;
+        if age >= 64 || self.seen & (1 << age) != 0 {
+            return false;
+        }
+        self.seen |= 1 << age;
+        true
+    }
+}
+
+/// Clés d'une session: une par sens, pour ne jamais réutiliser un nonce.
+pub struct Session {
+    send: ChaCha20Poly1305,
+    receive: ChaCha20Poly1305,
+    next_counter: u64,
+    replay: ReplayWindow,
+}
+
+impl Session {
+    fn new(send: [u8; 32], receive: [u8; 32]) -> Session {
+        Session {
+            send: ChaCha20Poly1305::new(Key::from_slice(&send)),
+            receive: ChaCha20Poly1305::new(Key::from_slice(&receive)),
+            next_counter: 0,
+            replay: ReplayWindow::default(),
+        }
+    }
+
+    /// Encrypts `plaintext` into a sealed datagram.
+    pub fn seal(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let counter = self.next_counter;
+        self.next_counter += 1;
+        let mut packet = Vec::with_capacity(SEALED_HEADER + plaintext.len() + 16);
+        packet.push(SEALED);
+        packet.extend_from_slice(&counter.to_be_bytes());
+        let ciphertext = self
+            .send
+            .encrypt(&nonce(counter), Payload { msg: plaintext, aad: &packet[..SEALED_HEADER] })
+            .expect("encryption cannot fail for in-memory buffers");
+        packet.extend_from_slice(&ciphertext);
+        packet
+    }
+
+    /// Decrypts a sealed datagram; None if it was forged, corrupted or already received.
+    pub fn open(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        if packet.len() < SEALED_HEADER || packet[0] != SEALED {
+            return None;
+        }
+        let counter = u64::from_be_bytes(packet[1..SEALED_HEADER].try_into().ok()?);
+        let plaintext = self
+            .receive
+            .decrypt(&nonce(counter), Payload { msg: &packet[SEALED_HEADER..], aad: &packet[..SEALED_HEADER] })
+            .ok()?;
+        // Vérifier le compteur après l'authentification: un faux paquet ne déplace pas la fenêtre
+        if !self.replay.accept(counter) {
+            return None;
+        }
+        Some(plaintext)
+    }
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    *Nonce::from_slice(&nonce)
+}
+
+// Deux secrets Diffie-Hellman: éphémère/éphémère et éphémère/clé du serveur
+fn derive_keys(client: &PublicKey, server_static: &PublicKey, server_ephemeral: &PublicKey, secrets: [[u8; 32]; 2]) -> ([u8; 32], [u8; 32]) {
+    let salt = [client.as_bytes().as_slice(), server_static.as_bytes().as_slice(), server_ephemeral.as_bytes().as_slice()].concat();
+    let hkdf = Hkdf::<Sha256>::new(Some(salt.as_slice()), &secrets.concat());
+    let mut keys = [0u8; 64];
+    hkdf.expand(KEY_INFO, &mut keys).expect("64 bytes is a valid HKDF output length");
+    let (client_to_server, server_to_client) = keys.split_at(32);
+    (client_to_server.try_into().unwrap(), server_to_client.try_into().unwrap())
+}
+
+/// Côté client: secret à usage unique gardé entre le HELLO et la réponse du serveur.
+/// Il sert à deux échanges (clé éphémère et clé fixe du serveur), d'où un `StaticSecret`.
+pub struct ClientHandshake {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl ClientHandshake {
+    pub fn new() -> ClientHandshake {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        ClientHandshake { secret, public }
+    }
+
+    pub fn hello(&self) -> Vec<u8> {
+        let mut hello = vec![HELLO];
+        hello.extend_from_slice(self.public.as_bytes());
+        hello
+    }
+
+    /// Checks the server's reply against the pinned key, if any, and returns the session
+    /// and the server's public key.
+    pub fn finish(self, reply: &[u8], pinned: Option<&[u8; 32]>) -> Result<(Session, [u8; 32]), String> {
+        if reply.len() != 65 || reply[0] != HELLO_REPLY {
+            return Err("malformed handshake reply".to_string());
+        }
+        let server_static: [u8; 32] = reply[1..33].try_into().unwrap();
+        let server_ephemeral: [u8; 32] = reply[33..65].try_into().unwrap();
+        if pinned.is_some_and(|pinned| pinned != &server_static) {
+            return Err(format!("server key {} does not match the pinned key", to_hex(&server_static)));
+        }
+        let server_static_key = PublicKey::from(server_static);
+        let server_ephemeral_key = PublicKey::from(server_ephemeral);
+        // Même ordre que sur le serveur
+        let secrets = [
+            *self.secret.diffie_hellman(&server_ephemeral_key).as_bytes(),
+            *self.secret.diffie_hellman(&server_static_key).as_bytes(),
+        ];
+        let (client_to_server, server_to_client) = derive_keys(&self.public, &server_static_key, &server_ephemeral_key, secrets);
+        Ok((Session::new(client_to_server, server_to_client), server_static))
+    }
+}
+
+impl Default for ClientHandshake {
+    fn default() -> Self {
+        ClientHandshake::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_packets_are_accepted_once() {
+        let mut client = Session::new([1; 32], [2; 32]);
+        let mut server = Session::new([2; 32], [1; 32]);
+        let packet = client.seal(b"{\"hello\":1}");
+        assert_eq!(server.open(&packet), Some(b"{\"hello\":1}".to_vec()));
+        assert_eq!(server.open(&packet), None);
+
+        let mut forged = client.seal(b"{}");
+        forged[1] ^= 1;
+        assert_eq!(server.open(&forged), None);
+        assert_eq!(from_hex(&to_hex(&[0, 171, 255])), Some(vec![0, 171, 255]));
+    }
+}
@@ -0,0 +1,220 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{MessageRecieve, SessionId};
+
+pub const DEFAULT_ACCOUNTS_FILE: &str = "accounts.json";
+pub const MIN_PASSWORD_LENGTH: usize = 4;
+// Au plus PASSWORD_RATE_LIMIT connexions avec un mot de passe par IP et par PASSWORD_RATE_WINDOW
+pub const PASSWORD_RATE_LIMIT: usize = 5;
+pub const PASSWORD_RATE_WINDOW: Duration = Duration::from_secs(30);
+// Calculs Argon2 en cours au plus, toutes adresses confondues
+pub const MAX_ACCOUNT_CHECKS: usize = 8;
+
+/// Mots de passe envoyés dans le contenu du message Connect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    pub server_password: Option<String>,
+    pub password: Option<String>,
+    // Réserver le nom avec `password` s'il ne l'est pas encore
+    pub register: bool,
+}
+
+impl Credentials {
+    /// Reads `{"server_password": .., "password": .., "register": true}`; older clients send null.
+    pub fn from_content(content: &Value) -> Credentials {
+        let text = |key: &str| content.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        Credentials {
+            server_password: text("server_password"),
+            password: text("password"),
+            register: content.get("register").and_then(|r| r.as_bool()).unwrap_or(false),
+        }
+    }
+}
+
+/// Comparaison en temps constant, pour ne pas révéler le mot de passe du serveur par la durée.
+pub fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Salted Argon2 hash in PHC format; the salt is stored in the string.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+/// Hash for a new account, once the password is long enough.
+pub fn new_password_hash(password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!("passwords need at least {} characters", MIN_PASSWORD_LENGTH));
+    }
+    hash_password(password)
+}
+
+/// Argon2 work a Connect waits for. `Game` only queues it: `serve` runs it with
+/// `spawn_blocking` so the game loop never hashes.
+#[derive(Debug, Clone)]
+pub struct AccountCheck {
+    pub message: MessageRecieve,
+    pub src: SocketAddr,
+    pub session: Option<SessionId>,
+    // Empreinte du nom demandé, à comparer au mot de passe donné
+    pub hash: Option<String>,
+    // Mot de passe à hacher pour réserver le nom
+    pub register: Option<String>,
+}
+
+/// What an `AccountCheck` found, handed back to `Game::account_checked`.
+#[derive(Debug, Clone)]
+pub struct CheckedAccount {
+    pub message: MessageRecieve,
+    pub src: SocketAddr,
+    pub session: Option<SessionId>,
+    // Le nom est libre ou le mot de passe est le bon
+    pub allowed: bool,
+    pub new_hash: Option<Result<String, String>>,
+}
+
+impl AccountCheck {
+    pub fn run(self) -> CheckedAccount {
+        let password = Credentials::from_content(&self.message.content).password;
+        let allowed = match &self.hash {
+            None => true,
+            Some(hash) => password.is_some_and(|password| verify_password(&password, hash)),
+        };
+        CheckedAccount {
+            new_hash: self.register.as_deref().map(new_password_hash),
+            message: self.message,
+            src: self.src,
+            session: self.session,
+            allowed,
+        }
+    }
+}
+
+/// Registered names and their password hashes, kept in a local JSON file.
+/// Without a file (`Accounts::default()`) they only live in memory.
+#[derive(Debug, Clone, Default)]
+pub struct Accounts {
+    path: Option<PathBuf>,
+    hashes: BTreeMap<String, String>,
+}
+
+impl Accounts {
+    /// Loads `path`; a missing file means no account yet.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Accounts> {
+        let path = path.into();
+        let hashes = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Accounts { path: Some(path), hashes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.hashes.contains_key(name)
+    }
+
+    pub fn hash_of(&self, name: &str) -> Option<&str> {
+        self.hashes.get(name).map(String::as_str)
+    }
+
+    /// Registers `name` with a hash from `new_password_hash`, computed beforehand.
+    pub fn add(&mut self, name: &str, hash: String) -> Result<(), String> {
+        if self.is_registered(name) {
+            return Err(format!("{} is already registered", name));
+        }
+        self.hashes.insert(name.to_string(), hash);
+        self.save().map_err(|e| format!("failed to save accounts: {}", e))
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Result<bool, String> {
+        if self.hashes.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save().map_err(|e| format!("failed to save accounts: {}", e))?;
+        Ok(true)
+    }
+
+    // Même écriture que les stats: fichier temporaire puis renommage
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_string_pretty(&self.hashes)?)?;
+        std::fs::rename(&temporary, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn hashes_are_salted_and_verified() {
+        let first = hash_password("hunter22").unwrap();
+        let second = hash_password("hunter22").unwrap();
+        assert_ne!(first, second);
+        assert!(verify_password("hunter22", &first));
+        assert!(!verify_password("hunter23", &first));
+        assert!(!verify_password("hunter22", "not a hash"));
+    }
+
+    #[test]
+    fn registered_names_need_their_password_and_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("maze-accounts-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut accounts = Accounts::load(&path).unwrap();
+        assert!(!accounts.is_registered("alogou"));
+        accounts.add("alogou", new_password_hash("maze-wars").unwrap()).unwrap();
+        assert!(accounts.add("alogou", new_password_hash("other").unwrap()).is_err());
+        assert!(new_password_hash("abc").is_err());
+
+        let accounts = Accounts::load(&path).unwrap();
+        let hash = accounts.hash_of("alogou").unwrap();
+        assert!(!verify_password("maze", hash));
+        assert!(verify_password("maze-wars", hash));
+        // Le fichier ne contient que des empreintes
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("maze-wars"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn credentials_come_from_the_connect_content() {
+        assert_eq!(Credentials::from_content(&Value::Null), Credentials::default());
+        let credentials = Credentials::from_content(&json!({ "server_password": "lan", "password": "pw", "register": true }));
+        assert_eq!(credentials.server_password.as_deref(), Some("lan"));
+        assert_eq!(credentials.password.as_deref(), Some("pw"));
+        assert!(credentials.register);
+        assert!(same_secret("lan", "lan"));
+        assert!(!same_secret("lan", "lap"));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::{NetConditions, Player};
+
+pub const ADMIN_HELP: &str = "\
+commands:
+  status                 list players with address, life and ping
+  info                   server name, map and player count, as shown in LAN browsers
+  kick <name>            disconnect a player
+  ban <name|ip>          ban a player (name and address) or an address
+  unban <name|ip>        lift a ban
+  level <1-3>            change the map; players must reconnect
+  restart                restart the round: full life, weapons and items
+  maxplayers <n>         set the maximum number of players
+  say <message>          broadcast a server message in the chat
+  bots <n>               keep up to n bots in the free slots
+  stats [name]           current match and career stats (kills, deaths, accuracy...)
+  password <pw|off>      require a password to join, or remove it
+  register <name> <pw>   reserve a name with an account password
+  unregister <name>      delete an account
+  netsim [spec|off]      show or set simulated latency, jitter, loss, duplication
+                         and reordering, e.g. latency=80ms,jitter=20ms,loss=5%
+  help                   show this help";
+
+/// Commande tapée sur l'entrée standard ou sur le port d'administration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminCommand {
+    Help,
+    Status,
+    Info,
+    Kick(String),
+    Ban(String),
+    Unban(String),
+    Level(i32),
+    Restart,
+    MaxPlayers(usize),
+    Say(String),
+    Bots(usize),
+    NetSim(Option<NetConditions>),
+    Stats(Option<String>),
+    Password(Option<String>),
+    Register(String, String),
+    Unregister(String),
+}
+
+impl AdminCommand {
+    pub fn parse(line: &str) -> Result<AdminCommand, String> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+        let required = |usage: &str| {
+            if argument.is_empty() {
+                Err(format!("usage: {}", usage))
+            } else {
+                Ok(argument.to_string())
+            }
+        };
+        match name {
+            "help" | "?" => Ok(AdminCommand::Help),
+            "status" | "players" => Ok(AdminCommand::Status),
+            "info" => Ok(AdminCommand::Info),
+            "kick" => required("kick <name>").map(AdminCommand::Kick),
+            "ban" => required("ban <name|ip>").map(AdminCommand::Ban),
+            "unban" => required("unban <name|ip>").map(AdminCommand::Unban),
+            "level" | "map" => match argument.parse() {
+                Ok(level) if (1..=3).contains(&level) => Ok(AdminCommand::Level(level)),
+                _ => Err("usage: level <1-3>".to_string()),
+            },
+            "restart" => Ok(AdminCommand::Restart),
+            "maxplayers" => match argument.parse() {
+                Ok(max) if max > 0 => Ok(AdminCommand::MaxPlayers(max)),
+                _ => Err("usage: maxplayers <n>".to_string()),
+            },
+            "say" => required("say <message>").map(AdminCommand::Say),
+            "bots" => match argument.parse() {
+                Ok(count) => Ok(AdminCommand::Bots(count)),
+                _ => Err("usage: bots <n>".to_string()),
+            },
+            "password" => match argument {
+                "" => Err("usage: password <pw|off>".to_string()),
+                "off" => Ok(AdminCommand::Password(None)),
+                password => Ok(AdminCommand::Password(Some(password.to_string()))),
+            },
+            "register" => match argument.split_once(char::is_whitespace) {
+                Some((name, password)) if !password.trim().is_empty() => {
+                    Ok(AdminCommand::Register(name.to_string(), password.trim().to_string()))
+                }
+                _ => Err("usage: register <name> <password>".to_string()),
+            },
+            "unregister" => required("unregister <name>").map(AdminCommand::Unregister),
+            "stats" if argument.is_empty() => Ok(AdminCommand::Stats(None)),
+            "stats" => Ok(AdminCommand::Stats(Some(argument.to_string()))),
+            "netsim" if argument.is_empty() => Ok(AdminCommand::NetSim(None)),
+            "netsim" => NetConditions::parse(argument)
+                .map(|conditions| AdminCommand::NetSim(Some(conditions)))
+                .map_err(|error| format!("netsim: {}", error)),
+            "" => Err("empty command, type `help`".to_string()),
+            other => Err(format!("unknown command `{}`, type `help`", other)),
+        }
+    }
+}
+
+/// Adresse IP d'un joueur, sans le port.
+pub fn ip_of(address: &str) -> Option<IpAddr> {
+    address.parse::<SocketAddr>().ok().map(|address| address.ip())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BanList {
+    names: HashSet<String>,
+    ips: HashSet<IpAddr>,
+}
+
+impl BanList {
+    /// Bans a connected player's name and address, or a bare address.
+    pub fn ban(&mut self, target: &str, players: &HashMap<String, Player>) -> Result<String, String> {
+        if let Some(player) = players.get(target) {
+            self.names.insert(player.player_name.clone());
+            // Les bots partagent l'adresse 0.0.0.0: seul leur nom est banni
+            if let Some(ip) = ip_of(&player.ip_address).filter(|ip| !ip.is_unspecified()) {
+                self.ips.insert(ip);
+            }
+            Ok(format!("banned {} ({})", player.player_name, player.ip_address))
+        } else if let Ok(ip) = target.parse::<IpAddr>() {
+            self.ips.insert(ip);
+            Ok(format!("banned {}", ip))
+        } else {
+            // Nom d'un joueur absent: il sera refusé à sa prochaine connexion
+            self.names.insert(target.to_string());
+            Ok(format!("banned name {}", target))
+        }
+    }
+
+    pub fn unban(&mut self, target: &str) -> bool {
+        let name = self.names.remove(target);
+        let ip = target.parse::<IpAddr>().is_ok_and(|ip| self.ips.remove(&ip));
+        name || ip
+    }
+
+    pub fn is_banned(&self, name: &str, address: &str) -> bool {
+        self.names.contains(name) || ip_of(address).is_some_and(|ip| self.ips.contains(&ip))
+    }
+}
+
+/// One line per player for the `status` command.
+pub fn status_table(players: &HashMap<String, Player>, level: i32, max_players: usize) -> String {
+    let mut lines = vec![format!(
+        "level {}, {}/{} players",
+        level,
+        players.len(),
+        max_players
+    )];
+    let mut sorted = players.values().collect::<Vec<_>>();
+    sorted.sort_by_key(|player| player.id);
+    for player in sorted {
+        lines.push(format!(
+            "  #{:<3} {:<16} {:<22} life {:<3} ping {} ms",
+            player.id,
+            player.player_name,
+            player.ip_address,
+            player.life,
+            player.rtt.as_millis()
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,359 @@
+//! Essaim de clients UDP simulés pour mesurer la charge que le serveur supporte:
+//! `cargo run --release --bin swarm -- --server=127.0.0.1:8081 --clients=300`
+
+use bevy::math::{Quat, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+use server::*;
+
+// Un ping sans réponse après ce délai est compté comme perdu
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Options {
+    server: String,
+    clients: usize,
+    // Nouveaux clients par seconde, pour voir à partir de combien le serveur décroche
+    spawn_rate: f64,
+    duration: Duration,
+    // Messages Action par seconde et par client
+    action_rate: f64,
+    fire_interval: Duration,
+    prefix: String,
+}
+
+impl Options {
+    fn from_args() -> Options {
+        let value = |prefix: &str| std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(str::to_string));
+        let number = |prefix: &str, default: f64| value(prefix).and_then(|v| v.parse::<f64>().ok()).unwrap_or(default);
+        Options {
+            server: value("--server=").unwrap_or_else(|| "127.0.0.1:8081".to_string()),
+            clients: number("--clients=", 100.0) as usize,
+            spawn_rate: number("--spawn-rate=", 20.0).max(0.1),
+            duration: Duration::from_secs_f64(number("--duration=", 60.0)),
+            action_rate: number("--action-rate=", 20.0).max(0.1),
+            fire_interval: Duration::from_secs_f64(number("--fire-interval=", 1.0).max(0.01)),
+            prefix: value("--prefix=").unwrap_or_else(|| "swarm".to_string()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    connected: AtomicUsize,
+    refused: AtomicUsize,
+    deaths: AtomicUsize,
+    packets_out: AtomicU64,
+    packets_in: AtomicU64,
+    bytes_in: AtomicU64,
+    pings: AtomicU64,
+    lost_pings: AtomicU64,
+    // RTT des pongs reçus depuis le dernier rapport, en millisecondes
+    rtts: Mutex<Vec<f64>>,
+    refusal: Mutex<Option<String>>,
+}
+
+struct Client {
+    socket: UdpSocket,
+    name: String,
+    id: usize,
+    stats: Arc<Stats>,
+}
+
+impl Client {
+    async fn send(&self, message_type: MessageType, content: Value) {
+        let message = MessageRecieve {
+            message_type,
+            player_name: self.name.clone(),
+            content,
+            id_player: self.id,
+        };
+        let data = serde_json::to_vec(&message).unwrap();
+        if self.socket.send(&data).await.is_ok() {
+            self.stats.packets_out.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> Option<MessageSended> {
+        let amt = self.socket.recv(buf).await.ok()?;
+        self.stats.packets_in.fetch_add(1, Ordering::Relaxed);
+        self.stats.bytes_in.fetch_add(amt as u64, Ordering::Relaxed);
+        serde_json::from_slice(&buf[..amt]).ok()
+    }
+
+    // Se connecte et renvoie l'id et le niveau, ou None si le serveur refuse ou ne répond pas
+    async fn join(&mut self, buf: &mut [u8]) -> Option<(usize, i32)> {
+        self.send(MessageType::Connect, Value::Null).await;
+        let deadline = tokio::time::sleep(CONNECT_TIMEOUT);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = &mut deadline => return None,
+                message = self.recv(buf) => match message {
+                    Some(message) if message.message_type == MessageType::ConnectSuccessfull => {
+                        self.id = message.id_player;
+                        return Some((message.id_player, message.level));
+                    }
+                    Some(message) if message.message_type == MessageType::ConnectFailed => {
+                        *self.stats.refusal.lock().unwrap() = message.content.as_str().map(str::to_string);
+                        return None;
+                    }
+                    _ => continue,
+                },
+            }
+        }
+    }
+}
+
+// Marche au hasard de case en case, sans jamais traverser un mur
+struct Walker {
+    position: Vec3,
+    target: (usize, usize),
+    facing: Vec3,
+}
+
+impl Walker {
+    fn new(maze: &Maze, rng: &mut StdRng) -> Walker {
+        let cells = open_cells(maze);
+        let cell = cells[rng.gen_range(0..cells.len())];
+        Walker {
+            position: cell_center(maze, cell),
+            target: cell,
+            facing: Vec3::NEG_Z,
+        }
+    }
+
+    fn step(&mut self, maze: &Maze, dt: f32, rng: &mut StdRng) {
+        let offset = cell_center(maze, self.target) - self.position;
+        let distance = offset.length();
+        let travel = BOT_SPEED * dt;
+        if distance > travel {
+            self.facing = offset / distance;
+            self.position += self.facing * travel;
+            return;
+        }
+        self.position += offset;
+        let (row, col) = (self.target.0 as i32, self.target.1 as i32);
+        let neighbours = [(row - 1, col), (row + 1, col), (row, col - 1), (row, col + 1)]
+            .into_iter()
+            .filter(|&(row, col)| !maze.is_wall(row, col))
+            .collect::<Vec<_>>();
+        if !neighbours.is_empty() {
+            let (row, col) = neighbours[rng.gen_range(0..neighbours.len())];
+            self.target = (row as usize, col as usize);
+        }
+    }
+}
+
+async fn run_client(index: usize, options: Arc<Options>, stats: Arc<Stats>, stop: Instant) {
+    let Ok(socket) = UdpSocket::bind("0.0.0.0:0").await else {
+        return;
+    };
+    if socket.connect(&options.server).await.is_err() {
+        return;
+    }
+    let mut client = Client {
+        socket,
+        name: format!("{}{}", options.prefix, index),
+        id: 0,
+        stats: stats.clone(),
+    };
+    let mut rng = StdRng::seed_from_u64(index as u64);
+    let mut buf = vec![0; 65536];
+
+    // Un client tué se reconnecte sous le même nom pour garder la charge constante
+    'session: while Instant::now() < stop {
+        let Some((_, level)) = client.join(&mut buf).await else {
+            stats.refused.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        stats.connected.fetch_add(1, Ordering::Relaxed);
+        let maze = Maze::for_level(level);
+        let mut walker = Walker::new(&maze, &mut rng);
+        let mut pending: HashMap<u64, Instant> = HashMap::new();
+        let mut next_seq = 0;
+        let mut rtt_ms = 0.0;
+
+        let action_period = Duration::from_secs_f64(1.0 / options.action_rate);
+        let mut action = tokio::time::interval(action_period);
+        let mut fire = tokio::time::interval(options.fire_interval);
+        let mut ping = tokio::time::interval(Duration::from_secs(1));
+        let end = tokio::time::sleep_until(stop.into());
+        tokio::pin!(end);
+
+        loop {
+            tokio::select! {
+                _ = &mut end => break 'session,
+                _ = action.tick() => {
+                    walker.step(&maze, action_period.as_secs_f32(), &mut rng);
+                    let rotation = Quat::from_rotation_y(f32::atan2(-walker.facing.x, -walker.facing.z));
+                    client.send(MessageType::Action, json!({ "movement": walker.position, "rotation": rotation })).await;
+                }
+                _ = fire.tick() => {
+                    let yaw = rng.gen_range(0.0..std::f32::consts::TAU);
+                    let direction = Quat::from_rotation_y(yaw) * Vec3::NEG_Z;
+                    client.send(MessageType::Shoot, json!({ "origin": walker.position, "direction": direction })).await;
+                }
+                _ = ping.tick() => {
+                    let now = Instant::now();
+                    let before = pending.len();
+                    pending.retain(|_, sent_at| now.duration_since(*sent_at) < PING_TIMEOUT);
+                    stats.lost_pings.fetch_add((before - pending.len()) as u64, Ordering::Relaxed);
+                    pending.insert(next_seq, now);
+                    client.send(MessageType::Ping, json!({ "seq": next_seq, "rtt_ms": rtt_ms })).await;
+                    stats.pings.fetch_add(1, Ordering::Relaxed);
+                    next_seq += 1;
+                }
+                message = client.recv(&mut buf) => {
+                    let Some(message) = message else {
+                        continue;
+                    };
+                    match message.message_type {
+                        MessageType::Pong => {
+                            let seq = message.content.get("seq").and_then(|s| s.as_u64());
+                            if let Some(sent_at) = seq.and_then(|seq| pending.remove(&seq)) {
+                                rtt_ms = sent_at.elapsed().as_secs_f64() * 1000.0;
+                                stats.rtts.lock().unwrap().push(rtt_ms);
+                            }
+                        }
+                        MessageType::PlayerDeath => {
+                            stats.deaths.fetch_add(1, Ordering::Relaxed);
+                            client.send(MessageType::OtherDeadPlayer, Value::Null).await;
+                            client.send(MessageType::Disconnect, Value::Null).await;
+                            stats.connected.fetch_sub(1, Ordering::Relaxed);
+                            continue 'session;
+                        }
+                        MessageType::Kicked => {
+                            stats.connected.fetch_sub(1, Ordering::Relaxed);
+                            return;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    client.send(MessageType::Disconnect, Value::Null).await;
+    stats.connected.fetch_sub(1, Ordering::Relaxed);
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Arc::new(Options::from_args());
+    let stats = Arc::new(Stats::default());
+    let started = Instant::now();
+    let stop = started + options.duration;
+    println!(
+        "Swarm of {} clients against {} for {:?} ({} new clients/s, {} actions/s each, one shot every {:?})",
+        options.clients, options.server, options.duration, options.spawn_rate, options.action_rate, options.fire_interval
+    );
+
+    {
+        let (options, stats) = (options.clone(), stats.clone());
+        tokio::spawn(async move {
+            let spawn_period = Duration::from_secs_f64(1.0 / options.spawn_rate);
+            for index in 0..options.clients {
+                tokio::spawn(run_client(index, options.clone(), stats.clone(), stop));
+                tokio::time::sleep(spawn_period).await;
+            }
+        });
+    }
+
+    // Rapport chaque seconde; le serveur décroche quand la latence s'envole ou que les pings se perdent
+    let mut report = tokio::time::interval(Duration::from_secs(1));
+    report.tick().await;
+    let (mut last_out, mut last_in, mut last_pings, mut last_lost) = (0, 0, 0, 0);
+    let mut all_rtts = Vec::new();
+    let mut baseline: Option<f64> = None;
+    let mut falling_behind: Option<(Duration, usize)> = None;
+    let (mut peak_out, mut peak_in) = (0, 0);
+    while Instant::now() < stop + Duration::from_millis(500) {
+        report.tick().await;
+        let packets_out = stats.packets_out.load(Ordering::Relaxed);
+        let packets_in = stats.packets_in.load(Ordering::Relaxed);
+        let pings = stats.pings.load(Ordering::Relaxed);
+        let lost = stats.lost_pings.load(Ordering::Relaxed);
+        let mut rtts = std::mem::take(&mut *stats.rtts.lock().unwrap());
+        rtts.sort_by(f64::total_cmp);
+        let connected = stats.connected.load(Ordering::Relaxed);
+        let loss = if pings > last_pings {
+            (lost - last_lost) as f64 / (pings - last_pings) as f64 * 100.0
+        } else {
+            0.0
+        };
+        let (p50, p95, p99) = (percentile(&rtts, 0.5), percentile(&rtts, 0.95), percentile(&rtts, 0.99));
+        peak_out = peak_out.max(packets_out - last_out);
+        peak_in = peak_in.max(packets_in - last_in);
+        println!(
+            "[{:>4}s] clients {:>4}/{}  out {:>6}/s  in {:>7}/s  rtt p50 {:>6.1} ms  p95 {:>6.1} ms  p99 {:>6.1} ms  loss {:>5.1}%",
+            started.elapsed().as_secs(),
+            connected,
+            options.clients,
+            packets_out - last_out,
+            packets_in - last_in,
+            p50,
+            p95,
+            p99,
+            loss,
+        );
+
+        if !rtts.is_empty() {
+            let base = *baseline.get_or_insert(p50);
+            baseline = Some(base.min(p50));
+            let lagging = p95 > (base * 4.0).max(base + 50.0) || loss > 5.0;
+            if lagging && falling_behind.is_none() {
+                falling_behind = Some((started.elapsed(), connected));
+                println!("  -> server falling behind with {} clients", connected);
+            }
+        }
+        all_rtts.extend(rtts);
+        (last_out, last_in, last_pings, last_lost) = (packets_out, packets_in, pings, lost);
+    }
+
+    all_rtts.sort_by(f64::total_cmp);
+    println!();
+    println!("Summary");
+    println!("  clients refused: {}", stats.refused.load(Ordering::Relaxed));
+    if let Some(reason) = stats.refusal.lock().unwrap().as_ref() {
+        println!("  last refusal: {}", reason);
+    }
+    println!("  deaths: {}", stats.deaths.load(Ordering::Relaxed));
+    println!(
+        "  packets: {} sent, {} received ({:.1} MB), peak {}/s out and {}/s in",
+        last_out,
+        last_in,
+        stats.bytes_in.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        peak_out,
+        peak_in,
+    );
+    println!(
+        "  rtt: p50 {:.1} ms, p95 {:.1} ms, p99 {:.1} ms, max {:.1} ms",
+        percentile(&all_rtts, 0.5),
+        percentile(&all_rtts, 0.95),
+        percentile(&all_rtts, 0.99),
+        all_rtts.last().copied().unwrap_or(0.0),
+    );
+    println!(
+        "  ping loss: {:.1}%",
+        if last_pings > 0 { last_lost as f64 / last_pings as f64 * 100.0 } else { 0.0 }
+    );
+    match falling_behind {
+        Some((at, clients)) => println!("  server fell behind after {:?} with {} clients", at, clients),
+        None => println!("  server kept up"),
+    }
+}
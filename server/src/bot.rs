@@ -0,0 +1,425 @@
+use bevy::math::{Quat, Vec3};
+use rand::Rng;
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::{apply_spread, parse_vec3, FireMode, Maze, MessageRecieve, MessageSended, MessageType, Player, Weapon, HIT_RADIUS};
+
+// Même vitesse que le joueur côté client
+pub const BOT_SPEED: f32 = 3.0;
+pub const BOT_SIGHT_RANGE: f32 = 30.0;
+// Distance à laquelle un bot s'arrête pour tirer sur sa cible
+pub const BOT_ENGAGE_DISTANCE: f32 = 6.0;
+// Erreur de visée d'un bot de niveau 0, en radians
+pub const MAX_AIM_ERROR: f32 = 0.25;
+// Délai avant qu'un bot mort soit remplacé
+pub const BOT_RESPAWN_DELAY: Duration = Duration::from_secs(5);
+// Premier id donné aux bots: les ids des humains restent petits, le client s'en sert comme index
+pub const FIRST_BOT_ID: usize = 1000;
+const REPATH_INTERVAL: Duration = Duration::from_millis(500);
+const PLAYER_HEIGHT: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotSettings {
+    /// Bots wanted on the server; they only take slots no human uses.
+    pub count: usize,
+    /// From 0 (aim error up to `MAX_AIM_ERROR`) to 1 (perfect aim).
+    pub skill: f32,
+    /// Delay between spotting an enemy and the first shot.
+    pub reaction: Duration,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        BotSettings {
+            count: 0,
+            skill: 0.5,
+            reaction: Duration::from_millis(400),
+        }
+    }
+}
+
+/// Adresse fictive d'un bot: les messages qui lui sont destinés ne partent jamais sur le réseau.
+pub fn bot_address(id: usize) -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::UNSPECIFIED, 1 + (id % u16::MAX as usize) as u16))
+}
+
+/// Centre d'une case, à hauteur de joueur.
+pub fn cell_center(maze: &Maze, (row, col): (usize, usize)) -> Vec3 {
+    Vec3::new(col as f32 * maze.cell_size, PLAYER_HEIGHT, row as f32 * maze.cell_size)
+}
+
+pub fn open_cells(maze: &Maze) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    for (row, cells_row) in maze.layout.iter().enumerate() {
+        for col in 0..cells_row.len() {
+            if !maze.is_wall(row as i32, col as i32) {
+                cells.push((row, col));
+            }
+        }
+    }
+    cells
+}
+
+/// Shortest path over the 4-connected grid (BFS), from `from` to `to` included.
+pub fn find_path(maze: &Maze, from: (usize, usize), to: (usize, usize)) -> Option<VecDeque<(usize, usize)>> {
+    if maze.is_wall(from.0 as i32, from.1 as i32) || maze.is_wall(to.0 as i32, to.1 as i32) {
+        return None;
+    }
+    let mut previous = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(cell) = queue.pop_front() {
+        if cell == to {
+            let mut path = VecDeque::from([cell]);
+            let mut current = cell;
+            while current != from {
+                current = previous[&current];
+                path.push_front(current);
+            }
+            return Some(path);
+        }
+        let (row, col) = (cell.0 as i32, cell.1 as i32);
+        for (next_row, next_col) in [(row - 1, col), (row + 1, col), (row, col - 1), (row, col + 1)] {
+            if maze.is_wall(next_row, next_col) {
+                continue;
+            }
+            let next = (next_row as usize, next_col as usize);
+            if let Entry::Vacant(entry) = previous.entry(next) {
+                entry.insert(cell);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Projectile tiré par un bot: le serveur le simule comme le ferait `move_balls` côté client
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotProjectile {
+    pub ball_id: u64,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub bounces_left: u32,
+    pub expires: Instant,
+}
+
+/// Ce qu'un bot sait de la partie, en plus de ce qu'il voit.
+#[derive(Debug, Clone)]
+pub struct Bot {
+    pub address: String,
+    // Ennemi visible poursuivi
+    pub target: Option<usize>,
+    // Depuis quand la cible est visible, pour le temps de réaction
+    pub seen_since: Option<Instant>,
+    // Dernière position connue d'un ennemi, vu ou qui nous a touchés
+    pub last_known: Option<Vec3>,
+    pub path: VecDeque<(usize, usize)>,
+    pub goal: Option<(usize, usize)>,
+    pub repath_at: Option<Instant>,
+    pub facing: Vec3,
+    pub projectiles: Vec<BotProjectile>,
+    pub next_ball: u64,
+    pub dead: bool,
+}
+
+impl Bot {
+    pub fn new(id: usize) -> Bot {
+        Bot {
+            address: bot_address(id).to_string(),
+            target: None,
+            seen_since: None,
+            last_known: None,
+            path: VecDeque::new(),
+            goal: None,
+            repath_at: None,
+            facing: Vec3::NEG_Z,
+            projectiles: Vec::new(),
+            next_ball: 0,
+            dead: false,
+        }
+    }
+
+    /// Reacts to a message the server sent to this bot, like a client would.
+    pub fn observe(&mut self, message: &MessageSended) {
+        match message.message_type {
+            MessageType::Damage => {
+                // Aller voir d'où vient le tir
+                if let Some(from) = message.content.get("from").and_then(parse_vec3) {
+                    self.last_known = Some(from);
+                }
+            }
+            MessageType::PlayerDeath => self.dead = true,
+            _ => {}
+        }
+    }
+
+    /// Decides this tick's moves and shots and returns them as client messages.
+    /// `enemies` are the (id, position) of the other living players.
+    #[allow(clippy::too_many_arguments)]
+    pub fn think(
+        &mut self,
+        me: &Player,
+        enemies: &[(usize, Vec3)],
+        maze: &Maze,
+        weapons: &[Weapon],
+        settings: &BotSettings,
+        now: Instant,
+        dt: f32,
+        rng: &mut impl Rng,
+    ) -> Vec<MessageRecieve> {
+        let mut out = self.move_projectiles(me, enemies, maze, now, dt);
+
+        // Ennemi visible le plus proche, à travers les murs du labyrinthe
+        let visible = enemies
+            .iter()
+            .filter(|(_, position)| {
+                position.distance(me.position) <= BOT_SIGHT_RANGE && maze.line_of_sight(me.position, *position)
+            })
+            .min_by(|a, b| a.1.distance(me.position).total_cmp(&b.1.distance(me.position)))
+            .copied();
+        match visible {
+            Some((id, position)) => {
+                if self.target != Some(id) {
+                    self.target = Some(id);
+                    self.seen_since = Some(now);
+                }
+                self.last_known = Some(position);
+            }
+            None => {
+                self.target = None;
+                self.seen_since = None;
+            }
+        }
+
+        // Déplacement: s'arrêter près d'une cible visible, sinon la poursuivre ou patrouiller
+        let destination = match visible {
+            Some((_, position)) if position.distance(me.position) <= BOT_ENGAGE_DISTANCE => None,
+            Some((_, position)) => maze.cell_at(position),
+            None => match self.last_known.and_then(|position| maze.cell_at(position)) {
+                Some(cell) if Some(cell) != maze.cell_at(me.position) => Some(cell),
+                _ => {
+                    self.last_known = None;
+                    self.patrol_goal(maze, me.position, rng)
+                }
+            },
+        };
+        match destination {
+            Some(cell) => self.plan(maze, me.position, cell, now),
+            None => self.path.clear(),
+        }
+        let position = self.steer(maze, me.position, dt);
+        if let Some((_, target)) = visible {
+            self.facing = Vec3::new(target.x - position.x, 0.0, target.z - position.z).normalize_or_zero();
+        }
+        if position != me.position || visible.is_some() {
+            let rotation = Quat::from_rotation_y(f32::atan2(-self.facing.x, -self.facing.z));
+            out.push(self.message(me, MessageType::Action, json!({ "movement": position, "rotation": rotation })));
+        }
+
+        // Tir après le temps de réaction, avec les mêmes règles d'arme que les joueurs
+        let ready = self.seen_since.is_some_and(|since| now.duration_since(since) >= settings.reaction);
+        if let (Some((target, target_position)), true) = (visible, ready) {
+            out.extend(self.fire(me, position, target, target_position, weapons, settings, now, rng));
+        }
+        out
+    }
+
+    // Case au hasard à rejoindre quand il n'y a rien d'autre à faire
+    fn patrol_goal(&self, maze: &Maze, position: Vec3, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        if self.goal.is_some() && !self.path.is_empty() {
+            return self.goal;
+        }
+        let cells = open_cells(maze);
+        let here = maze.cell_at(position);
+        let candidates = cells.into_iter().filter(|cell| Some(*cell) != here).collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(candidates[rng.gen_range(0..candidates.len())])
+    }
+
+    fn plan(&mut self, maze: &Maze, position: Vec3, goal: (usize, usize), now: Instant) {
+        let stale = self.repath_at.is_none_or(|at| now >= at);
+        if self.goal == Some(goal) && !self.path.is_empty() && !stale {
+            return;
+        }
+        self.goal = Some(goal);
+        self.repath_at = Some(now + REPATH_INTERVAL);
+        // Le chemin part de la case courante: le bot revient d'abord à son centre
+        self.path = maze
+            .cell_at(position)
+            .and_then(|start| find_path(maze, start, goal))
+            .unwrap_or_default();
+    }
+
+    // Avance de centre de case en centre de case: les murs ne sont jamais traversés
+    fn steer(&mut self, maze: &Maze, position: Vec3, dt: f32) -> Vec3 {
+        let mut position = position;
+        let mut remaining = BOT_SPEED * dt;
+        while remaining > 0.0 {
+            let Some(&next) = self.path.front() else {
+                break;
+            };
+            let offset = cell_center(maze, next) - position;
+            let distance = offset.length();
+            if distance > 0.0 {
+                self.facing = offset / distance;
+            }
+            if distance <= remaining {
+                position += offset;
+                remaining -= distance;
+                self.path.pop_front();
+            } else {
+                position += offset / distance * remaining;
+                remaining = 0.0;
+            }
+        }
+        position
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn fire(
+        &mut self,
+        me: &Player,
+        origin: Vec3,
+        target: usize,
+        target_position: Vec3,
+        weapons: &[Weapon],
+        settings: &BotSettings,
+        now: Instant,
+        rng: &mut impl Rng,
+    ) -> Vec<MessageRecieve> {
+        let weapon = &weapons[me.weapon.current];
+        if me.weapon.current_ammo(weapons, now) == 0 {
+            if me.weapon.reload_until.is_none() {
+                return vec![self.message(me, MessageType::Reload, Value::Null)];
+            }
+            return Vec::new();
+        }
+        // Le serveur refuserait le tir: inutile de l'envoyer
+        if me.weapon.clone().try_fire(weapons, now, None).is_err() {
+            return Vec::new();
+        }
+        let aim_error = (1.0 - settings.skill.clamp(0.0, 1.0)) * MAX_AIM_ERROR;
+        let direction = apply_spread((target_position - origin).normalize_or_zero(), aim_error, rng);
+
+        if weapon.fire_mode == FireMode::Hitscan {
+            // La dispersion de l'arme est tirée par le serveur
+            return vec![self.message(
+                me,
+                MessageType::Shoot,
+                json!({ "origin": origin, "direction": direction, "target": target }),
+            )];
+        }
+        let direction = apply_spread(direction, weapon.spread, rng);
+        let ball_id = self.next_ball;
+        self.next_ball += 1;
+        self.projectiles.push(BotProjectile {
+            ball_id,
+            position: origin,
+            velocity: direction * weapon.projectile_speed,
+            bounces_left: weapon.ricochets,
+            expires: now + Duration::from_secs_f32(weapon.projectile_lifetime),
+        });
+        vec![self.message(
+            me,
+            MessageType::Shoot,
+            json!({ "origin": origin, "direction": direction, "ball_id": ball_id }),
+        )]
+    }
+
+    // Déplace les projectiles du bot et signale leurs touches, comme le client
+    fn move_projectiles(
+        &mut self,
+        me: &Player,
+        enemies: &[(usize, Vec3)],
+        maze: &Maze,
+        now: Instant,
+        dt: f32,
+    ) -> Vec<MessageRecieve> {
+        let mut out = Vec::new();
+        let mut projectiles = std::mem::take(&mut self.projectiles);
+        projectiles.retain_mut(|ball| {
+            let previous = ball.position;
+            let next = previous + ball.velocity * dt;
+            ball.position = next;
+            let mut destroyed = now >= ball.expires;
+            if let Some(hit) = maze.raycast(previous, next) {
+                if ball.bounces_left > 0 && hit.normal != Vec3::ZERO {
+                    ball.bounces_left -= 1;
+                    ball.velocity -= 2.0 * ball.velocity.dot(hit.normal) * hit.normal;
+                    ball.position = hit.point + hit.normal * 0.01;
+                } else {
+                    destroyed = true;
+                }
+            }
+            if let Some(&(victim, _)) = enemies
+                .iter()
+                .find(|(_, position)| position.distance(ball.position) < HIT_RADIUS)
+            {
+                let mut hit = self.message(
+                    me,
+                    MessageType::UpdateLife,
+                    json!({ "ball_id": ball.ball_id, "point": ball.position }),
+                );
+                hit.id_player = victim;
+                out.push(hit);
+                destroyed = true;
+            }
+            let content = if destroyed {
+                json!({ "delete_ball": true, "ball_id": ball.ball_id })
+            } else {
+                json!({ "ball_movement": ball.position, "ball_id": ball.ball_id })
+            };
+            out.push(self.message(me, MessageType::Action, content));
+            !destroyed
+        });
+        self.projectiles = projectiles;
+        out
+    }
+
+    fn message(&self, me: &Player, message_type: MessageType, content: Value) -> MessageRecieve {
+        MessageRecieve {
+            message_type,
+            player_name: me.player_name.clone(),
+            content,
+            id_player: me.id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_goes_around_walls() {
+        let maze = Maze::for_level(1);
+        // (2,5) et (2,6) sont des murs entre (2,4) et (2,7)
+        let path = find_path(&maze, (2, 4), (2, 7)).unwrap();
+        assert_eq!(path.front(), Some(&(2, 4)));
+        assert_eq!(path.back(), Some(&(2, 7)));
+        assert!(path.len() > 4);
+        for (a, b) in path.iter().zip(path.iter().skip(1)) {
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+            assert!(!maze.is_wall(b.0 as i32, b.1 as i32));
+        }
+        assert_eq!(find_path(&maze, (2, 4), (0, 0)), None);
+    }
+
+    #[test]
+    fn steering_follows_cell_centers() {
+        let maze = Maze::for_level(1);
+        let mut bot = Bot::new(0);
+        bot.path = find_path(&maze, (3, 4), (3, 6)).unwrap();
+        let start = cell_center(&maze, (3, 4));
+        let position = bot.steer(&maze, start, 1.0);
+        assert_eq!(position, start + Vec3::X * BOT_SPEED);
+        let position = bot.steer(&maze, position, 10.0);
+        assert_eq!(position, cell_center(&maze, (3, 6)));
+        assert!(bot.path.is_empty());
+    }
+}
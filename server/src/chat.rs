@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const MAX_CHAT_LEN: usize = 200;
+// Au plus CHAT_RATE_LIMIT messages par fenêtre de CHAT_RATE_WINDOW
+pub const CHAT_RATE_LIMIT: usize = 5;
+pub const CHAT_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+// Les joueurs alternent entre deux équipes selon leur ordre de connexion, comme côté client
+pub fn team_of(id: usize) -> usize {
+    id % 2
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChatLimiter {
+    sent: VecDeque<Instant>,
+}
+
+impl ChatLimiter {
+    /// Records a message at `now` and returns false if the sender is over the limit.
+    pub fn allow(&mut self, now: Instant) -> bool {
+        self.allow_at_most(CHAT_RATE_LIMIT, CHAT_RATE_WINDOW, now)
+    }
+
+    /// Same as `allow` with another limit, for other things than chat messages.
+    pub fn allow_at_most(&mut self, limit: usize, window: Duration, now: Instant) -> bool {
+        self.forget_before(window, now);
+        if self.sent.len() >= limit {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+
+    // Oublie ce qui est sorti de la fenêtre; vrai s'il ne reste rien
+    pub fn forget_before(&mut self, window: Duration, now: Instant) -> bool {
+        while self
+            .sent
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= window)
+        {
+            self.sent.pop_front();
+        }
+        self.sent.is_empty()
+    }
+}
+
+/// Strips control characters and truncates to `MAX_CHAT_LEN` characters; `None` if nothing is left.
+pub fn sanitize_chat(text: &str) -> Option<String> {
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_CHAT_LEN)
+        .collect();
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum MessageType {
    Connect,
    ConnectSuccessfull,
@@ -12,9 +12,31 @@ pub enum MessageType {
    PlayerDeath,
    OtherDeadPlayer,
    DeletePlayer,
+   Ping,
+   Pong,
+   Shoot,
+   HitConfirm,
+   Reload,
+   SwitchWeapon,
+   WeaponState,
+   ItemState,
+   ItemPickup,
+   Damage,
+   Kill,
+   Chat,
+   Kicked,
+   RoundRestart,
+   MatchSummary,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FireMode {
+   Projectile,
+   Hitscan,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MessageSended {
    pub message_type: MessageType,
    pub player_name: String,
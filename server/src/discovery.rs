@@ -0,0 +1,79 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{AdminCommand, AdminRequest, ChatLimiter};
+
+/// Port UDP où les serveurs écoutent les requêtes de découverte envoyées en broadcast.
+pub const DISCOVERY_PORT: u16 = 8082;
+/// Contenu exact d'une requête de découverte; tout autre datagramme est ignoré.
+pub const DISCOVERY_QUERY: &[u8] = b"maze-wars discover";
+/// Réponses envoyées au plus à une même IP par fenêtre: la réponse est bien plus grande que la
+/// requête, et l'adresse source d'un datagramme UDP peut être usurpée.
+pub const DISCOVERY_RATE_LIMIT: usize = 4;
+pub const DISCOVERY_RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Durée pendant laquelle l'état de la partie est réutilisé sans le redemander à la boucle de jeu.
+pub const DISCOVERY_INFO_TTL: Duration = Duration::from_secs(1);
+// Au-delà, les requêtes de nouvelles IP sont ignorées jusqu'à ce que la fenêtre se vide
+const MAX_DISCOVERY_SOURCES: usize = 1024;
+
+/// Answers the discovery queries received on `socket` with the server's info, as given by
+/// the `info` admin command, and the address players must connect to.
+pub async fn answer_discovery(socket: UdpSocket, game_address: SocketAddr, admin_tx: mpsc::Sender<AdminRequest>) {
+    let mut buf = vec![0; 512];
+    let mut sources: HashMap<IpAddr, ChatLimiter> = HashMap::new();
+    let mut cached: Option<(Instant, String)> = None;
+    loop {
+        let (amt, from) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(e) => {
+                eprintln!("Failed to receive a discovery query: {}", e);
+                continue;
+            }
+        };
+        if buf[..amt] != DISCOVERY_QUERY[..] {
+            continue;
+        }
+        let now = Instant::now();
+        if !allow_reply(&mut sources, from.ip(), now) {
+            continue;
+        }
+        // La partie appartient à la boucle principale: on lui redemande son état au plus une fois par
+        // `DISCOVERY_INFO_TTL`, comme la console
+        if cached.as_ref().is_none_or(|(at, _)| now.duration_since(*at) >= DISCOVERY_INFO_TTL) {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            if admin_tx.send((AdminCommand::Info, reply_tx)).await.is_err() {
+                break;
+            }
+            let Ok(output) = reply_rx.await else {
+                break;
+            };
+            let Ok(mut info) = serde_json::from_str::<Value>(&output) else {
+                continue;
+            };
+            info["address"] = Value::String(game_address.to_string());
+            cached = Some((now, info.to_string()));
+        }
+        let Some((_, info)) = &cached else {
+            continue;
+        };
+        if let Err(e) = socket.send_to(info.as_bytes(), from).await {
+            eprintln!("Failed to answer the discovery query of {}: {}", from, e);
+        }
+    }
+}
+
+// Compte la réponse pour `ip`; faux si elle dépasse la limite
+fn allow_reply(sources: &mut HashMap<IpAddr, ChatLimiter>, ip: IpAddr, now: Instant) -> bool {
+    sources.retain(|_, replies| !replies.forget_before(DISCOVERY_RATE_WINDOW, now));
+    if !sources.contains_key(&ip) && sources.len() >= MAX_DISCOVERY_SOURCES {
+        return false;
+    }
+    sources
+        .entry(ip)
+        .or_default()
+        .allow_at_most(DISCOVERY_RATE_LIMIT, DISCOVERY_RATE_WINDOW, now)
+}
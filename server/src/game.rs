@@ -0,0 +1,1638 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+pub const DEFAULT_MAX_PLAYERS: usize = 10;
+pub const DEFAULT_SERVER_NAME: &str = "Maze Wars server";
+
+/// Un message à envoyer et l'adresse de son destinataire.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub to: String,
+    pub message: MessageSended,
+}
+
+/// Toutes les règles du serveur, sans socket: des messages entrent, des messages sortent.
+/// `main.rs` ne fait que les entrées-sorties autour de cette machine à états.
+pub struct Game {
+    // Nom affiché dans la liste des serveurs du réseau local
+    pub name: String,
+    pub level: i32,
+    pub maze: Maze,
+    pub weapons: Vec<Weapon>,
+    pub items: Vec<ItemSpawn>,
+    // Clé: nom du joueur
+    pub players: HashMap<String, Player>,
+    pub next_id: usize,
+    // Les bots ont leurs propres ids, à partir de `FIRST_BOT_ID`
+    next_bot_id: usize,
+    pub max_players: usize,
+    pub max_rewind: Duration,
+    pub bans: BanList,
+    // Clé: nom du bot, aussi présent dans `players`
+    pub bots: HashMap<String, Bot>,
+    pub bot_settings: BotSettings,
+    // Carrière des joueurs; en mémoire seulement sauf si `main.rs` en charge un fichier
+    pub stats: StatsStore,
+    // Mot de passe demandé à la connexion, et noms réservés par des comptes
+    pub server_password: Option<String>,
+    pub accounts: Accounts,
+    // Vérifications Argon2 en attente de `serve`, et celles dont on attend le résultat
+    account_checks: Vec<AccountCheck>,
+    account_checks_running: usize,
+    // Connexions récentes avec un mot de passe, par IP, comptées comme les messages du chat
+    password_attempts: HashMap<IpAddr, ChatLimiter>,
+    bot_spawn_at: Option<Instant>,
+    last_tick: Option<Instant>,
+    rng: StdRng,
+}
+
+impl Game {
+    pub fn new(level: i32, weapons: Vec<Weapon>) -> Game {
+        Game::with_rng(level, weapons, StdRng::from_entropy())
+    }
+
+    /// Same as `new` with a fixed seed, so weapon spread is reproducible.
+    pub fn with_seed(level: i32, weapons: Vec<Weapon>, seed: u64) -> Game {
+        Game::with_rng(level, weapons, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(level: i32, weapons: Vec<Weapon>, rng: StdRng) -> Game {
+        let maze = Maze::for_level(level);
+        Game {
+            name: DEFAULT_SERVER_NAME.to_string(),
+            level,
+            items: item_spawns(&maze),
+            maze,
+            weapons,
+            players: HashMap::new(),
+            next_id: 0,
+            next_bot_id: FIRST_BOT_ID,
+            max_players: DEFAULT_MAX_PLAYERS,
+            max_rewind: DEFAULT_MAX_REWIND,
+            bans: BanList::default(),
+            bots: HashMap::new(),
+            bot_settings: BotSettings::default(),
+            stats: StatsStore::default(),
+            server_password: None,
+            accounts: Accounts::default(),
+            account_checks: Vec::new(),
+            account_checks_running: 0,
+            password_attempts: HashMap::new(),
+            bot_spawn_at: None,
+            last_tick: None,
+            rng,
+        }
+    }
+
+    /// What LAN discovery tells about this server; the player count leaves bots out.
+    pub fn server_info(&self) -> Value {
+        // `bots` peut garder un bot déjà retiré de `players` jusqu'au prochain tick
+        let bots = self.players.keys().filter(|name| self.bots.contains_key(*name)).count();
+        json!({
+            "name": self.name,
+            "map": level_name(self.level),
+            "level": self.level,
+            "players": self.players.len() - bots,
+            "bots": bots,
+            "max_players": self.max_players,
+            "password": self.server_password.is_some(),
+        })
+    }
+
+    /// Handles one plaintext message received from `src` at `now` and returns what must be sent.
+    pub fn handle(&mut self, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        self.handle_in_session(message, src, None, now)
+    }
+
+    /// Same as `handle` for a message that arrived on the encrypted `session`, if any.
+    pub fn handle_in_session(
+        &mut self,
+        message: MessageRecieve,
+        src: SocketAddr,
+        session: Option<SessionId>,
+        now: Instant,
+    ) -> Vec<Outgoing> {
+        let out = self.dispatch(message, src, session, now);
+        self.route(out)
+    }
+
+    fn dispatch(&mut self, message: MessageRecieve, src: SocketAddr, session: Option<SessionId>, now: Instant) -> Vec<Outgoing> {
+        match message.message_type {
+            MessageType::Connect => self.connect(message, src, session, now),
+            // Un joueur ne parle qu'en son nom: le nom doit être celui connecté depuis `src`
+            _ if !self.sent_by(&message.player_name, src, session) => {
+                println!("dropped {:?} from {}: not the address of {:?}", message.message_type, src, message.player_name);
+                Vec::new()
+            }
+            MessageType::Disconnect => self.disconnect(&message.player_name, now),
+            MessageType::Action => self.action(message, src, now),
+            MessageType::UpdateLife => self.projectile_hit(message, src, now),
+            MessageType::OtherDeadPlayer => {
+                let id = self.players[&message.player_name].id;
+                self.other_dead_player(&message.player_name, id, message.content)
+            }
+            MessageType::Shoot => self.shoot(message, src, now),
+            MessageType::Reload | MessageType::SwitchWeapon => self.weapon_command(message, src, now),
+            MessageType::Chat => self.chat(message, src, now),
+            MessageType::Ping => self.ping(message, src),
+            other => {
+                println!("ignored message {:?} from {}", other, src);
+                Vec::new()
+            }
+        }
+    }
+
+    // Un joueur connecté par une session chiffrée n'est écouté que sur cette session
+    fn sent_by(&self, name: &str, src: SocketAddr, session: Option<SessionId>) -> bool {
+        self.players.get(name).is_some_and(|player| {
+            player.ip_address == src.to_string() && (player.session.is_none() || player.session == session)
+        })
+    }
+
+    /// Periodic work: items whose respawn time has come back, and the bots.
+    pub fn tick(&mut self, now: Instant) -> Vec<Outgoing> {
+        let respawned = respawn_items(&mut self.items, now)
+            .into_iter()
+            .map(ItemSpawn::to_json)
+            .collect::<Vec<_>>();
+        let mut out = Vec::new();
+        for item in respawned {
+            out.extend(self.broadcast(|game, player| game.item_state(player, item.clone())));
+        }
+        let dt = self
+            .last_tick
+            .map_or(0.0, |last| now.saturating_duration_since(last).as_secs_f32().min(0.25));
+        self.last_tick = Some(now);
+        out.extend(self.update_bots(now, dt));
+        self.route(out)
+    }
+
+    /// Adds the account of the admin `register` command once `serve` has hashed its password.
+    pub fn registered(&mut self, name: &str, hash: Result<String, String>) -> String {
+        match hash.and_then(|hash| self.accounts.add(name, hash)) {
+            Ok(()) => format!("registered {}", name),
+            Err(error) => error,
+        }
+    }
+
+    /// Runs an admin console command; returns its output and the messages to send.
+    pub fn admin(&mut self, command: AdminCommand, now: Instant) -> (String, Vec<Outgoing>) {
+        let mut out = Vec::new();
+        let output = match command {
+            AdminCommand::Help => ADMIN_HELP.to_string(),
+            AdminCommand::Status => status_table(&self.players, self.level, self.max_players),
+            AdminCommand::Info => self.server_info().to_string(),
+            AdminCommand::Kick(name) => {
+                out = self.end_match(&name, now);
+                match self.players.remove(&name) {
+                    Some(player) => {
+                        out.extend(self.kick(&player, "Kicked by the server"));
+                        format!("kicked {}", name)
+                    }
+                    None => format!("no player named {}", name),
+                }
+            }
+            AdminCommand::Ban(target) => match self.bans.ban(&target, &self.players) {
+                Ok(output) => {
+                    // Expulser les joueurs connectés qui sont désormais bannis
+                    let banned = self
+                        .players
+                        .values()
+                        .filter(|player| self.bans.is_banned(&player.player_name, &player.ip_address))
+                        .map(|player| player.player_name.clone())
+                        .collect::<Vec<_>>();
+                    for name in banned {
+                        out.extend(self.end_match(&name, now));
+                        if let Some(player) = self.players.remove(&name) {
+                            out.extend(self.kick(&player, "Banned from the server"));
+                        }
+                    }
+                    output
+                }
+                Err(error) => error,
+            },
+            AdminCommand::Unban(target) => {
+                if self.bans.unban(&target) {
+                    format!("unbanned {}", target)
+                } else {
+                    format!("{} is not banned", target)
+                }
+            }
+            AdminCommand::Level(level) => {
+                // Les clients chargent le labyrinthe à la connexion: ils doivent se reconnecter
+                let reason = format!("Server is changing to level {}, please reconnect", level);
+                let names = self.players.keys().cloned().collect::<Vec<_>>();
+                for name in names {
+                    out.extend(self.end_match(&name, now));
+                }
+                let players = self.players.drain().map(|(_, player)| player).collect::<Vec<_>>();
+                for player in &players {
+                    out.push(self.to(player, self.kicked(player, &reason)));
+                }
+                self.level = level;
+                self.maze = Maze::for_level(level);
+                self.items = item_spawns(&self.maze);
+                self.next_id = 0;
+                self.next_bot_id = FIRST_BOT_ID;
+                format!("now playing level {}", level)
+            }
+            AdminCommand::Restart => {
+                // Un client quitte le jeu à sa mort: on oublie les joueurs morts
+                self.players.retain(|_, player| player.life > 0);
+                // Fin de la manche: bilan pour chacun, puis une nouvelle partie commence
+                let scores = self.scoreboard();
+                let names = self.players.keys().cloned().collect::<Vec<_>>();
+                for name in names {
+                    out.extend(self.end_match(&name, now));
+                }
+                self.items = item_spawns(&self.maze);
+                for player in self.players.values_mut() {
+                    player.joined_at = Some(now);
+                    player.life = MAX_LIFE;
+                    player.shield_until = None;
+                    player.weapon = WeaponState::new(&self.weapons);
+                }
+                let items = self.items.iter().map(ItemSpawn::to_json).collect::<Vec<_>>();
+                for player in self.players.values() {
+                    let restart = self.message(MessageType::RoundRestart, player.player_name.clone(), Value::Null, player.id, player.life);
+                    out.push(self.to(player, restart));
+                    out.push(self.to(player, self.weapon_state(player, now)));
+                    out.push(self.to(player, self.system_chat("The round has been restarted", player)));
+                    for item in &items {
+                        out.push(self.to(player, self.item_state(player, item.clone())));
+                    }
+                }
+                format!("round restarted with {} players\n{}", self.players.len(), scores)
+            }
+            AdminCommand::MaxPlayers(max) => {
+                self.max_players = max;
+                format!("max players set to {}", max)
+            }
+            AdminCommand::Say(text) => {
+                let text = format!("[server] {}", text);
+                out = self.broadcast(|game, player| game.system_chat(&text, player));
+                "message sent".to_string()
+            }
+            AdminCommand::Bots(count) => {
+                // Les bots sont ajoutés ou retirés au prochain tick
+                self.bot_settings.count = count;
+                format!("bots set to {}", count)
+            }
+            AdminCommand::Stats(None) => format!("{}\n{}", self.scoreboard(), self.stats.leaderboard(10)),
+            AdminCommand::Stats(Some(name)) => {
+                let mut lines = Vec::new();
+                if let Some(player) = self.players.get(&name) {
+                    lines.push(format!("{} this match: {}", name, player.stats.summary()));
+                }
+                match self.stats.get(&name) {
+                    Some(career) => lines.push(format!("{} career: {}", name, career.summary())),
+                    None if lines.is_empty() => lines.push(format!("no stats for {}", name)),
+                    None => {}
+                }
+                lines.join("\n")
+            }
+            AdminCommand::Password(password) => {
+                let output = match &password {
+                    Some(_) => "players now need the server password to join",
+                    None => "server password removed",
+                };
+                self.server_password = password;
+                output.to_string()
+            }
+            // Haché par `serve` hors de la boucle de jeu, puis enregistré par `registered`
+            AdminCommand::Register(..) => "registering an account needs a running server".to_string(),
+            AdminCommand::Unregister(name) => match self.accounts.unregister(&name) {
+                Ok(true) => format!("unregistered {}", name),
+                Ok(false) => format!("{} is not registered", name),
+                Err(error) => error,
+            },
+            // Traité par `serve`, qui possède le transport
+            AdminCommand::NetSim(_) => "network simulation needs a running server".to_string(),
+        };
+        (output, self.route(out))
+    }
+
+    // Argon2 ne tourne pas dans la boucle: une connexion avec mot de passe de compte
+    // attend son `AccountCheck` et se termine dans `account_checked`
+    fn connect(&mut self, message: MessageRecieve, src: SocketAddr, session: Option<SessionId>, now: Instant) -> Vec<Outgoing> {
+        let credentials = Credentials::from_content(&message.content);
+        // Deviner un mot de passe demande beaucoup d'essais: on les limite par IP
+        if credentials.server_password.is_some() || credentials.password.is_some() {
+            self.password_attempts
+                .retain(|_, attempts| !attempts.forget_before(PASSWORD_RATE_WINDOW, now));
+            let attempts = self.password_attempts.entry(src.ip()).or_default();
+            if !attempts.allow_at_most(PASSWORD_RATE_LIMIT, PASSWORD_RATE_WINDOW, now) {
+                return vec![self.connect_failed(message.player_name, src, "Too many connection attempts, please wait.")];
+            }
+        }
+        let hash = self.accounts.hash_of(&message.player_name).map(str::to_string);
+        let register = credentials
+            .password
+            .clone()
+            .filter(|_| credentials.register && hash.is_none());
+        let needs_argon2 = (hash.is_some() && credentials.password.is_some()) || register.is_some();
+        if !needs_argon2 {
+            let checked = CheckedAccount { message, src, session, allowed: hash.is_none(), new_hash: None };
+            return self.join(checked, now);
+        }
+        // Refusée de toute façon: inutile de lancer Argon2
+        if let Some(refusal) = self.refusal(&message.player_name, src, &credentials, true) {
+            return vec![self.connect_failed(message.player_name, src, &refusal)];
+        }
+        if self.account_checks_running >= MAX_ACCOUNT_CHECKS {
+            return vec![self.connect_failed(message.player_name, src, "The server is busy, please try again.")];
+        }
+        self.account_checks_running += 1;
+        self.account_checks.push(AccountCheck { message, src, session, hash, register });
+        Vec::new()
+    }
+
+    /// Argon2 work queued by Connect messages since the last call, for `serve` to run off the loop.
+    pub fn take_account_checks(&mut self) -> Vec<AccountCheck> {
+        std::mem::take(&mut self.account_checks)
+    }
+
+    /// Finishes the Connect whose `AccountCheck` has run.
+    pub fn account_checked(&mut self, checked: CheckedAccount, now: Instant) -> Vec<Outgoing> {
+        self.account_checks_running = self.account_checks_running.saturating_sub(1);
+        let out = self.join(checked, now);
+        self.route(out)
+    }
+
+    // Refus: banni, mauvais mot de passe, nom réservé, serveur plein ou nom déjà pris.
+    // `account_allowed`: le nom n'est pas réservé, ou le mot de passe du compte est le bon
+    fn refusal(&self, name: &str, src: SocketAddr, credentials: &Credentials, account_allowed: bool) -> Option<String> {
+        let taken = self.players.contains_key(name);
+        let locked_out = match &self.server_password {
+            Some(password) => !credentials
+                .server_password
+                .as_deref()
+                .is_some_and(|given| same_secret(given, password)),
+            None => false,
+        };
+        // Un bot laisse sa place à un joueur quand le serveur est plein
+        let full = self.players.len() >= self.max_players && self.bots.is_empty();
+        if self.bans.is_banned(name, &src.to_string()) {
+            Some("You are banned from this server.".to_string())
+        } else if locked_out {
+            Some("Wrong server password.".to_string())
+        } else if !account_allowed {
+            Some(format!("The name {} is reserved: wrong or missing account password.", name))
+        } else if !taken && full {
+            Some("The server is full.".to_string())
+        } else if taken {
+            Some("Username already taken. Please choose another one.".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn connect_failed(&self, name: String, src: SocketAddr, refusal: &str) -> Outgoing {
+        let mut response = self.message(
+            MessageType::ConnectFailed,
+            name,
+            Value::String(refusal.to_string()),
+            self.next_id,
+            0,
+        );
+        response.canconnect = false;
+        Outgoing { to: src.to_string(), message: response }
+    }
+
+    // Dernière étape de la connexion, une fois le compte vérifié et le nouveau mot de passe haché
+    fn join(&mut self, checked: CheckedAccount, now: Instant) -> Vec<Outgoing> {
+        let CheckedAccount { message, src, session, allowed, new_hash } = checked;
+        let name = message.player_name.clone();
+        let credentials = Credentials::from_content(&message.content);
+        if let Some(refusal) = self.refusal(&name, src, &credentials, allowed) {
+            return vec![self.connect_failed(message.player_name, src, &refusal)];
+        }
+        let mut out = Vec::new();
+        if self.players.len() >= self.max_players {
+            out = self.remove_bot("Bot left to make room for a player");
+        }
+
+        let mut player = Player::new_player(message.player_name.clone(), src.to_string(), self.next_id, MAX_LIFE, &self.weapons);
+        player.session = session;
+        player.joined_at = Some(now);
+        self.next_id += 1;
+        println!("player {} connected with id {}", player.player_name, player.id);
+        let response = self.message(
+            MessageType::ConnectSuccessfull,
+            player.player_name.clone(),
+            json!({
+                "message": "Connected successfully",
+                "weapons": self.weapons,
+                "items": self.items.iter().map(ItemSpawn::to_json).collect::<Vec<_>>(),
+            }),
+            player.id,
+            player.life,
+        );
+        out.push(self.to(&player, response));
+        // Réserver le nom pour les prochaines connexions
+        let registration = match new_hash {
+            Some(hash) => Some(hash.and_then(|hash| self.accounts.add(&name, hash))),
+            None if credentials.register && credentials.password.is_none() && !self.accounts.is_registered(&name) => {
+                Some(Err("no account password given".to_string()))
+            }
+            None => None,
+        };
+        if let Some(registration) = registration {
+            let text = match registration {
+                Ok(()) => format!("The name {} is now registered to you", name),
+                Err(error) => format!("Registration failed: {}", error),
+            };
+            out.push(self.to(&player, self.system_chat(&text, &player)));
+        }
+        let text = format!("{} joined the game", player.player_name);
+        self.players.insert(player.player_name.clone(), player);
+        out.extend(self.broadcast(|game, player| game.system_chat(&text, player)));
+        out
+    }
+
+    fn disconnect(&mut self, name: &str, now: Instant) -> Vec<Outgoing> {
+        println!("player {} disconnected", name);
+        // Le joueur est parti: ses stats sont gardées, le bilan ne lui est pas envoyé
+        let _ = self.end_match(name, now);
+        if self.players.remove(name).is_none() {
+            return Vec::new();
+        }
+        let text = format!("{} left the game", name);
+        self.broadcast(|game, player| game.system_chat(&text, player))
+    }
+
+    fn action(&mut self, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        let position = message.content.get("movement").and_then(parse_vec3);
+        let Some(sender) = self.players.get_mut(&message.player_name) else {
+            return Vec::new();
+        };
+        if let Some(position) = position {
+            sender.position = position;
+            sender.history.record(now, position);
+        }
+        let mut out = Vec::new();
+
+        // Ramassage des objets, décidé par le serveur à partir de la position reçue
+        if let Some(index) = position.and_then(|position| item_in_reach(&self.items, position)) {
+            let item = &mut self.items[index];
+            item.respawn_at = Some(now + item.kind.respawn_time());
+            let (kind, item) = (item.kind, item.to_json());
+            let duration = sender.apply_item(kind, now);
+            println!("{} picked up {:?}", sender.player_name, kind);
+            let sender = sender.clone();
+            let pickup = self.message(
+                MessageType::ItemPickup,
+                sender.player_name.clone(),
+                json!({ "kind": kind, "duration": duration.as_secs_f32() }),
+                sender.id,
+                sender.life,
+            );
+            out.push(Outgoing { to: src.to_string(), message: pickup });
+            out.extend(self.broadcast(|game, player| game.item_state(player, item.clone())));
+        }
+
+        // L'id relayé est celui connu du serveur, pas celui annoncé par le client
+        let sender = &self.players[&message.player_name];
+        for player in self.players.values() {
+            if player.player_name != sender.player_name {
+                let relay = self.message(
+                    MessageType::Action,
+                    sender.player_name.clone(),
+                    message.content.clone(),
+                    sender.id,
+                    sender.life,
+                );
+                out.push(self.to(player, relay));
+            }
+        }
+        out
+    }
+
+    // Le tireur signale qu'un de ses projectiles a touché le joueur `id_player`
+    fn projectile_hit(&mut self, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        let Some(shooter) = self.players.get_mut(&message.player_name) else {
+            return Vec::new();
+        };
+        // Sans projectile ni point d'impact, la touche ne peut pas être vérifiée
+        let ball_id = message.content.get("ball_id").and_then(|b| b.as_u64());
+        let impact = message.content.get("point").and_then(parse_vec3);
+        let (Some(ball_id), Some(impact)) = (ball_id, impact) else {
+            println!("rejected hit from {}: missing ball_id or point", message.player_name);
+            return Vec::new();
+        };
+        if message.id_player == shooter.id {
+            println!("rejected hit from {}: cannot hit itself", message.player_name);
+            return Vec::new();
+        }
+        let Some(weapon) = shooter.weapon.consume_projectile_hit(now, ball_id) else {
+            println!("rejected hit from {}: no projectile in flight", message.player_name);
+            return Vec::new();
+        };
+        let rewind = rewind_duration(shooter.rtt, self.max_rewind);
+        let view_time = now.checked_sub(rewind).unwrap_or(now);
+        let Some(victim) = self.players.values_mut().find(|player| player.id == message.id_player) else {
+            return Vec::new();
+        };
+        // Vérifier l'impact contre la position du joueur vue par le tireur
+        let seen_at = victim.history.position_at(view_time).unwrap_or(victim.position);
+        if seen_at.distance(impact) > HIT_RADIUS + PROJECTILE_HIT_TOLERANCE {
+            println!("rejected hit from {}: target was not there", message.player_name);
+            return Vec::new();
+        }
+        let (killed, mut out) = self.apply_hit(&message.player_name, message.id_player, weapon, now);
+        let shooter = &self.players[&message.player_name];
+        let confirm = self.message(
+            MessageType::HitConfirm,
+            shooter.player_name.clone(),
+            json!({ "target": message.id_player, "killed": killed }),
+            shooter.id,
+            shooter.life,
+        );
+        out.push(Outgoing { to: src.to_string(), message: confirm });
+        out
+    }
+
+    // Le joueur `id` est mort: les autres retirent son personnage
+    fn other_dead_player(&mut self, name: &str, id: usize, content: Value) -> Vec<Outgoing> {
+        self.players
+            .values()
+            .filter(|player| player.id != id)
+            .map(|player| {
+                let delete = self.message(
+                    MessageType::DeletePlayer,
+                    name.to_string(),
+                    content.clone(),
+                    id,
+                    player.life,
+                );
+                self.to(player, delete)
+            })
+            .collect()
+    }
+
+    fn shoot(&mut self, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        let (Some(origin), Some(direction)) = (
+            message.content.get("origin").and_then(parse_vec3),
+            message.content.get("direction").and_then(parse_vec3),
+        ) else {
+            return Vec::new();
+        };
+        let Some(shooter) = self.players.get_mut(&message.player_name) else {
+            return Vec::new();
+        };
+        // Refuser un tir qui ne part pas de la position connue du tireur
+        if shooter.position.distance(origin) > MAX_ORIGIN_ERROR {
+            println!("rejected shot from {}: origin too far", shooter.player_name);
+            return Vec::new();
+        }
+        let ball_id = message.content.get("ball_id").and_then(|b| b.as_u64());
+        let fired = shooter.weapon.try_fire(&self.weapons, now, ball_id);
+        if fired.is_ok() {
+            shooter.stats.shots += 1;
+        }
+        let shooter = shooter.clone();
+        let mut out = vec![Outgoing { to: src.to_string(), message: self.weapon_state(&shooter, now) }];
+        let weapon = match fired {
+            Ok(index) => index,
+            Err(reason) => {
+                println!("rejected shot from {}: {:?}", shooter.player_name, reason);
+                return out;
+            }
+        };
+        if self.weapons[weapon].fire_mode != FireMode::Hitscan {
+            return out;
+        }
+
+        // Rembobiner les adversaires à l'instant vu par le tireur
+        let view_time = now
+            .checked_sub(rewind_duration(shooter.rtt, self.max_rewind))
+            .unwrap_or(now);
+        let targets = self
+            .players
+            .values()
+            .filter(|player| player.id != shooter.id && player.life > 0)
+            .map(|player| {
+                let position = player.history.position_at(view_time).unwrap_or(player.position);
+                (player.id, position)
+            })
+            .collect::<Vec<_>>();
+
+        let direction = apply_spread(direction.normalize_or_zero(), self.weapons[weapon].spread, &mut self.rng);
+        let outcome = resolve_hitscan(&self.maze, origin, direction, &targets);
+        let claimed = message.content.get("target").and_then(|t| t.as_u64());
+        if claimed.map(|t| t as usize) != outcome.target {
+            println!(
+                "shot from {} claimed {:?}, server found {:?}",
+                shooter.player_name, claimed, outcome.target
+            );
+        }
+
+        let mut killed = false;
+        if let Some(target) = outcome.target {
+            let (target_killed, hit) = self.apply_hit(&shooter.player_name, target, weapon, now);
+            killed = target_killed;
+            out.extend(hit);
+        }
+
+        // Le tireur reçoit le résultat, les autres voient la trace du tir
+        for player in self.players.values() {
+            let message_type = if player.id == shooter.id {
+                MessageType::HitConfirm
+            } else {
+                MessageType::Shoot
+            };
+            let trace = self.message(
+                message_type,
+                shooter.player_name.clone(),
+                json!({
+                    "origin": origin,
+                    "end": outcome.point,
+                    "target": outcome.target,
+                    "killed": killed,
+                }),
+                shooter.id,
+                shooter.life,
+            );
+            out.push(self.to(player, trace));
+        }
+        out
+    }
+
+    fn weapon_command(&mut self, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        let Some(player) = self.players.get_mut(&message.player_name) else {
+            return Vec::new();
+        };
+        if let MessageType::Reload = message.message_type {
+            player.weapon.start_reload(&self.weapons, now);
+        } else if let Some(index) = message.content.get("weapon").and_then(|w| w.as_u64()) {
+            player.weapon.switch(index as usize, &self.weapons);
+        }
+        let player = &self.players[&message.player_name];
+        vec![Outgoing { to: src.to_string(), message: self.weapon_state(player, now) }]
+    }
+
+    fn chat(&mut self, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        let Some(sender) = self.players.get_mut(&message.player_name) else {
+            return Vec::new();
+        };
+        let Some(text) = message.content.get("text").and_then(|t| t.as_str()).and_then(sanitize_chat) else {
+            return Vec::new();
+        };
+        if !sender.chat.allow(now) {
+            let sender = sender.clone();
+            let response = self.system_chat("You are sending messages too fast", &sender);
+            return vec![Outgoing { to: src.to_string(), message: response }];
+        }
+        let team_only = message.content.get("team").and_then(|t| t.as_bool()).unwrap_or(false);
+        let sender = sender.clone();
+        self.players
+            .values()
+            .filter(|player| !team_only || team_of(player.id) == team_of(sender.id))
+            .map(|player| {
+                let chat = self.message(
+                    MessageType::Chat,
+                    sender.player_name.clone(),
+                    json!({ "text": text, "team": team_only }),
+                    sender.id,
+                    player.life,
+                );
+                self.to(player, chat)
+            })
+            .collect()
+    }
+
+    fn ping(&mut self, message: MessageRecieve, src: SocketAddr) -> Vec<Outgoing> {
+        if let Some(rtt_ms) = message.content.get("rtt_ms").and_then(|r| r.as_f64()) {
+            // Un RTT trop grand pour une Duration est ignoré
+            if let (Some(player), Ok(rtt)) = (
+                self.players.get_mut(&message.player_name),
+                Duration::try_from_secs_f64(rtt_ms.max(0.0) / 1000.0),
+            ) {
+                player.rtt = rtt;
+            }
+        }
+        // Renvoyer le ping tel quel pour que le client mesure le RTT
+        let pong = self.message(MessageType::Pong, message.player_name, message.content, message.id_player, 0);
+        vec![Outgoing { to: src.to_string(), message: pong }]
+    }
+
+    // Ajoute ou retire des bots selon les places libres, puis les fait jouer
+    fn update_bots(&mut self, now: Instant, dt: f32) -> Vec<Outgoing> {
+        // Bots expulsés, bannis ou retirés par un changement de niveau
+        self.bots.retain(|name, _| self.players.contains_key(name));
+        let mut out = Vec::new();
+
+        // Un bot mort prévient les autres comme un client, puis libère sa place
+        let dead = self
+            .bots
+            .iter()
+            .filter(|(_, bot)| bot.dead)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        for name in dead {
+            self.bots.remove(&name);
+            if let Some(player) = self.players.remove(&name) {
+                out.extend(self.other_dead_player(&name, player.id, Value::Null));
+            }
+            self.bot_spawn_at = Some(now + BOT_RESPAWN_DELAY);
+        }
+
+        let humans = self.players.len() - self.bots.len();
+        let wanted = self.bot_settings.count.min(self.max_players.saturating_sub(humans));
+        while self.bots.len() > wanted {
+            out.extend(self.remove_bot("Too many bots"));
+        }
+        if self.bots.len() < wanted && self.bot_spawn_at.is_none_or(|at| now >= at) {
+            out.extend(self.spawn_bot(now));
+            // Un bot à la fois
+            self.bot_spawn_at = Some(now + Duration::from_millis(500));
+        }
+
+        let mut intents = Vec::new();
+        for (name, bot) in self.bots.iter_mut() {
+            let Some(me) = self.players.get(name).filter(|me| me.life > 0) else {
+                continue;
+            };
+            let enemies = self
+                .players
+                .values()
+                .filter(|player| player.id != me.id && player.life > 0)
+                .map(|player| (player.id, player.position))
+                .collect::<Vec<_>>();
+            let messages = bot.think(me, &enemies, &self.maze, &self.weapons, &self.bot_settings, now, dt, &mut self.rng);
+            intents.push((bot_address(me.id), messages));
+        }
+        // Les bots passent par les mêmes règles que les messages des clients
+        for (address, messages) in intents {
+            for message in messages {
+                out.extend(self.dispatch(message, address, None, now));
+            }
+        }
+        out
+    }
+
+    fn spawn_bot(&mut self, now: Instant) -> Vec<Outgoing> {
+        let id = self.next_bot_id;
+        self.next_bot_id += 1;
+        let name = format!("Bot{}", id);
+        let cells = open_cells(&self.maze);
+        if self.players.contains_key(&name) || cells.is_empty() {
+            return Vec::new();
+        }
+        let position = cell_center(&self.maze, cells[self.rng.gen_range(0..cells.len())]);
+        let mut player = Player::new_player(name.clone(), bot_address(id).to_string(), id, MAX_LIFE, &self.weapons);
+        player.joined_at = Some(now);
+        player.position = position;
+        player.history.record(now, position);
+        println!("bot {} joined with id {}", name, id);
+        self.players.insert(name.clone(), player);
+        self.bots.insert(name.clone(), Bot::new(id));
+
+        let text = format!("{} joined the game", name);
+        let mut out = self.broadcast(|game, player| game.system_chat(&text, player));
+        // Les clients créent le joueur distant à son premier mouvement
+        let action = MessageRecieve {
+            message_type: MessageType::Action,
+            player_name: name,
+            content: json!({ "movement": position }),
+            id_player: id,
+        };
+        out.extend(self.dispatch(action, bot_address(id), None, now));
+        out
+    }
+
+    fn remove_bot(&mut self, reason: &str) -> Vec<Outgoing> {
+        let Some(name) = self.bots.keys().next().cloned() else {
+            return Vec::new();
+        };
+        self.bots.remove(&name);
+        match self.players.remove(&name) {
+            Some(player) => {
+                println!("bot {} removed: {}", name, reason);
+                self.kick(&player, reason)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // Les messages destinés aux bots leur sont remis ici au lieu de partir sur le réseau
+    fn route(&mut self, out: Vec<Outgoing>) -> Vec<Outgoing> {
+        let bots = &mut self.bots;
+        out.into_iter()
+            .filter(|outgoing| {
+                if !ip_of(&outgoing.to).is_some_and(|ip| ip.is_unspecified()) {
+                    return true;
+                }
+                if let Some(bot) = bots.values_mut().find(|bot| bot.address == outgoing.to) {
+                    bot.observe(&outgoing.message);
+                }
+                false
+            })
+            .collect()
+    }
+
+    // Applique les dégâts de l'arme `weapon` à `target`; renvoie si la cible est morte
+    // et les messages qui suivent: dégâts pour la victime, sa mort et le fil des éliminations
+    fn apply_hit(&mut self, shooter: &str, target: usize, weapon: usize, now: Instant) -> (bool, Vec<Outgoing>) {
+        let Some(victim) = self.players.values_mut().find(|player| player.id == target) else {
+            return (false, Vec::new());
+        };
+        let life_before = victim.life;
+        let killed = victim.take_damage(self.weapons[weapon].damage, now);
+        if killed {
+            println!("player death {:?}", victim.player_name);
+            victim.stats.deaths += 1;
+        }
+        let victim = victim.clone();
+        let Some(shooter) = self.players.get_mut(shooter) else {
+            return (killed, Vec::new());
+        };
+        shooter.stats.hits += 1;
+        if killed {
+            shooter.stats.kills += 1;
+        }
+        let shooter = shooter.clone();
+
+        let mut out = Vec::new();
+        if victim.life < life_before {
+            let damage = self.message(
+                MessageType::Damage,
+                shooter.player_name.clone(),
+                json!({
+                    "attacker": shooter.id,
+                    "from": shooter.position,
+                    "damage": life_before - victim.life,
+                }),
+                victim.id,
+                victim.life,
+            );
+            out.push(self.to(&victim, damage));
+        }
+        if killed {
+            // Le client quitte à sa mort: sa partie se termine ici, bilan avant la mort
+            out.extend(self.end_match(&victim.player_name, now));
+            let death = self.message(MessageType::PlayerDeath, victim.player_name.clone(), Value::Null, victim.id, victim.life);
+            out.push(self.to(&victim, death));
+            let kill = json!({
+                "killer": shooter.player_name,
+                "victim": victim.player_name,
+                "weapon": self.weapons[weapon].name,
+            });
+            let (killer, killer_id) = (shooter.player_name.clone(), shooter.id);
+            out.extend(self.broadcast(|game, player| {
+                game.message(MessageType::Kill, killer.clone(), kill.clone(), killer_id, player.life)
+            }));
+        }
+        (killed, out)
+    }
+
+    // Termine la partie d'un joueur: ses compteurs rejoignent sa carrière et il reçoit son bilan
+    fn end_match(&mut self, name: &str, now: Instant) -> Vec<Outgoing> {
+        let Some(player) = self.players.get_mut(name) else {
+            return Vec::new();
+        };
+        // Partie déjà terminée, par exemple à la mort du joueur
+        let Some(joined_at) = player.joined_at.take() else {
+            return Vec::new();
+        };
+        let mut played = std::mem::take(&mut player.stats);
+        played.matches = 1;
+        played.seconds_played = now.saturating_duration_since(joined_at).as_secs();
+        let player = player.clone();
+        // Les bots ne sont pas enregistrés
+        if ip_of(&player.ip_address).is_some_and(|ip| ip.is_unspecified()) {
+            return Vec::new();
+        }
+        let career = self.stats.record(name, &played);
+        println!("match over for {}: {}", name, played.summary());
+        let summary = self.message(
+            MessageType::MatchSummary,
+            player.player_name.clone(),
+            json!({ "match": played.to_json(), "career": career.to_json() }),
+            player.id,
+            player.life,
+        );
+        vec![self.to(&player, summary)]
+    }
+
+    // Compteurs de la partie en cours, du meilleur au moins bon
+    fn scoreboard(&self) -> String {
+        let mut players = self.players.values().collect::<Vec<_>>();
+        players.sort_by(|a, b| b.stats.kills.cmp(&a.stats.kills).then(a.stats.deaths.cmp(&b.stats.deaths)));
+        let mut lines = vec!["current match:".to_string()];
+        for player in players {
+            lines.push(format!(
+                "  {:<16} {} kills, {} deaths, accuracy {:.0}%",
+                player.player_name,
+                player.stats.kills,
+                player.stats.deaths,
+                player.stats.accuracy() * 100.0
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // Prévient le joueur expulsé, retire son entité chez les autres et l'annonce dans le chat
+    fn kick(&self, player: &Player, reason: &str) -> Vec<Outgoing> {
+        let mut out = vec![self.to(player, self.kicked(player, reason))];
+        let text = format!("{} was removed from the game", player.player_name);
+        for other in self.players.values() {
+            let delete = self.message(MessageType::DeletePlayer, player.player_name.clone(), Value::Null, player.id, other.life);
+            out.push(self.to(other, delete));
+            out.push(self.to(other, self.system_chat(&text, other)));
+        }
+        out
+    }
+
+    fn message(&self, message_type: MessageType, player_name: String, content: Value, id_player: usize, player_life: i64) -> MessageSended {
+        MessageSended {
+            message_type,
+            player_name,
+            content,
+            id_player,
+            player_life,
+            level: self.level,
+            canconnect: true,
+        }
+    }
+
+    fn to(&self, player: &Player, message: MessageSended) -> Outgoing {
+        Outgoing {
+            to: player.ip_address.clone(),
+            message,
+        }
+    }
+
+    // Un message par joueur connecté
+    fn broadcast(&self, make: impl Fn(&Game, &Player) -> MessageSended) -> Vec<Outgoing> {
+        self.players
+            .values()
+            .map(|player| self.to(player, make(self, player)))
+            .collect()
+    }
+
+    // État autoritaire des armes, renvoyé au joueur après chaque tir, rechargement ou changement
+    fn weapon_state(&self, player: &Player, now: Instant) -> MessageSended {
+        let state = &player.weapon;
+        self.message(
+            MessageType::WeaponState,
+            player.player_name.clone(),
+            json!({
+                "weapon": state.current,
+                "ammo": state.current_ammo(&self.weapons, now),
+                "reload_remaining": state.reload_remaining(now),
+                "magazine_size": self.weapons[state.current].magazine_size,
+            }),
+            player.id,
+            player.life,
+        )
+    }
+
+    fn item_state(&self, player: &Player, item: Value) -> MessageSended {
+        self.message(MessageType::ItemState, String::new(), item, player.id, player.life)
+    }
+
+    // Message du serveur affiché dans le chat du joueur
+    fn system_chat(&self, text: &str, player: &Player) -> MessageSended {
+        self.message(MessageType::Chat, String::new(), json!({ "text": text, "system": true }), player.id, player.life)
+    }
+
+    fn kicked(&self, player: &Player, reason: &str) -> MessageSended {
+        self.message(MessageType::Kicked, player.player_name.clone(), Value::String(reason.to_string()), player.id, player.life)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::Vec3;
+
+    fn test_weapons() -> Vec<Weapon> {
+        vec![
+            Weapon {
+                name: "Blaster".to_string(),
+                fire_mode: FireMode::Projectile,
+                fire_rate: 2.0,
+                projectile_speed: 20.0,
+                damage: 1,
+                spread: 0.0,
+                magazine_size: 10,
+                reload_time: 1.5,
+                projectile_lifetime: 3.0,
+                ricochets: 0,
+            },
+            Weapon {
+                name: "Rail".to_string(),
+                fire_mode: FireMode::Hitscan,
+                fire_rate: 1.0,
+                projectile_speed: 0.0,
+                damage: 5,
+                spread: 0.0,
+                magazine_size: 10,
+                reload_time: 2.5,
+                projectile_lifetime: 0.0,
+                ricochets: 0,
+            },
+        ]
+    }
+
+    fn addr(index: usize) -> SocketAddr {
+        // Une adresse IP par joueur, pour que les bans par IP ne touchent que lui
+        SocketAddr::from(([127, 0, 0, 1 + index as u8], 9000))
+    }
+
+    fn message(message_type: MessageType, name: &str, content: Value) -> MessageRecieve {
+        MessageRecieve {
+            message_type,
+            player_name: name.to_string(),
+            content,
+            id_player: 0,
+        }
+    }
+
+    // Partie de niveau 1 avec les joueurs `names`, d'ids 0, 1, 2...
+    fn game_with(names: &[&str]) -> (Game, Instant) {
+        let mut game = Game::with_seed(1, test_weapons(), 7);
+        let now = Instant::now();
+        for (index, name) in names.iter().enumerate() {
+            game.handle(message(MessageType::Connect, name, Value::Null), addr(index), now);
+        }
+        (game, now)
+    }
+
+    fn of_type(out: &[Outgoing], message_type: MessageType) -> Vec<&Outgoing> {
+        out.iter().filter(|o| o.message.message_type == message_type).collect()
+    }
+
+    fn move_to(game: &mut Game, name: &str, position: Vec3, now: Instant) -> Vec<Outgoing> {
+        let index = game.players[name].id;
+        game.handle(
+            message(MessageType::Action, name, json!({ "movement": position })),
+            addr(index),
+            now,
+        )
+    }
+
+    fn rail_shot(game: &mut Game, name: &str, origin: Vec3, direction: Vec3, now: Instant) -> Vec<Outgoing> {
+        let index = game.players[name].id;
+        game.handle(
+            message(MessageType::SwitchWeapon, name, json!({ "weapon": 1 })),
+            addr(index),
+            now,
+        );
+        game.handle(
+            message(MessageType::Shoot, name, json!({ "origin": origin, "direction": direction })),
+            addr(index),
+            now,
+        )
+    }
+
+    #[test]
+    fn connect_assigns_ids_and_sends_weapons_and_items() {
+        let mut game = Game::with_seed(1, test_weapons(), 7);
+        let now = Instant::now();
+        let out = game.handle(message(MessageType::Connect, "alice", Value::Null), addr(0), now);
+        let accepted = of_type(&out, MessageType::ConnectSuccessfull);
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].to, addr(0).to_string());
+        assert_eq!(accepted[0].message.id_player, 0);
+        assert_eq!(accepted[0].message.player_life, MAX_LIFE);
+        assert_eq!(accepted[0].message.content["weapons"].as_array().unwrap().len(), 2);
+        assert_eq!(accepted[0].message.content["items"].as_array().unwrap().len(), 4);
+
+        let out = game.handle(message(MessageType::Connect, "bob", Value::Null), addr(1), now);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull)[0].message.id_player, 1);
+        // Les deux joueurs voient l'arrivée de bob dans le chat
+        assert_eq!(of_type(&out, MessageType::Chat).len(), 2);
+    }
+
+    #[test]
+    fn connect_refuses_taken_names_and_full_server() {
+        let (mut game, now) = game_with(&["alice"]);
+        let out = game.handle(message(MessageType::Connect, "alice", Value::Null), addr(1), now);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].message.message_type, MessageType::ConnectFailed);
+        assert!(!out[0].message.canconnect);
+
+        game.max_players = 1;
+        let out = game.handle(message(MessageType::Connect, "bob", Value::Null), addr(1), now);
+        assert_eq!(out[0].message.message_type, MessageType::ConnectFailed);
+        assert_eq!(out[0].message.content, json!("The server is full."));
+        assert_eq!(game.players.len(), 1);
+    }
+
+    #[test]
+    fn action_is_relayed_to_others_with_the_server_id() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        let mut spoofed = message(MessageType::Action, "bob", json!({ "movement": [8.0, 1.0, 6.0] }));
+        spoofed.id_player = 42;
+        let out = game.handle(spoofed, addr(1), now);
+        let relayed = of_type(&out, MessageType::Action);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(relayed[0].to, addr(0).to_string());
+        assert_eq!(relayed[0].message.id_player, 1);
+        assert_eq!(game.players["bob"].position, Vec3::new(8.0, 1.0, 6.0));
+    }
+
+    #[test]
+    fn messages_in_the_name_of_another_address_are_dropped() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        let position = game.players["alice"].position;
+        // Ni une nouvelle adresse, ni celle d'un autre joueur ne peuvent parler au nom d'alice
+        for impostor in [addr(5), addr(1)] {
+            let moved = message(MessageType::Action, "alice", json!({ "movement": [8.0, 1.0, 6.0] }));
+            assert!(game.handle(moved, impostor, now).is_empty());
+            let chat = message(MessageType::Chat, "alice", json!({ "text": "gg" }));
+            assert!(game.handle(chat, impostor, now).is_empty());
+            let shot = json!({ "origin": position, "direction": [1.0, 0.0, 0.0] });
+            assert!(game.handle(message(MessageType::Shoot, "alice", shot), impostor, now).is_empty());
+            assert!(game.handle(message(MessageType::OtherDeadPlayer, "alice", Value::Null), impostor, now).is_empty());
+            assert!(game.handle(message(MessageType::Disconnect, "alice", Value::Null), impostor, now).is_empty());
+        }
+        let alice = &game.players["alice"];
+        assert_eq!(alice.position, position);
+        assert_eq!(alice.stats.shots, 0);
+
+        // Depuis son adresse, alice est toujours écoutée
+        let out = move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        assert_eq!(of_type(&out, MessageType::Action).len(), 1);
+    }
+
+    #[test]
+    fn players_connected_through_a_session_are_only_heard_on_it() {
+        let mut game = Game::with_seed(1, test_weapons(), 7);
+        let now = Instant::now();
+        game.handle_in_session(message(MessageType::Connect, "alice", Value::Null), addr(0), Some(4), now);
+        assert_eq!(game.players["alice"].session, Some(4));
+
+        // Du clair usurpant son adresse, ou une autre session, ne parle pas pour elle
+        let moved = || message(MessageType::Action, "alice", json!({ "movement": [8.0, 1.0, 6.0] }));
+        assert!(game.handle(moved(), addr(0), now).is_empty());
+        assert!(game.handle_in_session(moved(), addr(0), Some(5), now).is_empty());
+        assert_eq!(game.players["alice"].position, Vec3::ZERO);
+
+        game.handle_in_session(moved(), addr(0), Some(4), now);
+        assert_eq!(game.players["alice"].position, Vec3::new(8.0, 1.0, 6.0));
+    }
+
+    #[test]
+    fn hitscan_shots_damage_then_kill() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        // Même couloir (ligne 3 du niveau 1), sans mur entre les deux
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+
+        let out = rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now);
+        let damage = of_type(&out, MessageType::Damage);
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].to, addr(1).to_string());
+        assert_eq!(damage[0].message.player_life, 5);
+        let confirm = of_type(&out, MessageType::HitConfirm);
+        assert_eq!(confirm[0].message.content["target"], json!(1));
+        assert_eq!(confirm[0].message.content["killed"], json!(false));
+        assert_eq!(of_type(&out, MessageType::Shoot).len(), 1);
+
+        let later = now + Duration::from_secs(2);
+        let out = rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, later);
+        let death = of_type(&out, MessageType::PlayerDeath);
+        assert_eq!(death.len(), 1);
+        assert_eq!(death[0].to, addr(1).to_string());
+        assert_eq!(of_type(&out, MessageType::Kill).len(), 2);
+        assert!(game.players["bob"].life <= 0);
+    }
+
+    #[test]
+    fn a_death_ends_the_match_and_records_career_stats() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+        rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now);
+        let later = now + Duration::from_secs(62);
+        let out = rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, later);
+
+        // Le bilan arrive avant la mort, à la victime seulement
+        let summary = of_type(&out, MessageType::MatchSummary);
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].to, addr(1).to_string());
+        assert_eq!(summary[0].message.content["match"]["deaths"], json!(1));
+        assert_eq!(summary[0].message.content["match"]["seconds_played"], json!(62));
+        let bob = game.stats.get("bob").unwrap();
+        assert_eq!((bob.deaths, bob.matches), (1, 1));
+
+        // La partie de bob est finie: sa déconnexion ne la compte pas deux fois
+        game.handle(message(MessageType::Disconnect, "bob", Value::Null), addr(1), later);
+        assert_eq!(game.stats.get("bob").unwrap().matches, 1);
+
+        assert_eq!(game.players["alice"].stats.kills, 1);
+        assert_eq!(game.players["alice"].stats.accuracy(), 1.0);
+        let (_, out) = game.admin(AdminCommand::Restart, later);
+        assert_eq!(of_type(&out, MessageType::MatchSummary).len(), 1);
+        let alice = game.stats.get("alice").unwrap();
+        assert_eq!((alice.kills, alice.shots, alice.hits), (1, 2, 2));
+        assert_eq!(game.players["alice"].stats, PlayerStats::default());
+    }
+
+    #[test]
+    fn shots_are_rejected_during_cooldown_and_from_a_wrong_origin() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+        rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now);
+
+        let out = rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now);
+        assert_eq!(of_type(&out, MessageType::WeaponState).len(), 1);
+        assert!(of_type(&out, MessageType::Damage).is_empty());
+
+        let later = now + Duration::from_secs(2);
+        let out = rail_shot(&mut game, "alice", Vec3::new(20.0, 1.0, 6.0), Vec3::X, later);
+        assert!(out.is_empty());
+        assert_eq!(game.players["bob"].life, 5);
+    }
+
+    #[test]
+    fn walls_block_hitscan_shots() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        // Le mur de la case (2, 5) sépare les deux joueurs
+        move_to(&mut game, "alice", Vec3::new(10.0, 1.0, 2.0), now);
+        move_to(&mut game, "bob", Vec3::new(10.0, 1.0, 6.0), now);
+        let out = rail_shot(&mut game, "alice", Vec3::new(10.0, 1.0, 2.0), Vec3::Z, now);
+        assert!(of_type(&out, MessageType::Damage).is_empty());
+        assert_eq!(game.players["bob"].life, MAX_LIFE);
+    }
+
+    #[test]
+    fn projectile_hits_need_a_projectile_in_flight() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+        let mut hit = message(MessageType::UpdateLife, "alice", json!({ "ball_id": 1, "point": [16.0, 1.0, 6.0] }));
+        hit.id_player = 1;
+
+        assert!(game.handle(hit.clone(), addr(0), now).is_empty());
+
+        let shot = json!({ "origin": [8.0, 1.0, 6.0], "direction": [1.0, 0.0, 0.0], "ball_id": 1 });
+        game.handle(message(MessageType::Shoot, "alice", shot), addr(0), now);
+        let out = game.handle(hit.clone(), addr(0), now);
+        assert_eq!(of_type(&out, MessageType::Damage)[0].message.player_life, MAX_LIFE - 1);
+        assert_eq!(of_type(&out, MessageType::HitConfirm).len(), 1);
+
+        // Le même projectile ne touche qu'une fois
+        assert!(game.handle(hit, addr(0), now).is_empty());
+    }
+
+    #[test]
+    fn projectile_hits_far_from_the_target_are_rejected() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+        let shot = json!({ "origin": [8.0, 1.0, 6.0], "direction": [1.0, 0.0, 0.0], "ball_id": 1 });
+        game.handle(message(MessageType::Shoot, "alice", shot), addr(0), now);
+        let mut hit = message(MessageType::UpdateLife, "alice", json!({ "ball_id": 1, "point": [24.0, 1.0, 6.0] }));
+        hit.id_player = 1;
+        assert!(game.handle(hit, addr(0), now).is_empty());
+        assert_eq!(game.players["bob"].life, MAX_LIFE);
+    }
+
+    #[test]
+    fn projectile_hits_without_a_ball_or_a_point_are_rejected() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+        let shot = json!({ "origin": [8.0, 1.0, 6.0], "direction": [1.0, 0.0, 0.0], "ball_id": 1 });
+        game.handle(message(MessageType::Shoot, "alice", shot), addr(0), now);
+
+        for content in [json!({}), json!({ "ball_id": 1 }), json!({ "point": [16.0, 1.0, 6.0] })] {
+            let mut hit = message(MessageType::UpdateLife, "alice", content);
+            hit.id_player = 1;
+            assert!(game.handle(hit, addr(0), now).is_empty());
+        }
+        // Un joueur ne peut pas se toucher lui-même
+        let mut hit = message(MessageType::UpdateLife, "alice", json!({ "ball_id": 1, "point": [8.0, 1.0, 6.0] }));
+        hit.id_player = 0;
+        assert!(game.handle(hit, addr(0), now).is_empty());
+        assert_eq!(game.players["alice"].life, MAX_LIFE);
+        assert_eq!(game.players["bob"].life, MAX_LIFE);
+
+        // Le projectile est toujours en vol pour une vraie touche
+        let mut hit = message(MessageType::UpdateLife, "alice", json!({ "ball_id": 1, "point": [16.0, 1.0, 6.0] }));
+        hit.id_player = 1;
+        let out = game.handle(hit, addr(0), now);
+        assert_eq!(of_type(&out, MessageType::Damage)[0].message.player_life, MAX_LIFE - 1);
+    }
+
+    #[test]
+    fn health_packs_heal_and_respawn() {
+        let (mut game, now) = game_with(&["alice"]);
+        game.players.get_mut("alice").unwrap().life = 4;
+        // Trousse de soin du niveau 1 en case (1, 3)
+        let out = move_to(&mut game, "alice", Vec3::new(6.0, 1.0, 2.0), now);
+        let pickup = of_type(&out, MessageType::ItemPickup);
+        assert_eq!(pickup.len(), 1);
+        assert_eq!(pickup[0].message.player_life, 4 + HEALTH_PACK_LIFE);
+        assert_eq!(of_type(&out, MessageType::ItemState)[0].message.content["available"], json!(false));
+
+        // Plus rien à ramasser tant que l'objet n'est pas revenu
+        let out = move_to(&mut game, "alice", Vec3::new(6.0, 1.0, 2.0), now);
+        assert!(of_type(&out, MessageType::ItemPickup).is_empty());
+        assert!(game.tick(now + Duration::from_secs(1)).is_empty());
+
+        let out = game.tick(now + ItemKind::HealthPack.respawn_time());
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].message.content["available"], json!(true));
+    }
+
+    #[test]
+    fn shields_absorb_damage() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        move_to(&mut game, "bob", Vec3::new(16.0, 1.0, 6.0), now);
+        game.players.get_mut("bob").unwrap().apply_item(ItemKind::Shield, now);
+        let out = rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now);
+        assert!(of_type(&out, MessageType::Damage).is_empty());
+        assert_eq!(game.players["bob"].life, MAX_LIFE);
+    }
+
+    #[test]
+    fn team_chat_only_reaches_teammates() {
+        let (mut game, now) = game_with(&["alice", "bob", "carol"]);
+        let out = game.handle(
+            message(MessageType::Chat, "alice", json!({ "text": "hi team", "team": true })),
+            addr(0),
+            now,
+        );
+        let mut recipients = out.iter().map(|o| o.to.clone()).collect::<Vec<_>>();
+        recipients.sort();
+        assert_eq!(recipients, vec![addr(0).to_string(), addr(2).to_string()]);
+
+        let out = game.handle(message(MessageType::Chat, "bob", json!({ "text": "hi all" })), addr(1), now);
+        assert_eq!(out.len(), 3);
+    }
+
+    #[test]
+    fn chat_is_rate_limited() {
+        let (mut game, now) = game_with(&["alice"]);
+        for _ in 0..CHAT_RATE_LIMIT {
+            let out = game.handle(message(MessageType::Chat, "alice", json!({ "text": "spam" })), addr(0), now);
+            assert_eq!(out[0].message.content["text"], json!("spam"));
+        }
+        let out = game.handle(message(MessageType::Chat, "alice", json!({ "text": "spam" })), addr(0), now);
+        assert_eq!(out[0].message.content["system"], json!(true));
+    }
+
+    #[test]
+    fn ping_is_echoed_and_rtt_stored() {
+        let (mut game, now) = game_with(&["alice"]);
+        let out = game.handle(
+            message(MessageType::Ping, "alice", json!({ "seq": 3, "rtt_ms": 500.0 })),
+            addr(0),
+            now,
+        );
+        assert_eq!(out[0].message.message_type, MessageType::Pong);
+        assert_eq!(out[0].message.content["seq"], json!(3));
+        assert_eq!(game.players["alice"].rtt, Duration::from_millis(500));
+
+        let out = game.handle(
+            message(MessageType::Ping, "alice", json!({ "seq": 1, "rtt_ms": 1e300 })),
+            addr(0),
+            now,
+        );
+        assert_eq!(out[0].message.content["seq"], json!(1));
+        assert_eq!(game.players["alice"].rtt, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn disconnect_and_kick_remove_the_player() {
+        let (mut game, now) = game_with(&["alice", "bob", "carol"]);
+        let out = game.handle(message(MessageType::Disconnect, "carol", Value::Null), addr(2), now);
+        assert_eq!(of_type(&out, MessageType::Chat).len(), 2);
+        assert!(!game.players.contains_key("carol"));
+
+        let (output, out) = game.admin(AdminCommand::Kick("bob".to_string()), now);
+        assert_eq!(output, "kicked bob");
+        assert_eq!(of_type(&out, MessageType::Kicked)[0].to, addr(1).to_string());
+        assert_eq!(of_type(&out, MessageType::DeletePlayer)[0].to, addr(0).to_string());
+        assert_eq!(game.players.len(), 1);
+    }
+
+    fn refusal(out: &[Outgoing]) -> String {
+        let failed = of_type(out, MessageType::ConnectFailed);
+        assert_eq!(failed.len(), 1);
+        failed[0].message.content.as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn server_password_is_required_to_join() {
+        let mut game = Game::with_seed(1, test_weapons(), 7);
+        game.server_password = Some("lan-party".to_string());
+        let now = Instant::now();
+        let out = game.handle(message(MessageType::Connect, "alice", Value::Null), addr(0), now);
+        assert_eq!(refusal(&out), "Wrong server password.");
+        let wrong = message(MessageType::Connect, "alice", json!({ "server_password": "lan" }));
+        assert_eq!(refusal(&game.handle(wrong, addr(0), now)), "Wrong server password.");
+
+        let right = message(MessageType::Connect, "alice", json!({ "server_password": "lan-party" }));
+        let out = game.handle(right, addr(0), now);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull).len(), 1);
+    }
+
+    // Comme `serve`: les vérifications Argon2 en attente tournent, puis leur résultat revient à la partie
+    fn handle_checked(game: &mut Game, message: MessageRecieve, src: SocketAddr, now: Instant) -> Vec<Outgoing> {
+        let mut out = game.handle(message, src, now);
+        for check in game.take_account_checks() {
+            out.extend(game.account_checked(check.run(), now));
+        }
+        out
+    }
+
+    #[test]
+    fn registered_names_are_reserved_for_their_owner() {
+        let mut game = Game::with_seed(1, test_weapons(), 7);
+        let now = Instant::now();
+        let register = message(MessageType::Connect, "alogou", json!({ "password": "maze-wars", "register": true }));
+        // Le hachage n'est pas fait par `handle`: la connexion attend sa vérification
+        assert!(game.handle(register.clone(), addr(0), now).is_empty());
+        let checks = game.take_account_checks();
+        assert_eq!(checks.len(), 1);
+        let out = game.account_checked(checks[0].clone().run(), now);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull).len(), 1);
+        assert!(game.accounts.is_registered("alogou"));
+        game.handle(message(MessageType::Disconnect, "alogou", Value::Null), addr(0), now);
+
+        let out = handle_checked(&mut game, message(MessageType::Connect, "alogou", Value::Null), addr(1), now);
+        assert_eq!(refusal(&out), "The name alogou is reserved: wrong or missing account password.");
+        let impostor = message(MessageType::Connect, "alogou", json!({ "password": "guess" }));
+        let out = handle_checked(&mut game, impostor, addr(1), now);
+        assert_eq!(refusal(&out), "The name alogou is reserved: wrong or missing account password.");
+        let owner = message(MessageType::Connect, "alogou", json!({ "password": "maze-wars" }));
+        let out = handle_checked(&mut game, owner, addr(0), now);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull).len(), 1);
+
+        let (output, _) = game.admin(AdminCommand::Unregister("alogou".to_string()), now);
+        assert_eq!(output, "unregistered alogou");
+        assert!(!game.accounts.is_registered("alogou"));
+        // La console fait hacher le mot de passe par `serve` avant `registered`
+        assert_eq!(game.registered("alogou", new_password_hash("maze-wars")), "registered alogou");
+        assert_eq!(game.registered("alogou", new_password_hash("maze-wars")), "alogou is already registered");
+        assert_eq!(game.registered("bob", new_password_hash("abc")), "passwords need at least 4 characters");
+    }
+
+    #[test]
+    fn password_guesses_are_limited_per_address() {
+        let mut game = Game::with_seed(1, test_weapons(), 7);
+        game.accounts.add("alogou", hash_password("maze-wars").unwrap()).unwrap();
+        let now = Instant::now();
+        for _ in 0..PASSWORD_RATE_LIMIT {
+            let guess = message(MessageType::Connect, "alogou", json!({ "password": "guess" }));
+            let out = handle_checked(&mut game, guess, addr(1), now);
+            assert_eq!(refusal(&out), "The name alogou is reserved: wrong or missing account password.");
+        }
+        // Refusé sans calcul Argon2, même avec le bon mot de passe
+        let owner = message(MessageType::Connect, "alogou", json!({ "password": "maze-wars" }));
+        assert_eq!(refusal(&game.handle(owner.clone(), addr(1), now)), "Too many connection attempts, please wait.");
+        assert!(game.take_account_checks().is_empty());
+
+        // Les autres adresses ne sont pas touchées, et la limite passe avec le temps
+        let out = handle_checked(&mut game, owner.clone(), addr(0), now);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull).len(), 1);
+        game.handle(message(MessageType::Disconnect, "alogou", Value::Null), addr(0), now);
+        let out = handle_checked(&mut game, owner, addr(1), now + PASSWORD_RATE_WINDOW);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull).len(), 1);
+    }
+
+    #[test]
+    fn banned_players_cannot_reconnect() {
+        let (mut game, now) = game_with(&["alice", "bob"]);
+        game.admin(AdminCommand::Ban("bob".to_string()), now);
+        assert!(!game.players.contains_key("bob"));
+        let out = game.handle(message(MessageType::Connect, "bobby", Value::Null), addr(1), now);
+        assert_eq!(out[0].message.message_type, MessageType::ConnectFailed);
+    }
+
+    // Aucun message ne part vers l'adresse fictive des bots
+    fn assert_no_bot_address(out: &[Outgoing]) {
+        assert!(out.iter().all(|o| !o.to.starts_with("0.0.0.0")));
+    }
+
+    // Place le bot `name` à `position`, sans chemin en cours
+    fn place_bot(game: &mut Game, name: &str, position: Vec3, now: Instant) {
+        let player = game.players.get_mut(name).unwrap();
+        player.position = position;
+        // Le point d'apparition enregistré au même instant masquerait la nouvelle position
+        player.history = PositionHistory::default();
+        player.history.record(now, position);
+        game.bots.get_mut(name).unwrap().path.clear();
+    }
+
+    #[test]
+    fn bots_fill_free_slots_and_make_room_for_players() {
+        let (mut game, now) = game_with(&["alice"]);
+        game.max_players = 3;
+        game.bot_settings.count = 5;
+        for step in 0..10 {
+            let out = game.tick(now + Duration::from_millis(600 * step));
+            assert_no_bot_address(&out);
+        }
+        assert_eq!(game.bots.len(), 2);
+        assert_eq!(game.players.len(), 3);
+
+        let later = now + Duration::from_secs(10);
+        let out = game.handle(message(MessageType::Connect, "bob", Value::Null), addr(1), later);
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull).len(), 1);
+        assert_eq!(of_type(&out, MessageType::DeletePlayer)[0].to, addr(0).to_string());
+        assert_eq!(game.bots.len(), 1);
+
+        let (_, out) = game.admin(AdminCommand::Bots(0), later);
+        assert_no_bot_address(&out);
+        game.tick(later);
+        assert!(game.bots.is_empty());
+        assert_eq!(game.players.len(), 2);
+    }
+
+    #[test]
+    fn bot_shoots_a_visible_player_with_projectiles() {
+        let (mut game, now) = game_with(&["alice"]);
+        game.bot_settings = BotSettings {
+            count: 1,
+            skill: 1.0,
+            reaction: Duration::ZERO,
+        };
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+        game.tick(now);
+        let bot = game.bots.keys().next().unwrap().clone();
+        place_bot(&mut game, &bot, Vec3::new(16.0, 1.0, 6.0), now);
+
+        let mut damaged = false;
+        for step in 1..40 {
+            let out = game.tick(now + Duration::from_millis(50 * step));
+            assert_no_bot_address(&out);
+            damaged |= of_type(&out, MessageType::Damage).iter().any(|o| o.to == addr(0).to_string());
+        }
+        assert!(damaged);
+        assert!(game.players["alice"].life < MAX_LIFE);
+    }
+
+    #[test]
+    fn server_info_counts_bots_removed_before_the_next_tick() {
+        let (mut game, now) = game_with(&["alice"]);
+        game.bot_settings.count = 2;
+        game.tick(now);
+        game.tick(now + Duration::from_secs(1));
+        assert_eq!(game.bots.len(), 2);
+        let bot = game.bots.keys().next().unwrap().clone();
+        game.admin(AdminCommand::Kick(bot), now);
+        let info = game.server_info();
+        assert_eq!((info["players"].clone(), info["bots"].clone()), (json!(1), json!(1)));
+
+        game.admin(AdminCommand::Level(2), now);
+        let info = game.server_info();
+        assert_eq!((info["players"].clone(), info["bots"].clone()), (json!(0), json!(0)));
+    }
+
+    #[test]
+    fn dead_bots_are_deleted_and_replaced() {
+        let (mut game, now) = game_with(&["alice"]);
+        game.bot_settings.count = 1;
+        game.tick(now);
+        let bot = game.bots.keys().next().unwrap().clone();
+        place_bot(&mut game, &bot, Vec3::new(16.0, 1.0, 6.0), now);
+        move_to(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), now);
+
+        rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now);
+        let out = rail_shot(&mut game, "alice", Vec3::new(8.0, 1.0, 6.0), Vec3::X, now + Duration::from_secs(2));
+        assert_no_bot_address(&out);
+        assert!(game.bots[&bot].dead);
+
+        let out = game.tick(now + Duration::from_secs(2));
+        let deleted = of_type(&out, MessageType::DeletePlayer);
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].to, addr(0).to_string());
+        assert!(game.bots.is_empty());
+
+        game.tick(now + Duration::from_secs(8));
+        assert_eq!(game.bots.len(), 1);
+        assert!(!game.bots.contains_key(&bot));
+    }
+
+    #[test]
+    fn bots_do_not_use_up_player_ids() {
+        let (mut game, now) = game_with(&["alice"]);
+        game.bot_settings.count = 1;
+        for second in 0..60 {
+            let at = now + Duration::from_secs(second);
+            game.tick(at);
+            // Le bot meurt et sera remplacé après `BOT_RESPAWN_DELAY`
+            for bot in game.bots.values_mut() {
+                bot.dead = true;
+            }
+        }
+        assert!(game.players.values().all(|player| player.player_name == "alice" || player.id >= FIRST_BOT_ID));
+        let out = game.handle(message(MessageType::Connect, "bob", Value::Null), addr(1), now + Duration::from_secs(60));
+        assert_eq!(of_type(&out, MessageType::ConnectSuccessfull)[0].message.id_player, 1);
+    }
+}
@@ -0,0 +1,52 @@
+use bevy::math::Vec3;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Durée d'historique gardée par joueur, supérieure à toute fenêtre de rembobinage raisonnable
+pub const HISTORY_DURATION: Duration = Duration::from_secs(1);
+pub const DEFAULT_MAX_REWIND: Duration = Duration::from_millis(200);
+
+// Positions récentes d'un joueur, pour valider les tirs à l'instant vu par le tireur
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionHistory {
+    samples: VecDeque<(Instant, Vec3)>,
+}
+
+impl PositionHistory {
+    pub fn record(&mut self, now: Instant, position: Vec3) {
+        self.samples.push_back((now, position));
+        while let Some(&(time, _)) = self.samples.front() {
+            if now.duration_since(time) > HISTORY_DURATION {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Position at `time`, interpolated between the two surrounding samples.
+    /// Before the oldest sample the oldest position is used, after the newest the newest.
+    pub fn position_at(&self, time: Instant) -> Option<Vec3> {
+        let &(first_time, first_position) = self.samples.front()?;
+        if time <= first_time {
+            return Some(first_position);
+        }
+        for (&(before_time, before), &(after_time, after)) in self.samples.iter().zip(self.samples.iter().skip(1)) {
+            if time <= after_time {
+                let span = after_time.duration_since(before_time).as_secs_f32();
+                let t = if span > 0.0 {
+                    time.duration_since(before_time).as_secs_f32() / span
+                } else {
+                    1.0
+                };
+                return Some(before.lerp(after, t));
+            }
+        }
+        self.samples.back().map(|&(_, position)| position)
+    }
+}
+
+/// How far back to rewind a shooter's opponents: half its round trip, bounded by `max_rewind`.
+pub fn rewind_duration(rtt: Duration, max_rewind: Duration) -> Duration {
+    (rtt / 2).min(max_rewind)
+}
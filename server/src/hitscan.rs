@@ -0,0 +1,57 @@
+use bevy::math::Vec3;
+
+use crate::{ray_sphere, Maze};
+
+// Rayon du volume de touche d'un joueur, comme `check_ball_player_collisions` côté client
+pub const HIT_RADIUS: f32 = 1.0;
+pub const HITSCAN_RANGE: f32 = 100.0;
+// Écart maximal accepté entre l'origine du tir et la dernière position connue du tireur
+pub const MAX_ORIGIN_ERROR: f32 = 2.0;
+// Tolérance ajoutée au rayon de touche pour valider l'impact d'un projectile
+pub const PROJECTILE_HIT_TOLERANCE: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShotOutcome {
+    pub target: Option<usize>,
+    pub point: Vec3,
+}
+
+/// Traces a shot through the maze and the targets' hit volumes and returns the first hit.
+/// `targets` are (player id, position) pairs, already rewound to the shooter's view time.
+pub fn resolve_hitscan(maze: &Maze, origin: Vec3, direction: Vec3, targets: &[(usize, Vec3)]) -> ShotOutcome {
+    let direction = direction.normalize_or_zero();
+    let end = origin + direction * HITSCAN_RANGE;
+    let wall_distance = maze
+        .raycast(origin, end)
+        .map_or(HITSCAN_RANGE, |hit| hit.distance);
+
+    let mut outcome = ShotOutcome {
+        target: None,
+        point: origin + direction * wall_distance,
+    };
+    let mut nearest = wall_distance;
+    for &(id, position) in targets {
+        if let Some(distance) = ray_sphere(origin, direction, position, HIT_RADIUS) {
+            if distance < nearest {
+                nearest = distance;
+                outcome = ShotOutcome {
+                    target: Some(id),
+                    point: origin + direction * distance,
+                };
+            }
+        }
+    }
+    outcome
+}
+
+pub fn parse_vec3(value: &serde_json::Value) -> Option<Vec3> {
+    let array = value.as_array()?;
+    if array.len() != 3 {
+        return None;
+    }
+    Some(Vec3::new(
+        array[0].as_f64()? as f32,
+        array[1].as_f64()? as f32,
+        array[2].as_f64()? as f32,
+    ))
+}
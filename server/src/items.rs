@@ -0,0 +1,104 @@
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+use crate::Maze;
+
+// Distance maximale entre un joueur et un objet pour le ramasser
+pub const PICKUP_RADIUS: f32 = 1.2;
+pub const MAX_LIFE: i64 = 10;
+pub const HEALTH_PACK_LIFE: i64 = 3;
+pub const EFFECT_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    HealthPack,
+    SpeedBoost,
+    Shield,
+    RapidFire,
+}
+
+impl ItemKind {
+    // Code de la case dans le labyrinthe du serveur
+    pub fn from_cell(cell: u8) -> Option<ItemKind> {
+        match cell {
+            2 => Some(ItemKind::HealthPack),
+            3 => Some(ItemKind::SpeedBoost),
+            4 => Some(ItemKind::Shield),
+            5 => Some(ItemKind::RapidFire),
+            _ => None,
+        }
+    }
+
+    pub fn respawn_time(&self) -> Duration {
+        match self {
+            ItemKind::HealthPack => Duration::from_secs(20),
+            _ => Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemSpawn {
+    pub id: usize,
+    pub kind: ItemKind,
+    pub position: Vec3,
+    // None quand l'objet est disponible
+    pub respawn_at: Option<Instant>,
+}
+
+impl ItemSpawn {
+    pub fn is_available(&self) -> bool {
+        self.respawn_at.is_none()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "kind": self.kind,
+            "position": self.position,
+            "available": self.is_available(),
+        })
+    }
+}
+
+/// One item per spawn cell of the maze, all available at start.
+pub fn item_spawns(maze: &Maze) -> Vec<ItemSpawn> {
+    let mut items = Vec::new();
+    for (row, cells) in maze.layout.iter().enumerate() {
+        for (col, &cell) in cells.iter().enumerate() {
+            if let Some(kind) = ItemKind::from_cell(cell) {
+                items.push(ItemSpawn {
+                    id: items.len(),
+                    kind,
+                    position: Vec3::new(col as f32 * maze.cell_size, 1.0, row as f32 * maze.cell_size),
+                    respawn_at: None,
+                });
+            }
+        }
+    }
+    items
+}
+
+/// Marks every item whose timer has elapsed as available again and returns them.
+pub fn respawn_items(items: &mut [ItemSpawn], now: Instant) -> Vec<&ItemSpawn> {
+    let mut respawned = Vec::new();
+    for item in items.iter_mut() {
+        if item.respawn_at.is_some_and(|at| now >= at) {
+            item.respawn_at = None;
+            respawned.push(&*item);
+        }
+    }
+    respawned
+}
+
+/// Index of the available item within reach of `position`, if any.
+pub fn item_in_reach(items: &[ItemSpawn], position: Vec3) -> Option<usize> {
+    items.iter().position(|item| {
+        item.is_available()
+            && Vec3::new(item.position.x - position.x, 0.0, item.position.z - position.z).length()
+                <= PICKUP_RADIUS
+    })
+}
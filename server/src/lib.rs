@@ -1,5 +1,35 @@
 pub mod player;
 pub mod components;
+pub mod maze;
+pub mod hitscan;
+pub mod weapon;
+pub mod history;
+pub mod items;
+pub mod chat;
+pub mod accounts;
+pub mod admin;
+pub mod bot;
+pub mod discovery;
+pub mod game;
+pub mod net;
+pub mod netsim;
+pub mod secure;
+pub mod stats;
 
 pub use player::*;
-pub  use components::*;
\ No newline at end of file
+pub  use components::*;
+pub use maze::*;
+pub use hitscan::*;
+pub use weapon::*;
+pub use history::*;
+pub use items::*;
+pub use chat::*;
+pub use accounts::*;
+pub use admin::*;
+pub use bot::*;
+pub use discovery::*;
+pub use game::*;
+pub use net::*;
+pub use netsim::*;
+pub use secure::*;
+pub use stats::*;
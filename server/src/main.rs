@@ -1,10 +1,10 @@
 use local_ip_address::local_ip;
-use serde_json::{Error, Value};
-use std::collections::HashMap;
 use std::io::Write;
 use std::sync::Arc;
-use tokio::net::UdpSocket;
-use tokio::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::sync::{mpsc, oneshot};
 
 use server::*;
 
@@ -28,10 +28,103 @@ async fn main() -> std::io::Result<()> {
         eprintln!("Number must be between 1 and 3.");
         return Ok(());
     }
+    // Armes chargées depuis weapons.json; `cargo run -- --hitscan` rend tous les tirs instantanés
+    let mut weapons = load_weapons("weapons.json");
+    if std::env::args().any(|arg| arg == "--hitscan") {
+        for weapon in weapons.iter_mut() {
+            weapon.fire_mode = FireMode::Hitscan;
+        }
+    }
+    let mut game = Game::new(number, weapons);
+    // Fenêtre maximale de rembobinage pour la compensation de latence: `--max-rewind-ms=200`
+    if let Some(max_rewind) = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--max-rewind-ms=").and_then(|ms| ms.parse().ok()))
+    {
+        game.max_rewind = Duration::from_millis(max_rewind);
+    }
+    // Nombre maximal de joueurs: `--max-players=300`, pour les tests de charge
+    if let Some(max_players) = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--max-players=").and_then(|max| max.parse::<usize>().ok()))
+    {
+        game.max_players = max_players.max(1);
+    }
+    // Bots: `--bots=4` (ou `--bots=fill` pour toutes les places libres), `--bot-skill=0.8`, `--bot-reaction-ms=300`
+    if let Some(count) = std::env::args().find_map(|arg| arg.strip_prefix("--bots=").map(str::to_string)) {
+        game.bot_settings.count = if count == "fill" { usize::MAX } else { count.parse().unwrap_or(0) };
+    }
+    if let Some(skill) = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--bot-skill=").and_then(|skill| skill.parse::<f32>().ok()))
+    {
+        game.bot_settings.skill = skill.clamp(0.0, 1.0);
+    }
+    if let Some(reaction) = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--bot-reaction-ms=").and_then(|ms| ms.parse().ok()))
+    {
+        game.bot_settings.reaction = Duration::from_millis(reaction);
+    }
+    // Réseau simulé pour tester le netcode en local: `--netsim=latency=80ms,jitter=20ms,loss=5%`
+    let conditions = match std::env::args().find_map(|arg| arg.strip_prefix("--netsim=").map(str::to_string)) {
+        Some(spec) => match NetConditions::parse(&spec) {
+            Ok(conditions) => conditions,
+            Err(e) => {
+                eprintln!("Invalid --netsim: {}", e);
+                return Ok(());
+            }
+        },
+        None => NetConditions::default(),
+    };
+    // Stats des joueurs conservées entre deux lancements: `--stats=<fichier>`, stats.json par défaut
+    let stats_file = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--stats=").map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_STATS_FILE.to_string());
+    game.stats = match StatsStore::load(&stats_file) {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("Failed to load player stats: {}", e);
+            return Ok(());
+        }
+    };
+    // Mot de passe pour rejoindre: `--password=<mot de passe>`
+    game.server_password = std::env::args().find_map(|arg| arg.strip_prefix("--password=").map(str::to_string));
+    // Comptes qui réservent un nom: `--accounts=<fichier>`, accounts.json par défaut
+    let accounts_file = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--accounts=").map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_ACCOUNTS_FILE.to_string());
+    game.accounts = match Accounts::load(&accounts_file) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            eprintln!("Failed to load accounts: {}", e);
+            return Ok(());
+        }
+    };
+    if !game.accounts.is_empty() {
+        println!("{} registered names", game.accounts.len());
+    }
+    // Nom affiché dans la liste des serveurs du réseau local: `--server-name=<nom>`
+    if let Some(name) = std::env::args().find_map(|arg| arg.strip_prefix("--server-name=").map(str::to_string)) {
+        game.name = name;
+    }
+    // Canal chiffré: `--encryption=off|optional|required`, optionnel par défaut
+    let encryption = match std::env::args().find_map(|arg| arg.strip_prefix("--encryption=").map(str::to_string)) {
+        Some(mode) => match Encryption::parse(&mode) {
+            Ok(mode) => mode,
+            Err(e) => {
+                eprintln!("Invalid --encryption: {}", e);
+                return Ok(());
+            }
+        },
+        None => Encryption::default(),
+    };
+    // Clé fixe du serveur, créée au premier lancement: `--server-key=<fichier>`, server_key par défaut
+    let key_file = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--server-key=").map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_SERVER_KEY_FILE.to_string());
+    // Port TCP local pour la console d'administration: `--admin-port=9000`
+    let admin_port = std::env::args()
+        .find_map(|arg| arg.strip_prefix("--admin-port=").and_then(|port| port.parse::<u16>().ok()));
     println!("Starting server...");
 
     let port: &str = "8081";
-    let mut can = true;
     let ip = match local_ip() {
         Ok(ip) => ip,
         Err(_) => {
@@ -44,211 +137,86 @@ async fn main() -> std::io::Result<()> {
 
     // Bind the UDP socket
     let socket: Arc<UdpSocket> = Arc::new(UdpSocket::bind(socket_addr).await?);
+    if !conditions.is_perfect() {
+        println!("Simulating network conditions: {}", conditions);
+    }
+    let mut transport = Transport::with_conditions(socket, conditions, rand::random());
+    if encryption != Encryption::Off {
+        let identity = match ServerIdentity::load_or_create(&key_file) {
+            Ok(identity) => identity,
+            Err(e) => {
+                eprintln!("Failed to load the server key: {}", e);
+                return Ok(());
+            }
+        };
+        println!("Encryption {:?}, server key {}", encryption, identity.fingerprint());
+        transport = transport.with_sessions(Sessions::new(identity, encryption));
+    }
+    let socket = Arc::new(transport);
 
     println!("Server listening on {}:{}", &ip, &port);
 
-    let (tx, mut rx) = mpsc::channel(32);
-
-    let usernames = Arc::new(Mutex::new(HashMap::new()));
-
-    // Task to receive messages and send them through the channel
+    // Console d'administration: entrée standard et port TCP optionnel
+    let (admin_tx, admin_rx) = mpsc::channel::<AdminRequest>(8);
     {
-        let socket = socket.clone();
-        let tx = tx.clone();
+        let admin_tx = admin_tx.clone();
         tokio::spawn(async move {
-            let mut buf = vec![0; 1024];
-            loop {
-                let (amt, src) = match socket.recv_from(&mut buf).await {
-                    Ok((amt, src)) => (amt, src),
-                    Err(e) => {
-                        eprintln!("Failed to receive data: {}", e);
-                        continue;
-                    }
-                };
-
-                let msg: Result<MessageRecieve, Error> = serde_json::from_slice(&buf[..amt]);
-                match msg {
-                    Ok(message) => {
-                        if let Err(e) = tx.send((message, src)).await {
-                            eprintln!("Failed to send message to channel: {}", e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to deserialize message: {}", e);
-                    }
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match run_admin_line(&admin_tx, &line).await {
+                    Some(output) => println!("{}", output),
+                    None => break,
                 }
             }
         });
     }
-
-    let mut id: usize = 0;
-
-    // Task to process messages from the channel
-    while let Some((message, src)) = rx.recv().await {
-        println!("Received: {:?}", message);
-        // Handle the message based on its type
-        match message.message_type {
-            MessageType::Connect => {
-                let mut usernames = usernames.lock().await;
-
-                if usernames.contains_key(&message.player_name) {
-                    // Username is taken, send a response to choose another one
-                    // println!("EXISTùùù$$$$$$");
-                    let response = MessageSended {
-                        message_type: MessageType::ConnectFailed,
-                        player_name: message.player_name,
-                        content: Value::String(
-                            "Username already taken. Please choose another one.".to_string(),
-                        ),
-                        id_player: id.clone(),
-                        player_life: 0,
-                        level: number,
-                        canconnect: false,
-                        // Add other fields here if needed
-                    };
-
-                    let response_data = serde_json::to_vec(&response).unwrap();
-                    if let Err(e) = socket.send_to(&response_data, src).await {
-                        eprintln!("Failed to send response: {}", e);
-                    }
-                } else {
-                    // Username is available, add to the map and acknowledge connection
-                    let player = Player {
-                        player_name: message.player_name.clone(),
-                        ip_address: src.to_string(),
-                        id: id,
-                        life: 10,
-                    };
-                    let life_player = player.life;
-
-                    can = usernames.len() < 9;
-
-                    usernames.insert(message.player_name.clone(), player);
-
-                    println!("usernames added to map: {:?}", &usernames);
-                    let response = MessageSended {
-                        message_type: MessageType::ConnectSuccessfull,
-                        player_name: message.player_name,
-                        content: Value::String("Connected successfully".to_string()),
-                        id_player: id.clone(),
-                        player_life: life_player,
-                        level: number,
-                        canconnect: can,
-                        // Add other fields here if needed
-                    };
-
-                    let response_data = serde_json::to_vec(&response).unwrap();
-                    if let Err(e) = socket.send_to(&response_data, src).await {
-                        eprintln!("Failed to send response: {}", e);
-                    }
-
-                    id += 1;
-                }
-            }
-
-            MessageType::Disconnect => {
-                println!("player {} disconnected", &message.player_name);
-                let mut usernames = usernames.lock().await;
-                usernames.remove(&message.player_name);
-                // Handle disconnection
-            }
-            MessageType::Action => {
-                // Handle player action
-
-                // println!("player position {:?}", &message);
-                let clients = usernames.lock().await;
-                let mut life_player_to_move = 0;
-                for player in clients.values() {
-                    if player.player_name == message.player_name {
-                        life_player_to_move = player.life
-                    }
-                }
-                for player in clients.values() {
-                    if player.player_name != message.player_name {
-                        // println!("quelque chose: {:?}", &player);
-                        let response = MessageSended {
-                            message_type: MessageType::Action,
-                            player_name: message.player_name.clone(),
-                            content: message.content.clone(),
-                            id_player: message.id_player,
-                            player_life: life_player_to_move,
-                            level: number,
-                            canconnect: can,
-                        };
-
-                        // println!("player move {:?}", response);
-                        println!("content message {}", message.content.clone());
-
-                        let response_data = serde_json::to_vec(&response).unwrap();
-                        if let Err(e) = socket
-                            .send_to(&response_data, player.ip_address.clone())
-                            .await
-                        {
-                            eprintln!("Failed to send response: {}", e);
-                        }
-                    }
-                }
-            }
-            MessageType::UpdateLife => {
-                // println!("enter");
-                let mut clients = usernames.lock().await;
-                for player in clients.values_mut() {
-                    if player.id == message.id_player {
-                        if player.life <= 0 {
-                            let response = MessageSended {
-                                message_type: MessageType::PlayerDeath,
-                                player_name: message.player_name.clone(),
-                                content: message.content.clone(),
-                                id_player: message.id_player,
-                                player_life: player.life,
-                                level: number,
-                                canconnect: can,
-                            };
-
-                            // println!("player move {:?}", response);
-
-                            let response_data = serde_json::to_vec(&response).unwrap();
-                            if let Err(e) = socket
-                                .send_to(&response_data, player.ip_address.clone())
-                                .await
-                            {
-                                eprintln!("Failed to send response: {}", e);
-                            }
-                        } else {
-                            player.life -= 1;
-                            println!("player death {:?}", player);
-                        }
-                    }
-                }
-            }
-            MessageType::OtherDeadPlayer => {
-                let mut clients = usernames.lock().await;
-                for player in clients.values_mut() {
-                    if player.id != message.id_player {
-                        let response = MessageSended {
-                            message_type: MessageType::DeletePlayer,
-                            player_name: message.player_name.clone(),
-                            content: message.content.clone(),
-                            id_player: message.id_player,
-                            player_life: player.life,
-                            level: number,
-                            canconnect: can,
+    // Réponses aux clients qui cherchent les serveurs du réseau local
+    match UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT)).await {
+        Ok(discovery) => {
+            let game_address = socket.local_addr()?;
+            tokio::spawn(answer_discovery(discovery, game_address, admin_tx.clone()));
+            println!("Answering LAN discovery on port {} as \"{}\"", DISCOVERY_PORT, game.name);
+        }
+        // Un autre serveur sur la même machine a déjà le port
+        Err(e) => eprintln!("LAN discovery disabled: {}", e),
+    }
+    if let Some(admin_port) = admin_port {
+        let listener = TcpListener::bind(("127.0.0.1", admin_port)).await?;
+        println!("Admin console listening on 127.0.0.1:{}", admin_port);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let admin_tx = admin_tx.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let Some(output) = run_admin_line(&admin_tx, &line).await else {
+                            break;
                         };
-                        let response_data = serde_json::to_vec(&response).unwrap();
-                        if let Err(e) = socket
-                            .send_to(&response_data, player.ip_address.clone())
-                            .await
-                        {
-                            eprintln!("Failed to send response: {}", e);
+                        if writer.write_all(format!("{}\n", output).as_bytes()).await.is_err() {
+                            break;
                         }
                     }
-                }
-            }
-            _ => {
-                unimplemented!()
+                });
             }
-        }
+        });
     }
 
+    serve(socket, game, admin_rx).await;
+
     Ok(())
 }
+
+// Envoie une commande à la boucle principale et attend sa sortie; None si le serveur s'arrête
+async fn run_admin_line(
+    admin_tx: &mpsc::Sender<AdminRequest>,
+    line: &str,
+) -> Option<String> {
+    let command = match AdminCommand::parse(line) {
+        Ok(command) => command,
+        Err(error) => return Some(error),
+    };
+    let (reply_tx, reply_rx) = oneshot::channel();
+    admin_tx.send((command, reply_tx)).await.ok()?;
+    reply_rx.await.ok()
+}
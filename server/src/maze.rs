@@ -0,0 +1,204 @@
+use bevy::math::{IVec2, Vec2, Vec3};
+
+// Mêmes labyrinthes que le client (voir `setup` dans client/src/labyrinte.rs).
+// 1 = mur; 2 à 5 marquent les cases d'apparition des objets (voir `ItemKind::from_cell`).
+const LEVEL_1: [[u8; 14]; 15] = [
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 0, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
+    [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5, 1],
+    [1, 0, 0, 1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 1, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
+    [1, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 1],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+];
+
+const LEVEL_2: [[u8; 14]; 15] = [
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1, 1, 1, 0, 1, 0, 5, 0, 0, 1],
+    [1, 1, 1, 0, 1, 0, 0, 0, 1, 0, 1, 1, 0, 1],
+    [1, 2, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 1],
+    [1, 0, 1, 1, 1, 0, 1, 0, 0, 0, 0, 1, 0, 1],
+    [1, 0, 1, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1],
+    [1, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 1, 0, 1],
+    [1, 0, 0, 1, 0, 0, 4, 0, 1, 0, 1, 0, 0, 1],
+    [1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
+    [1, 0, 0, 1, 0, 1, 0, 0, 1, 1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 0, 0, 1, 1, 0, 1, 0, 1, 0, 1],
+    [1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3, 1],
+    [1, 0, 1, 0, 0, 0, 1, 0, 1, 1, 1, 1, 0, 1],
+    [1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+];
+
+const LEVEL_3: [[u8; 14]; 15] = [
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 0, 2, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 1],
+    [1, 1, 0, 1, 1, 1, 0, 1, 1, 0, 0, 1, 0, 1],
+    [1, 0, 0, 1, 0, 0, 0, 0, 1, 1, 0, 0, 0, 1],
+    [1, 1, 0, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
+    [1, 0, 0, 5, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1],
+    [1, 1, 1, 0, 1, 0, 1, 0, 0, 0, 1, 0, 0, 1],
+    [1, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 1],
+    [1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
+    [1, 0, 0, 1, 0, 0, 4, 0, 1, 1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1],
+    [1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 0, 0, 1, 0, 1, 0, 1, 0, 0, 1],
+    [1, 0, 1, 0, 0, 0, 1, 1, 0, 0, 0, 0, 3, 1],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+];
+
+pub const CELL_SIZE: f32 = 2.0;
+
+/// Nom du labyrinthe d'un niveau, pour la liste des serveurs.
+pub fn level_name(level: i32) -> &'static str {
+    match level {
+        2 => "medium maze",
+        3 => "hard maze",
+        _ => "easy maze",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Maze {
+    pub layout: Vec<Vec<u8>>,
+    pub cell_size: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WallHit {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Maze {
+    pub fn for_level(level: i32) -> Maze {
+        let layout = match level {
+            2 => LEVEL_2,
+            3 => LEVEL_3,
+            _ => LEVEL_1,
+        };
+        Maze {
+            layout: layout.iter().map(|row| row.to_vec()).collect(),
+            cell_size: CELL_SIZE,
+        }
+    }
+
+    pub fn cell_at(&self, position: Vec3) -> Option<(usize, usize)> {
+        let col = (position.x / self.cell_size + 0.5).floor();
+        let row = (position.z / self.cell_size + 0.5).floor();
+        if col < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (row, col) = (row as usize, col as usize);
+        if row < self.layout.len() && col < self.layout[row].len() {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+
+    // Les cases hors du labyrinthe comptent comme des murs
+    pub fn is_wall(&self, row: i32, col: i32) -> bool {
+        if row < 0 || col < 0 {
+            return true;
+        }
+        self.layout
+            .get(row as usize)
+            .and_then(|r| r.get(col as usize))
+            .is_none_or(|&cell| cell == 1)
+    }
+
+    /// Walks the grid from `from` to `to` on the XZ plane and returns the first wall crossed.
+    pub fn raycast(&self, from: Vec3, to: Vec3) -> Option<WallHit> {
+        let start = Vec2::new(from.x, from.z) / self.cell_size + Vec2::splat(0.5);
+        let end = Vec2::new(to.x, to.z) / self.cell_size + Vec2::splat(0.5);
+        let delta = end - start;
+        let length = (to - from).length();
+
+        let mut cell = IVec2::new(start.x.floor() as i32, start.y.floor() as i32);
+        let end_cell = IVec2::new(end.x.floor() as i32, end.y.floor() as i32);
+        let step = IVec2::new(
+            if delta.x > 0.0 { 1 } else if delta.x < 0.0 { -1 } else { 0 },
+            if delta.y > 0.0 { 1 } else if delta.y < 0.0 { -1 } else { 0 },
+        );
+        let t_delta = Vec2::new(
+            if step.x != 0 { 1.0 / delta.x.abs() } else { f32::INFINITY },
+            if step.y != 0 { 1.0 / delta.y.abs() } else { f32::INFINITY },
+        );
+        let mut t_max = Vec2::new(
+            match step.x {
+                1 => (cell.x as f32 + 1.0 - start.x) / delta.x,
+                -1 => (start.x - cell.x as f32) / -delta.x,
+                _ => f32::INFINITY,
+            },
+            match step.y {
+                1 => (cell.y as f32 + 1.0 - start.y) / delta.y,
+                -1 => (start.y - cell.y as f32) / -delta.y,
+                _ => f32::INFINITY,
+            },
+        );
+
+        let mut t = 0.0;
+        let mut normal = Vec3::ZERO;
+        loop {
+            if self.is_wall(cell.y, cell.x) {
+                return Some(WallHit {
+                    point: from.lerp(to, t),
+                    normal,
+                    distance: length * t,
+                });
+            }
+            if cell == end_cell {
+                return None;
+            }
+            if t_max.x < t_max.y {
+                t = t_max.x;
+                cell.x += step.x;
+                t_max.x += t_delta.x;
+                normal = Vec3::new(-step.x as f32, 0.0, 0.0);
+            } else {
+                t = t_max.y;
+                cell.y += step.y;
+                t_max.y += t_delta.y;
+                normal = Vec3::new(0.0, 0.0, -step.y as f32);
+            }
+            if t > 1.0 {
+                return None;
+            }
+        }
+    }
+
+    pub fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        self.raycast(from, to).is_none()
+    }
+}
+
+/// Distance along a normalized ray to the first intersection with a sphere.
+pub fn ray_sphere(origin: Vec3, direction: Vec3, center: Vec3, radius: f32) -> Option<f32> {
+    let offset = origin - center;
+    let b = offset.dot(direction);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    if -b - sqrt >= 0.0 {
+        Some(-b - sqrt)
+    } else if -b + sqrt >= 0.0 {
+        // L'origine est dans la sphère
+        Some(0.0)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::ToSocketAddrs;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{new_password_hash, AdminCommand, Game, MessageRecieve, MessageSended, MessageType, Transport};
+
+/// Une commande de la console et le canal où renvoyer sa sortie.
+pub type AdminRequest = (AdminCommand, oneshot::Sender<String>);
+
+/// Runs the server on `socket` until the socket fails: every datagram goes through `game`
+/// and the resulting messages are sent back. Admin commands arrive on `admin_rx`.
+pub async fn serve(socket: Arc<Transport>, mut game: Game, mut admin_rx: mpsc::Receiver<AdminRequest>) {
+    let (tx, mut rx) = mpsc::channel(32);
+
+    // Task to receive messages and send them through the channel
+    {
+        let socket = socket.clone();
+        tokio::spawn(async move {
+            // Taille maximale d'un datagramme UDP
+            let mut buf = vec![0; 65536];
+            loop {
+                let (amt, src, session) = match socket.recv_from(&mut buf).await {
+                    Ok(received) => received,
+                    Err(e) => {
+                        eprintln!("Failed to receive data: {}", e);
+                        continue;
+                    }
+                };
+
+                let msg: Result<MessageRecieve, Error> = serde_json::from_slice(&buf[..amt]);
+                match msg {
+                    Ok(message) => {
+                        if let Err(e) = tx.send((message, src, session)).await {
+                            eprintln!("Failed to send message to channel: {}", e);
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to deserialize message: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Objets à faire réapparaître et bots, 20 fois par seconde
+    let mut ticker = tokio::time::interval(Duration::from_millis(50));
+    // Résultats des vérifications Argon2, faites hors de la boucle
+    let (checked_tx, mut checked_rx) = mpsc::channel(32);
+    // Comptes créés par la console, une fois leur mot de passe haché
+    let (hashed_tx, mut hashed_rx) = mpsc::channel::<(String, Result<String, String>, oneshot::Sender<String>)>(8);
+
+    // Toute la logique est dans `Game`: cette boucle ne fait que lire et envoyer
+    loop {
+        let out = tokio::select! {
+            received = rx.recv() => match received {
+                Some((message, src, session)) => {
+                    if !matches!(message.message_type, MessageType::Ping) {
+                        println!("Received: {:?}", message);
+                    }
+                    let disconnect = message.message_type == MessageType::Disconnect;
+                    let out = game.handle_in_session(message, src, session, Instant::now());
+                    // Un Disconnect chiffré clôt la session: l'adresse peut en négocier une autre
+                    if let (true, Some(session)) = (disconnect, session) {
+                        socket.end_session(src, session);
+                    }
+                    out
+                }
+                None => break,
+            },
+            _ = ticker.tick() => game.tick(Instant::now()),
+            Some(checked) = checked_rx.recv() => game.account_checked(checked, Instant::now()),
+            Some((name, hash, reply)) = hashed_rx.recv() => {
+                let _ = reply.send(game.registered(&name, hash));
+                continue;
+            }
+            Some((command, reply)) = admin_rx.recv() => {
+                // Le réseau simulé appartient au transport, pas à la partie
+                if let AdminCommand::NetSim(conditions) = command {
+                    if let Some(conditions) = conditions {
+                        socket.set_conditions(conditions);
+                    }
+                    let _ = reply.send(format!("network simulation: {}", socket.conditions()));
+                    continue;
+                }
+                // Argon2 prend du temps: le mot de passe est haché à côté de la boucle
+                if let AdminCommand::Register(name, password) = command {
+                    if game.accounts.is_registered(&name) {
+                        let _ = reply.send(format!("{} is already registered", name));
+                        continue;
+                    }
+                    let hashed_tx = hashed_tx.clone();
+                    tokio::spawn(async move {
+                        if let Ok(hash) = tokio::task::spawn_blocking(move || new_password_hash(&password)).await {
+                            let _ = hashed_tx.send((name, hash, reply)).await;
+                        }
+                    });
+                    continue;
+                }
+                let (output, out) = game.admin(command, Instant::now());
+                let _ = reply.send(output);
+                out
+            }
+        };
+        for check in game.take_account_checks() {
+            let checked_tx = checked_tx.clone();
+            tokio::spawn(async move {
+                if let Ok(checked) = tokio::task::spawn_blocking(move || check.run()).await {
+                    let _ = checked_tx.send(checked).await;
+                }
+            });
+        }
+        for outgoing in out {
+            send_response(&socket, &outgoing.message, outgoing.to).await;
+        }
+    }
+}
+
+pub async fn send_response(socket: &Transport, response: &MessageSended, addr: impl ToSocketAddrs) {
+    let response_data = serde_json::to_vec(response).unwrap();
+    if let Err(e) = socket.send_to(&response_data, addr).await {
+        eprintln!("Failed to send response: {}", e);
+    }
+}
@@ -0,0 +1,314 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{ToSocketAddrs, UdpSocket};
+use tokio::time::Instant;
+
+use crate::secure::{Incoming, SessionId, Sessions};
+
+// Retard ajouté à un datagramme « réordonné »: les suivants le doublent
+pub const REORDER_DELAY: Duration = Duration::from_millis(40);
+
+/// Simulated network conditions, e.g. `latency=80ms,jitter=20ms,loss=5%,dup=1%,reorder=2%`.
+/// Each direction of a [`Transport`] goes through them on its own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetConditions {
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub loss: f64,
+    pub duplicate: f64,
+    pub reorder: f64,
+}
+
+impl NetConditions {
+    /// Parses comma separated `key=value` settings; `off` or an empty string is a perfect network.
+    pub fn parse(spec: &str) -> Result<NetConditions, String> {
+        let mut conditions = NetConditions::default();
+        let spec = spec.trim();
+        if spec.is_empty() || spec == "off" {
+            return Ok(conditions);
+        }
+        for setting in spec.split(',') {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got `{}`", setting.trim()))?;
+            let value = value.trim();
+            match key.trim() {
+                "latency" => conditions.latency = parse_millis(value)?,
+                "jitter" => conditions.jitter = parse_millis(value)?,
+                "loss" => conditions.loss = parse_percent(value)?,
+                "dup" | "duplicate" => conditions.duplicate = parse_percent(value)?,
+                "reorder" => conditions.reorder = parse_percent(value)?,
+                other => return Err(format!("unknown setting `{}`", other)),
+            }
+        }
+        Ok(conditions)
+    }
+
+    pub fn is_perfect(&self) -> bool {
+        *self == NetConditions::default()
+    }
+
+    /// Délais des copies d'un datagramme: aucune s'il est perdu, deux s'il est dupliqué.
+    pub fn fate(&self, rng: &mut impl Rng) -> Vec<Duration> {
+        if rng.gen_bool(self.loss) {
+            return vec![];
+        }
+        let mut delay = self.latency;
+        if !self.jitter.is_zero() {
+            let jitter = self.jitter.as_secs_f64();
+            let offset = rng.gen_range(-jitter..=jitter);
+            delay = Duration::from_secs_f64((self.latency.as_secs_f64() + offset).max(0.0));
+        }
+        if rng.gen_bool(self.reorder) {
+            delay += REORDER_DELAY;
+        }
+        let mut delays = vec![delay];
+        if rng.gen_bool(self.duplicate) {
+            delays.push(delay + Duration::from_millis(rng.gen_range(0..=10)));
+        }
+        delays
+    }
+}
+
+impl fmt::Display for NetConditions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_perfect() {
+            return write!(f, "off");
+        }
+        write!(
+            f,
+            "latency={}ms,jitter={}ms,loss={}%,dup={}%,reorder={}%",
+            self.latency.as_millis(),
+            self.jitter.as_millis(),
+            self.loss * 100.0,
+            self.duplicate * 100.0,
+            self.reorder * 100.0
+        )
+    }
+}
+
+fn parse_millis(value: &str) -> Result<Duration, String> {
+    value
+        .strip_suffix("ms")
+        .unwrap_or(value)
+        .trim()
+        .parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("invalid duration `{}`, expected milliseconds", value))
+}
+
+fn parse_percent(value: &str) -> Result<f64, String> {
+    match value.strip_suffix('%').unwrap_or(value).trim().parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent / 100.0),
+        _ => Err(format!("invalid percentage `{}`", value)),
+    }
+}
+
+// Datagramme reçu en attente de livraison: (échéance, numéro d'arrivée, source, données)
+type Delayed = Reverse<(Instant, u64, SocketAddr, Vec<u8>)>;
+
+/// UDP socket wrapper that delays, drops, duplicates and reorders datagrams in both
+/// directions. With the default conditions it behaves exactly like the socket.
+/// With [`Sessions`] it also encrypts the traffic of the clients that asked for it.
+pub struct Transport {
+    socket: Arc<UdpSocket>,
+    conditions: Mutex<NetConditions>,
+    rng: Mutex<StdRng>,
+    incoming: Mutex<BinaryHeap<Delayed>>,
+    arrivals: AtomicU64,
+    sessions: Option<Mutex<Sessions>>,
+}
+
+impl Transport {
+    pub fn new(socket: Arc<UdpSocket>) -> Self {
+        Transport::with_conditions(socket, NetConditions::default(), rand::random())
+    }
+
+    pub fn with_conditions(socket: Arc<UdpSocket>, conditions: NetConditions, seed: u64) -> Self {
+        Transport {
+            socket,
+            conditions: Mutex::new(conditions),
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            incoming: Mutex::new(BinaryHeap::new()),
+            arrivals: AtomicU64::new(0),
+            sessions: None,
+        }
+    }
+
+    /// Accepts encrypted sessions; plaintext clients are still served unless the mode is `Required`.
+    pub fn with_sessions(mut self, sessions: Sessions) -> Self {
+        self.sessions = Some(Mutex::new(sessions));
+        self
+    }
+
+    pub fn conditions(&self) -> NetConditions {
+        self.conditions.lock().unwrap().clone()
+    }
+
+    /// Changes the conditions at runtime, e.g. from the admin console.
+    pub fn set_conditions(&self, conditions: NetConditions) {
+        *self.conditions.lock().unwrap() = conditions;
+    }
+
+    /// Forgets the session `id` of `addr`, so that the address can negotiate a new one.
+    pub fn end_session(&self, addr: SocketAddr, id: SessionId) {
+        if let Some(sessions) = &self.sessions {
+            sessions.lock().unwrap().end(addr, id);
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    fn fate(&self) -> Vec<Duration> {
+        let conditions = self.conditions.lock().unwrap();
+        if conditions.is_perfect() {
+            return vec![Duration::ZERO];
+        }
+        conditions.fate(&mut *self.rng.lock().unwrap())
+    }
+
+    pub async fn send_to(&self, data: &[u8], addr: impl ToSocketAddrs) -> io::Result<usize> {
+        let addr = tokio::net::lookup_host(addr)
+            .await?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send to"))?;
+        match &self.sessions {
+            Some(sessions) => {
+                let sealed = sessions.lock().unwrap().send(data, addr);
+                self.send_simulated(&sealed, addr).await?;
+            }
+            None => self.send_simulated(data, addr).await?,
+        }
+        Ok(data.len())
+    }
+
+    async fn send_simulated(&self, data: &[u8], addr: SocketAddr) -> io::Result<()> {
+        // Un datagramme perdu passe pour envoyé, comme sur un vrai réseau
+        for delay in self.fate() {
+            if delay.is_zero() {
+                self.socket.send_to(data, addr).await?;
+                continue;
+            }
+            let socket = self.socket.clone();
+            let data = data.to_vec();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if let Err(e) = socket.send_to(&data, addr).await {
+                    eprintln!("Failed to send delayed datagram: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Receives the next datagram for the game, decrypted if it was sealed, and the session it
+    /// arrived on. Handshakes are answered here and forged or replayed datagrams are dropped.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr, Option<SessionId>)> {
+        loop {
+            let (len, from) = self.recv_simulated(buf).await?;
+            let Some(sessions) = &self.sessions else {
+                return Ok((len, from, None));
+            };
+            let incoming = sessions.lock().unwrap().receive(&buf[..len], from);
+            match incoming {
+                Incoming::Deliver(data, session) => {
+                    let len = data.len().min(buf.len());
+                    buf[..len].copy_from_slice(&data[..len]);
+                    return Ok((len, from, session));
+                }
+                Incoming::Reply(reply) => {
+                    if let Err(e) = self.send_simulated(&reply, from).await {
+                        eprintln!("Failed to answer the handshake of {}: {}", from, e);
+                    }
+                }
+                Incoming::Drop(reason) => eprintln!("Dropped datagram from {}: {}", from, reason),
+            }
+        }
+    }
+
+    // Prochain datagramme dont le délai simulé est écoulé
+    async fn recv_simulated(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        loop {
+            let next_due = {
+                let mut incoming = self.incoming.lock().unwrap();
+                match incoming.peek().map(|Reverse((due, ..))| *due) {
+                    Some(due) if due <= Instant::now() => {
+                        let Reverse((_, _, from, data)) = incoming.pop().unwrap();
+                        let len = data.len().min(buf.len());
+                        buf[..len].copy_from_slice(&data[..len]);
+                        return Ok((len, from));
+                    }
+                    due => due,
+                }
+            };
+
+            let received = match next_due {
+                Some(due) => tokio::select! {
+                    received = self.socket.recv_from(buf) => Some(received?),
+                    _ = tokio::time::sleep_until(due) => None,
+                },
+                None => Some(self.socket.recv_from(buf).await?),
+            };
+            let Some((len, from)) = received else {
+                continue;
+            };
+            let delays = self.fate();
+            // Réseau parfait et rien en attente: livré tout de suite
+            if next_due.is_none() && delays == [Duration::ZERO] {
+                return Ok((len, from));
+            }
+            let now = Instant::now();
+            let mut incoming = self.incoming.lock().unwrap();
+            for delay in delays {
+                let arrival = self.arrivals.fetch_add(1, Ordering::Relaxed);
+                incoming.push(Reverse((now + delay, arrival, from, buf[..len].to_vec())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_and_prints_them_back() {
+        let conditions = NetConditions::parse("latency=80ms, jitter=20, loss=5%,dup=1%,reorder=2").unwrap();
+        assert_eq!(conditions.latency, Duration::from_millis(80));
+        assert_eq!(conditions.jitter, Duration::from_millis(20));
+        assert_eq!(conditions.loss, 0.05);
+        assert_eq!(conditions.duplicate, 0.01);
+        assert_eq!(conditions.reorder, 0.02);
+        assert_eq!(NetConditions::parse(&conditions.to_string()).unwrap(), conditions);
+        assert!(NetConditions::parse("off").unwrap().is_perfect());
+
+        assert!(NetConditions::parse("loss=150%").is_err());
+        assert!(NetConditions::parse("latency=fast").is_err());
+        assert_eq!(NetConditions::parse("speed=1").unwrap_err(), "unknown setting `speed`");
+    }
+
+    #[test]
+    fn fates_follow_the_configured_rates() {
+        let conditions = NetConditions::parse("latency=50ms,jitter=10ms,loss=20%,dup=10%").unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let fates: Vec<_> = (0..10_000).map(|_| conditions.fate(&mut rng)).collect();
+
+        let lost = fates.iter().filter(|delays| delays.is_empty()).count();
+        let duplicated = fates.iter().filter(|delays| delays.len() == 2).count();
+        assert!((1_800..2_200).contains(&lost), "lost {}", lost);
+        assert!((600..1_000).contains(&duplicated), "duplicated {}", duplicated);
+        for delay in fates.iter().flatten() {
+            assert!(*delay >= Duration::from_millis(40) && *delay <= Duration::from_millis(70));
+        }
+    }
+}
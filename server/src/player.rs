@@ -1,18 +1,76 @@
-#[derive(Debug, Clone,PartialEq, Eq)]
+use bevy::math::Vec3;
+use std::time::{Duration, Instant};
+
+use crate::{ChatLimiter, ItemKind, PlayerStats, PositionHistory, SessionId, Weapon, WeaponState, EFFECT_DURATION, HEALTH_PACK_LIFE, MAX_LIFE};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Player {
     pub player_name: String,
     pub ip_address: String,
+    // Session chiffrée sur laquelle le joueur s'est connecté: ses messages doivent y arriver
+    pub session: Option<SessionId>,
     pub id: usize,
-    pub life: i64
+    pub life: i64,
+    // Dernière position reçue dans un message Action
+    pub position: Vec3,
+    pub history: PositionHistory,
+    // Aller-retour mesuré par le client et transmis dans ses pings
+    pub rtt: Duration,
+    pub weapon: WeaponState,
+    // Bouclier ramassé: aucun dégât jusqu'à cet instant
+    pub shield_until: Option<Instant>,
+    pub chat: ChatLimiter,
+    // Compteurs de la partie en cours et son début; None une fois la partie terminée
+    pub stats: PlayerStats,
+    pub joined_at: Option<Instant>,
 }
 
 impl Player {
-    pub fn new_player(player_name: String, ip_address: String, id: usize, life: i64) -> Player {
+    pub fn new_player(player_name: String, ip_address: String, id: usize, life: i64, weapons: &[Weapon]) -> Player {
         Player {
             player_name,
             ip_address,
+            session: None,
             id,
-            life
+            life,
+            position: Vec3::ZERO,
+            history: PositionHistory::default(),
+            rtt: Duration::ZERO,
+            weapon: WeaponState::new(weapons),
+            shield_until: None,
+            chat: ChatLimiter::default(),
+            stats: PlayerStats::default(),
+            joined_at: None,
+        }
+    }
+
+    /// Removes `damage` lives and returns true if the player just died.
+    pub fn take_damage(&mut self, damage: i64, now: Instant) -> bool {
+        if self.shield_until.is_some_and(|until| now < until) {
+            return false;
+        }
+        let was_alive = self.life > 0;
+        self.life -= damage;
+        was_alive && self.life <= 0
+    }
+
+    /// Applies a picked-up item and returns how long its effect lasts.
+    pub fn apply_item(&mut self, kind: ItemKind, now: Instant) -> Duration {
+        match kind {
+            ItemKind::HealthPack => {
+                self.life = (self.life + HEALTH_PACK_LIFE).min(MAX_LIFE);
+                Duration::ZERO
+            }
+            ItemKind::Shield => {
+                self.shield_until = Some(now + EFFECT_DURATION);
+                EFFECT_DURATION
+            }
+            ItemKind::RapidFire => {
+                self.weapon.rapid_fire_until = Some(now + EFFECT_DURATION);
+                EFFECT_DURATION
+            }
+            // La vitesse est appliquée par le client
+            ItemKind::SpeedBoost => EFFECT_DURATION,
         }
     }
 }
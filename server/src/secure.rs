@@ -0,0 +1,491 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+// Premier octet d'un datagramme chiffré ou de la poignée de main; le JSON en clair commence par `{`
+pub const HELLO: u8 = 0xE1;
+pub const HELLO_REPLY: u8 = 0xE2;
+pub const SEALED: u8 = 0xE3;
+// Type, puis compteur du paquet qui sert de nonce
+const SEALED_HEADER: usize = 1 + 8;
+const KEY_INFO: &[u8] = b"maze-wars transport v1";
+pub const DEFAULT_SERVER_KEY_FILE: &str = "server_key";
+// Sessions sans trafic oubliées après ce délai
+pub const SESSION_TIMEOUT: Duration = Duration::from_secs(600);
+// Un client utilise sa session dès la réponse: une poignée de main inachevée est vite oubliée
+pub const PENDING_TIMEOUT: Duration = Duration::from_secs(10);
+// Poignées de main inachevées gardées au plus; chacune a coûté deux calculs x25519
+pub const MAX_PENDING_HANDSHAKES: usize = 256;
+
+/// Numéro d'une session chiffrée, unique pour la durée du serveur.
+pub type SessionId = u64;
+
+/// Whether clients must, may or cannot use the encrypted channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encryption {
+    Off,
+    #[default]
+    Optional,
+    Required,
+}
+
+impl Encryption {
+    pub fn parse(value: &str) -> Result<Encryption, String> {
+        match value {
+            "off" => Ok(Encryption::Off),
+            "optional" => Ok(Encryption::Optional),
+            "required" => Ok(Encryption::Required),
+            other => Err(format!("unknown encryption mode `{}`, expected off, optional or required", other)),
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Fenêtre glissante de 64 paquets: chaque compteur n'est accepté qu'une fois.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayWindow {
+    highest: Option<u64>,
+    // Bit n: le paquet `highest - n` a déjà été reçu
+    seen: u64,
+}
+
+impl ReplayWindow {
+    /// Returns true and remembers `counter` if it is new and not too old.
+    pub fn accept(&mut self, counter: u64) -> bool {
+        let Some(highest) = self.highest else {
+            self.highest = Some(counter);
+            self.seen = 1;
+            return true;
+        };
+        if counter > highest {
+            let shift = counter - highest;
+            self.seen = if shift >= 64 { 0 } else { self.seen << shift };
+            self.seen |= 1;
+            self.highest = Some(counter);
+            return true;
+        }
+        let age = highest - counter;
+        if age >= 64 || self.seen & (1 << age) != 0 {
+            return false;
+        }
+        self.seen |= 1 << age;
+        true
+    }
+}
+
+/// Clés d'une session: une par sens, pour ne jamais réutiliser un nonce.
+pub struct Session {
+    id: SessionId,
+    send: ChaCha20Poly1305,
+    receive: ChaCha20Poly1305,
+    next_counter: u64,
+    replay: ReplayWindow,
+    last_seen: Instant,
+}
+
+impl Session {
+    fn new(send: [u8; 32], receive: [u8; 32]) -> Session {
+        Session {
+            id: 0,
+            send: ChaCha20Poly1305::new(Key::from_slice(&send)),
+            receive: ChaCha20Poly1305::new(Key::from_slice(&receive)),
+            next_counter: 0,
+            replay: ReplayWindow::default(),
+            last_seen: Instant::now(),
+        }
+    }
+
+    /// Encrypts `plaintext` into a sealed datagram.
+    pub fn seal(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let counter = self.next_counter;
+        self.next_counter += 1;
+        let mut packet = Vec::with_capacity(SEALED_HEADER + plaintext.len() + 16);
+        packet.push(SEALED);
+        packet.extend_from_slice(&counter.to_be_bytes());
+        let ciphertext = self
+            .send
+            .encrypt(&nonce(counter), Payload { msg: plaintext, aad: &packet[..SEALED_HEADER] })
+            .expect("encryption cannot fail for in-memory buffers");
+        packet.extend_from_slice(&ciphertext);
+        packet
+    }
+
+    /// Decrypts a sealed datagram; None if it was forged, corrupted or already received.
+    pub fn open(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        if packet.len() < SEALED_HEADER || packet[0] != SEALED {
+            return None;
+        }
+        let counter = u64::from_be_bytes(packet[1..SEALED_HEADER].try_into().ok()?);
+        let plaintext = self
+            .receive
+            .decrypt(&nonce(counter), Payload { msg: &packet[SEALED_HEADER..], aad: &packet[..SEALED_HEADER] })
+            .ok()?;
+        // Vérifier le compteur après l'authentification: un faux paquet ne déplace pas la fenêtre
+        if !self.replay.accept(counter) {
+            return None;
+        }
+        self.last_seen = Instant::now();
+        Some(plaintext)
+    }
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    *Nonce::from_slice(&nonce)
+}
+
+// Deux secrets Diffie-Hellman: éphémère/éphémère et éphémère/clé du serveur
+fn derive_keys(client: &PublicKey, server_static: &PublicKey, server_ephemeral: &PublicKey, secrets: [[u8; 32]; 2]) -> ([u8; 32], [u8; 32]) {
+    let salt = [client.as_bytes().as_slice(), server_static.as_bytes().as_slice(), server_ephemeral.as_bytes().as_slice()].concat();
+    let hkdf = Hkdf::<Sha256>::new(Some(salt.as_slice()), &secrets.concat());
+    let mut keys = [0u8; 64];
+    hkdf.expand(KEY_INFO, &mut keys).expect("64 bytes is a valid HKDF output length");
+    let (client_to_server, server_to_client) = keys.split_at(32);
+    (client_to_server.try_into().unwrap(), server_to_client.try_into().unwrap())
+}
+
+/// Long-term server key. Clients can pin its public half with `--server-key=<hex>`.
+pub struct ServerIdentity {
+    secret: StaticSecret,
+    pub public: PublicKey,
+}
+
+impl ServerIdentity {
+    pub fn generate() -> ServerIdentity {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        ServerIdentity { secret, public }
+    }
+
+    /// Loads the hex secret in `path`, or creates it on the first run.
+    pub fn load_or_create(path: impl AsRef<Path>) -> io::Result<ServerIdentity> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                let bytes: [u8; 32] = from_hex(&text)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{}: invalid key", path.display())))?;
+                let secret = StaticSecret::from(bytes);
+                let public = PublicKey::from(&secret);
+                Ok(ServerIdentity { secret, public })
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let identity = ServerIdentity::generate();
+                write_secret(path, &to_hex(identity.secret.as_bytes()))?;
+                Ok(identity)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn fingerprint(&self) -> String {
+        to_hex(self.public.as_bytes())
+    }
+}
+
+// Le secret n'est lisible que par son propriétaire
+fn write_secret(path: &Path, text: &str) -> io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    io::Write::write_all(&mut options.open(path)?, text.as_bytes())
+}
+
+/// Sessions chiffrées du serveur, par adresse de client.
+pub struct Sessions {
+    pub identity: ServerIdentity,
+    pub mode: Encryption,
+    current: HashMap<SocketAddr, Session>,
+    // Session négociée mais pas encore utilisée par le client: un HELLO rejoué
+    // ne remplace pas la session en cours
+    pending: HashMap<SocketAddr, Pending>,
+    next_id: SessionId,
+}
+
+// Un HELLO répété (réponse perdue) reçoit la même réponse, pour que le client
+// et le serveur finissent avec les mêmes clés
+struct Pending {
+    client: [u8; 32],
+    reply: Vec<u8>,
+    session: Session,
+}
+
+/// Ce que le transport doit faire d'un datagramme reçu.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Incoming {
+    // Données pour la partie, et la session par laquelle elles sont arrivées si elles étaient chiffrées
+    Deliver(Vec<u8>, Option<SessionId>),
+    Reply(Vec<u8>),
+    Drop(&'static str),
+}
+
+impl Sessions {
+    pub fn new(identity: ServerIdentity, mode: Encryption) -> Sessions {
+        Sessions { identity, mode, current: HashMap::new(), pending: HashMap::new(), next_id: 1 }
+    }
+
+    pub fn receive(&mut self, packet: &[u8], from: SocketAddr) -> Incoming {
+        match packet.first() {
+            Some(&HELLO) if self.mode != Encryption::Off => self.handshake(packet, from),
+            Some(&SEALED) if self.mode != Encryption::Off => {
+                if let Some(session) = self.current.get_mut(&from) {
+                    if let Some(plaintext) = session.open(packet) {
+                        return Incoming::Deliver(plaintext, Some(session.id));
+                    }
+                }
+                match self.pending.get_mut(&from).and_then(|pending| pending.session.open(packet)) {
+                    Some(plaintext) => {
+                        let pending = self.pending.remove(&from).unwrap();
+                        let id = pending.session.id;
+                        self.current.insert(from, pending.session);
+                        Incoming::Deliver(plaintext, Some(id))
+                    }
+                    None => Incoming::Drop("invalid or replayed encrypted packet"),
+                }
+            }
+            // Un client chiffré ne peut pas être usurpé en clair
+            _ if self.current.contains_key(&from) => Incoming::Drop("plaintext from an encrypted client"),
+            _ if self.mode == Encryption::Required => Incoming::Drop("plaintext refused, encryption is required"),
+            _ => Incoming::Deliver(packet.to_vec(), None),
+        }
+    }
+
+    fn handshake(&mut self, packet: &[u8], from: SocketAddr) -> Incoming {
+        let Some(client) = packet.get(1..33).and_then(|bytes| <[u8; 32]>::try_from(bytes).ok()) else {
+            return Incoming::Drop("malformed hello");
+        };
+        if let Some(pending) = self.pending.get(&from).filter(|pending| pending.client == client) {
+            return Incoming::Reply(pending.reply.clone());
+        }
+        self.forget_idle();
+        // Un HELLO usurpant l'adresse d'un client ne remplace pas sa session: il faut
+        // qu'elle expire, ou que le client se déconnecte par elle
+        if self.current.contains_key(&from) {
+            return Incoming::Drop("handshake refused, the address has a live session");
+        }
+        if self.pending.len() >= MAX_PENDING_HANDSHAKES && !self.pending.contains_key(&from) {
+            return Incoming::Drop("too many handshakes in progress");
+        }
+        let client_key = PublicKey::from(client);
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral_public = PublicKey::from(&ephemeral);
+        let secrets = [
+            *ephemeral.diffie_hellman(&client_key).as_bytes(),
+            *self.identity.secret.diffie_hellman(&client_key).as_bytes(),
+        ];
+        let (client_to_server, server_to_client) = derive_keys(&client_key, &self.identity.public, &ephemeral_public, secrets);
+        let mut reply = vec![HELLO_REPLY];
+        reply.extend_from_slice(self.identity.public.as_bytes());
+        reply.extend_from_slice(ephemeral_public.as_bytes());
+        let mut session = Session::new(server_to_client, client_to_server);
+        session.id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(from, Pending { client, reply: reply.clone(), session });
+        Incoming::Reply(reply)
+    }
+
+    /// Seals `data` for `to` if that client uses encryption.
+    pub fn send(&mut self, data: &[u8], to: SocketAddr) -> Vec<u8> {
+        match self.current.get_mut(&to) {
+            Some(session) => session.seal(data),
+            None => data.to_vec(),
+        }
+    }
+
+    /// Ends the session `id` of `from`, after the client said goodbye on it.
+    pub fn end(&mut self, from: SocketAddr, id: SessionId) {
+        if self.current.get(&from).is_some_and(|session| session.id == id) {
+            self.current.remove(&from);
+        }
+    }
+
+    fn forget_idle(&mut self) {
+        self.current.retain(|_, session| session.last_seen.elapsed() < SESSION_TIMEOUT);
+        self.pending.retain(|_, pending| pending.session.last_seen.elapsed() < PENDING_TIMEOUT);
+    }
+}
+
+/// Côté client: secret à usage unique gardé entre le HELLO et la réponse du serveur.
+/// Il sert à deux échanges (clé éphémère et clé fixe du serveur), d'où un `StaticSecret`.
+pub struct ClientHandshake {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl ClientHandshake {
+    pub fn new() -> ClientHandshake {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        ClientHandshake { secret, public }
+    }
+
+    pub fn hello(&self) -> Vec<u8> {
+        let mut hello = vec![HELLO];
+        hello.extend_from_slice(self.public.as_bytes());
+        hello
+    }
+
+    /// Checks the server's reply against the pinned key, if any, and returns the session
+    /// and the server's public key.
+    pub fn finish(self, reply: &[u8], pinned: Option<&[u8; 32]>) -> Result<(Session, [u8; 32]), String> {
+        if reply.len() != 65 || reply[0] != HELLO_REPLY {
+            return Err("malformed handshake reply".to_string());
+        }
+        let server_static: [u8; 32] = reply[1..33].try_into().unwrap();
+        let server_ephemeral: [u8; 32] = reply[33..65].try_into().unwrap();
+        if pinned.is_some_and(|pinned| pinned != &server_static) {
+            return Err(format!("server key {} does not match the pinned key", to_hex(&server_static)));
+        }
+        let server_static_key = PublicKey::from(server_static);
+        let server_ephemeral_key = PublicKey::from(server_ephemeral);
+        // Même ordre que sur le serveur
+        let secrets = [
+            *self.secret.diffie_hellman(&server_ephemeral_key).as_bytes(),
+            *self.secret.diffie_hellman(&server_static_key).as_bytes(),
+        ];
+        let (client_to_server, server_to_client) = derive_keys(&self.public, &server_static_key, &server_ephemeral_key, secrets);
+        Ok((Session::new(client_to_server, server_to_client), server_static))
+    }
+}
+
+impl Default for ClientHandshake {
+    fn default() -> Self {
+        ClientHandshake::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    // Poignée de main complète entre un client et `sessions`
+    fn connect(sessions: &mut Sessions, from: SocketAddr) -> Session {
+        let handshake = ClientHandshake::new();
+        let Incoming::Reply(reply) = sessions.receive(&handshake.hello(), from) else {
+            panic!("no handshake reply");
+        };
+        // La réponse à un HELLO répété est la même
+        assert_eq!(sessions.receive(&handshake.hello(), from), Incoming::Reply(reply.clone()));
+        let pinned = *sessions.identity.public.as_bytes();
+        handshake.finish(&reply, Some(&pinned)).unwrap().0
+    }
+
+    #[test]
+    fn sealed_packets_round_trip_and_cannot_be_replayed_or_forged() {
+        let mut sessions = Sessions::new(ServerIdentity::generate(), Encryption::Optional);
+        let alice = address(1);
+        let mut client = connect(&mut sessions, alice);
+
+        let packet = client.seal(b"{\"hello\":1}");
+        assert!(!packet.windows(5).any(|w| w == b"hello"));
+        // Première session négociée par ce serveur
+        assert_eq!(sessions.receive(&packet, alice), Incoming::Deliver(b"{\"hello\":1}".to_vec(), Some(1)));
+        assert!(matches!(sessions.receive(&packet, alice), Incoming::Drop(_)));
+
+        let mut forged = client.seal(b"{}");
+        *forged.last_mut().unwrap() ^= 1;
+        assert!(matches!(sessions.receive(&forged, alice), Incoming::Drop(_)));
+        // Le même paquet, venu d'une autre adresse, n'a pas de session
+        assert!(matches!(sessions.receive(&client.seal(b"{}"), address(2)), Incoming::Drop(_)));
+        // Plus de clair pour un client chiffré
+        assert!(matches!(sessions.receive(b"{}", alice), Incoming::Drop(_)));
+
+        let reply = sessions.send(b"{\"pong\":1}", alice);
+        assert_eq!(client.open(&reply), Some(b"{\"pong\":1}".to_vec()));
+        assert_eq!(sessions.send(b"{}", address(2)), b"{}".to_vec());
+    }
+
+    #[test]
+    fn a_live_session_cannot_be_replaced_by_a_new_handshake() {
+        let mut sessions = Sessions::new(ServerIdentity::generate(), Encryption::Optional);
+        let alice = address(1);
+        let mut client = connect(&mut sessions, alice);
+        assert!(matches!(sessions.receive(&client.seal(b"{}"), alice), Incoming::Deliver(_, Some(_))));
+
+        // HELLO usurpant l'adresse d'alice: refusé, sa session continue
+        let spoofed = ClientHandshake::new();
+        assert!(matches!(sessions.receive(&spoofed.hello(), alice), Incoming::Drop(_)));
+        let Incoming::Deliver(_, Some(id)) = sessions.receive(&client.seal(b"{}"), alice) else {
+            panic!("the session of alice was replaced");
+        };
+
+        // Après sa déconnexion, l'adresse peut négocier une nouvelle session
+        sessions.end(alice, id);
+        assert!(matches!(sessions.receive(&ClientHandshake::new().hello(), alice), Incoming::Reply(_)));
+    }
+
+    #[test]
+    fn pending_handshakes_are_capped() {
+        let mut sessions = Sessions::new(ServerIdentity::generate(), Encryption::Optional);
+        for port in 0..MAX_PENDING_HANDSHAKES as u16 {
+            assert!(matches!(sessions.receive(&ClientHandshake::new().hello(), address(port)), Incoming::Reply(_)));
+        }
+        let hello = ClientHandshake::new().hello();
+        assert!(matches!(sessions.receive(&hello, address(60000)), Incoming::Drop(_)));
+        // Un client déjà en attente peut toujours recommencer
+        assert!(matches!(sessions.receive(&hello, address(0)), Incoming::Reply(_)));
+    }
+
+    #[test]
+    fn modes_and_pinned_keys_are_enforced() {
+        let mut required = Sessions::new(ServerIdentity::generate(), Encryption::Required);
+        assert!(matches!(required.receive(b"{}", address(1)), Incoming::Drop(_)));
+        let mut optional = Sessions::new(ServerIdentity::generate(), Encryption::Optional);
+        assert_eq!(optional.receive(b"{}", address(1)), Incoming::Deliver(b"{}".to_vec(), None));
+
+        let handshake = ClientHandshake::new();
+        let Incoming::Reply(reply) = optional.receive(&handshake.hello(), address(1)) else {
+            panic!("no handshake reply");
+        };
+        assert!(handshake.finish(&reply, Some(&[7; 32])).is_err());
+    }
+
+    #[test]
+    fn replay_window_accepts_each_counter_once() {
+        let mut window = ReplayWindow::default();
+        assert!(window.accept(5));
+        assert!(window.accept(3));
+        assert!(!window.accept(5));
+        assert!(!window.accept(3));
+        assert!(window.accept(100));
+        assert!(!window.accept(20));
+        assert!(window.accept(99));
+        assert!(!window.accept(99));
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(to_hex(&[0, 171, 255]), "00abff");
+        assert_eq!(from_hex("00abff"), Some(vec![0, 171, 255]));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
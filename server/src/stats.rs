@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const DEFAULT_STATS_FILE: &str = "stats.json";
+
+/// Compteurs d'un joueur, pour une partie ou pour toute sa carrière.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub kills: u64,
+    pub deaths: u64,
+    pub matches: u64,
+    pub shots: u64,
+    pub hits: u64,
+    pub seconds_played: u64,
+}
+
+impl PlayerStats {
+    /// Share of shots that hit someone, between 0 and 1.
+    pub fn accuracy(&self) -> f64 {
+        if self.shots == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.shots as f64
+        }
+    }
+
+    pub fn add(&mut self, other: &PlayerStats) {
+        self.kills += other.kills;
+        self.deaths += other.deaths;
+        self.matches += other.matches;
+        self.shots += other.shots;
+        self.hits += other.hits;
+        self.seconds_played += other.seconds_played;
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} kills, {} deaths, {} matches, accuracy {:.0}% ({}/{}), played {}",
+            self.kills,
+            self.deaths,
+            self.matches,
+            self.accuracy() * 100.0,
+            self.hits,
+            self.shots,
+            format_played(Duration::from_secs(self.seconds_played))
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "kills": self.kills,
+            "deaths": self.deaths,
+            "matches": self.matches,
+            "shots": self.shots,
+            "hits": self.hits,
+            "accuracy": self.accuracy(),
+            "seconds_played": self.seconds_played,
+        })
+    }
+}
+
+fn format_played(played: Duration) -> String {
+    let seconds = played.as_secs();
+    if seconds >= 3600 {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+/// Career stats of every player, kept in a JSON file between server runs.
+/// Without a file (`StatsStore::default()`) they only live in memory.
+#[derive(Debug, Clone, Default)]
+pub struct StatsStore {
+    path: Option<PathBuf>,
+    players: BTreeMap<String, PlayerStats>,
+}
+
+impl StatsStore {
+    /// Loads `path`; a missing file is an empty store that will be created on the first save.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<StatsStore> {
+        let path = path.into();
+        let players = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(StatsStore { path: Some(path), players })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PlayerStats> {
+        self.players.get(name)
+    }
+
+    /// Adds a finished match to the player's career, saves, and returns the new totals.
+    pub fn record(&mut self, name: &str, played: &PlayerStats) -> PlayerStats {
+        let career = self.players.entry(name.to_string()).or_default();
+        career.add(played);
+        let career = career.clone();
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save stats: {}", e);
+        }
+        career
+    }
+
+    // Écrit dans un fichier temporaire puis le renomme: un arrêt brutal ne corrompt pas les stats
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_string_pretty(&self.players)?)?;
+        std::fs::rename(&temporary, path)
+    }
+
+    /// Les meilleurs joueurs par nombre d'éliminations, pour la commande `stats`.
+    pub fn leaderboard(&self, count: usize) -> String {
+        if self.players.is_empty() {
+            return "no stats recorded yet".to_string();
+        }
+        let mut sorted = self.players.iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| b.1.kills.cmp(&a.1.kills).then(a.0.cmp(b.0)));
+        let mut lines = vec![format!("{} players with stats", self.players.len())];
+        for (name, stats) in sorted.into_iter().take(count) {
+            lines.push(format!("  {:<16} {}", name, stats.summary()));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_add_up_in_the_career() {
+        let mut store = StatsStore::default();
+        let first = PlayerStats { kills: 2, deaths: 1, matches: 1, shots: 8, hits: 4, seconds_played: 90 };
+        store.record("alice", &first);
+        let career = store.record("alice", &PlayerStats { kills: 1, matches: 1, shots: 2, ..Default::default() });
+        assert_eq!(career.kills, 3);
+        assert_eq!(career.matches, 2);
+        assert_eq!(career.accuracy(), 0.4);
+        assert_eq!(store.get("alice"), Some(&career));
+        assert_eq!(
+            career.summary(),
+            "3 kills, 1 deaths, 2 matches, accuracy 40% (4/10), played 1m30s"
+        );
+    }
+
+    #[test]
+    fn stats_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("maze-stats-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = StatsStore::load(&path).unwrap();
+        assert!(store.get("bob").is_none());
+        store.record("bob", &PlayerStats { deaths: 1, matches: 1, ..Default::default() });
+
+        let reloaded = StatsStore::load(&path).unwrap();
+        assert_eq!(reloaded.get("bob").map(|stats| stats.deaths), Some(1));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
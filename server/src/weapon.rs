@@ -0,0 +1,267 @@
+use bevy::math::{Quat, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::FireMode;
+
+// Définition d'une arme, chargée depuis weapons.json et envoyée aux clients
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub name: String,
+    pub fire_mode: FireMode,
+    /// Shots per second.
+    pub fire_rate: f32,
+    pub projectile_speed: f32,
+    pub damage: i64,
+    /// Maximum deviation from the aim direction, in radians.
+    pub spread: f32,
+    pub magazine_size: u32,
+    /// Seconds.
+    pub reload_time: f32,
+    /// Seconds before a projectile disappears on its own.
+    pub projectile_lifetime: f32,
+    #[serde(default)]
+    pub ricochets: u32,
+}
+
+pub fn default_weapons() -> Vec<Weapon> {
+    vec![
+        Weapon {
+            name: "Blaster".to_string(),
+            fire_mode: FireMode::Projectile,
+            fire_rate: 2.0,
+            projectile_speed: 20.0,
+            damage: 1,
+            spread: 0.02,
+            magazine_size: 10,
+            reload_time: 1.5,
+            projectile_lifetime: 3.0,
+            ricochets: 0,
+        },
+        Weapon {
+            name: "Rail".to_string(),
+            fire_mode: FireMode::Hitscan,
+            fire_rate: 0.8,
+            projectile_speed: 0.0,
+            damage: 3,
+            spread: 0.0,
+            magazine_size: 3,
+            reload_time: 2.5,
+            projectile_lifetime: 0.0,
+            ricochets: 0,
+        },
+    ]
+}
+
+impl Weapon {
+    /// Checks the values the server turns into durations: they must be finite and not negative,
+    /// and `fire_rate` must be strictly positive.
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            ("fire_rate", self.fire_rate),
+            ("projectile_speed", self.projectile_speed),
+            ("spread", self.spread),
+            ("reload_time", self.reload_time),
+            ("projectile_lifetime", self.projectile_lifetime),
+        ];
+        for (field, value) in values {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{} must be a finite number >= 0, got {}", field, value));
+            }
+        }
+        if self.fire_rate <= 0.0 {
+            return Err(format!("fire_rate must be > 0, got {}", self.fire_rate));
+        }
+        Ok(())
+    }
+}
+
+// Charge les armes depuis `path`, ou les armes par défaut si le fichier est absent ou invalide.
+// Une arme aux valeurs invalides est ignorée; s'il n'en reste aucune, on garde les armes par défaut.
+pub fn load_weapons(path: &str) -> Vec<Weapon> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(_) => return default_weapons(),
+    };
+    let weapons = match serde_json::from_str::<Vec<Weapon>>(&data) {
+        Ok(weapons) => weapons,
+        Err(e) => {
+            eprintln!("Invalid weapons file {}: {}", path, e);
+            return default_weapons();
+        }
+    };
+    let weapons: Vec<Weapon> = weapons
+        .into_iter()
+        .filter(|weapon| match weapon.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Ignoring weapon {} from {}: {}", weapon.name, path, e);
+                false
+            }
+        })
+        .collect();
+    if weapons.is_empty() {
+        return default_weapons();
+    }
+    weapons
+}
+
+/// Deviates `direction` by a random angle up to `spread` radians, in yaw and pitch.
+pub fn apply_spread(direction: Vec3, spread: f32, rng: &mut impl Rng) -> Vec3 {
+    if spread <= 0.0 {
+        return direction;
+    }
+    let yaw = rng.gen_range(-spread..=spread);
+    let pitch = rng.gen_range(-spread..=spread);
+    let right = direction.cross(Vec3::Y).normalize_or_zero();
+    (Quat::from_rotation_y(yaw) * Quat::from_axis_angle(right, pitch) * direction).normalize()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShotRejected {
+    Cooldown,
+    Reloading,
+    EmptyMagazine,
+}
+
+// État des armes d'un joueur, tenu par le serveur
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponState {
+    pub current: usize,
+    pub ammo: Vec<u32>,
+    pub next_shot_at: Option<Instant>,
+    pub reload_until: Option<Instant>,
+    // Projectiles tirés qui peuvent encore toucher: (arme, expiration, id de la balle)
+    pub live_projectiles: Vec<(usize, Instant, Option<u64>)>,
+    // Cadence doublée jusqu'à cet instant
+    pub rapid_fire_until: Option<Instant>,
+}
+
+impl WeaponState {
+    pub fn new(weapons: &[Weapon]) -> WeaponState {
+        WeaponState {
+            current: 0,
+            ammo: weapons.iter().map(|weapon| weapon.magazine_size).collect(),
+            next_shot_at: None,
+            reload_until: None,
+            live_projectiles: Vec::new(),
+            rapid_fire_until: None,
+        }
+    }
+
+    fn finish_reload(&mut self, weapons: &[Weapon], now: Instant) {
+        if let Some(until) = self.reload_until {
+            if now >= until {
+                self.ammo[self.current] = weapons[self.current].magazine_size;
+                self.reload_until = None;
+            }
+        }
+    }
+
+    /// Consumes one round of the current weapon and returns its index.
+    /// `ball_id` is the client's sequence number for the projectile, if any.
+    pub fn try_fire(&mut self, weapons: &[Weapon], now: Instant, ball_id: Option<u64>) -> Result<usize, ShotRejected> {
+        self.finish_reload(weapons, now);
+        if self.reload_until.is_some() {
+            return Err(ShotRejected::Reloading);
+        }
+        if self.next_shot_at.is_some_and(|next| now < next) {
+            return Err(ShotRejected::Cooldown);
+        }
+        if self.ammo[self.current] == 0 {
+            return Err(ShotRejected::EmptyMagazine);
+        }
+
+        let weapon = &weapons[self.current];
+        self.ammo[self.current] -= 1;
+        let mut fire_rate = weapon.fire_rate.max(0.01);
+        if self.rapid_fire_until.is_some_and(|until| now < until) {
+            fire_rate *= 2.0;
+        }
+        self.next_shot_at = Some(now + Duration::from_secs_f32(1.0 / fire_rate));
+        if weapon.fire_mode == FireMode::Projectile {
+            self.live_projectiles.push((
+                self.current,
+                now + Duration::from_secs_f32(weapon.projectile_lifetime),
+                ball_id,
+            ));
+        }
+        Ok(self.current)
+    }
+
+    pub fn start_reload(&mut self, weapons: &[Weapon], now: Instant) -> bool {
+        self.finish_reload(weapons, now);
+        if self.reload_until.is_some() || self.ammo[self.current] == weapons[self.current].magazine_size {
+            return false;
+        }
+        self.reload_until = Some(now + Duration::from_secs_f32(weapons[self.current].reload_time));
+        true
+    }
+
+    // Changer d'arme annule le rechargement en cours
+    pub fn switch(&mut self, index: usize, weapons: &[Weapon]) -> bool {
+        if index >= weapons.len() || index == self.current {
+            return false;
+        }
+        self.current = index;
+        self.reload_until = None;
+        true
+    }
+
+    /// A projectile hit is only accepted while the shot `ball_id` is still in flight.
+    pub fn consume_projectile_hit(&mut self, now: Instant, ball_id: u64) -> Option<usize> {
+        self.live_projectiles.retain(|&(_, expires, _)| expires > now);
+        let index = self
+            .live_projectiles
+            .iter()
+            .position(|&(_, _, ball)| ball == Some(ball_id))?;
+        Some(self.live_projectiles.remove(index).0)
+    }
+
+    // Munitions de l'arme courante, en tenant compte d'un rechargement terminé
+    pub fn current_ammo(&self, weapons: &[Weapon], now: Instant) -> u32 {
+        match self.reload_until {
+            Some(until) if now >= until => weapons[self.current].magazine_size,
+            _ => self.ammo[self.current],
+        }
+    }
+
+    pub fn reload_remaining(&self, now: Instant) -> f32 {
+        self.reload_until
+            .map_or(0.0, |until| until.saturating_duration_since(now).as_secs_f32())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weapons_with_unusable_values_are_ignored() {
+        let path = std::env::temp_dir().join(format!("maze-weapons-{}.json", std::process::id()));
+        let mut weapons = default_weapons();
+        let mut broken = weapons[0].clone();
+        broken.name = "Broken".to_string();
+        broken.projectile_lifetime = -1.0;
+        weapons.push(broken);
+        let mut stuck = weapons[0].clone();
+        stuck.name = "Stuck".to_string();
+        stuck.fire_rate = 0.0;
+        weapons.push(stuck);
+        // JSON n'a pas de NaN ni d'infini, mais un nombre trop grand pour un f32 devient infini
+        let mut data = serde_json::to_string(&weapons).unwrap();
+        data = data.replacen("\"reload_time\":2.5", "\"reload_time\":1e300", 1);
+        std::fs::write(&path, data).unwrap();
+
+        let loaded = load_weapons(path.to_str().unwrap());
+        let names: Vec<&str> = loaded.iter().map(|weapon| weapon.name.as_str()).collect();
+        assert_eq!(names, ["Blaster"]);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut nan = default_weapons()[1].clone();
+        nan.spread = f32::NAN;
+        assert!(nan.validate().is_err());
+        assert!(default_weapons().iter().all(|weapon| weapon.validate().is_ok()));
+    }
+}
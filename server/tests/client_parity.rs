@@ -0,0 +1,162 @@
+// Le client et le serveur ont chacun leur copie des labyrinthes, des calculs de tir, du
+// chiffrement et du réseau simulé. Ces tests incluent les fichiers du client et vérifient que les deux copies s'accordent.
+use bevy::math::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+use server::*;
+
+#[path = "../../client/src/levels.rs"]
+mod client_levels;
+#[allow(dead_code)]
+#[path = "../../client/src/geometry.rs"]
+mod client_geometry;
+// Même nom que dans le client, dont les autres fichiers importent `crate::secure`
+#[allow(dead_code)]
+#[path = "../../client/src/secure.rs"]
+mod secure;
+#[allow(dead_code)]
+#[path = "../../client/src/netsim.rs"]
+mod client_netsim;
+
+fn client_maze(level: i32) -> client_geometry::Maze {
+    client_geometry::Maze { layout: client_levels::level_layout(level), cell_size: CELL_SIZE }
+}
+
+#[test]
+fn level_layouts_match_the_client() {
+    for level in 1..=3 {
+        // Les cases 2 à 5 marquent les objets du serveur: ce sont des couloirs pour le client
+        let layout: Vec<Vec<u8>> = Maze::for_level(level)
+            .layout
+            .iter()
+            .map(|row| row.iter().map(|&cell| if (2..=5).contains(&cell) { 0 } else { cell }).collect())
+            .collect();
+        assert_eq!(layout, client_levels::level_layout(level), "level {}", level);
+    }
+}
+
+#[test]
+fn raycasts_and_hit_volumes_match_the_client() {
+    let mut rng = StdRng::seed_from_u64(5);
+    for level in 1..=3 {
+        let server_maze = Maze::for_level(level);
+        let client_maze = client_maze(level);
+        for _ in 0..500 {
+            let from = Vec3::new(rng.gen_range(0.0..28.0), 1.0, rng.gen_range(0.0..30.0));
+            let to = Vec3::new(rng.gen_range(-2.0..30.0), 1.0, rng.gen_range(-2.0..32.0));
+            let server_hit = server_maze.raycast(from, to).map(|hit| (hit.point, hit.normal, hit.distance));
+            let client_hit = client_maze.raycast(from, to).map(|hit| (hit.point, hit.normal, hit.distance));
+            assert_eq!(server_hit, client_hit, "level {} from {} to {}", level, from, to);
+            assert_eq!(server_maze.cell_at(to), client_maze.cell_at(to));
+
+            let direction = (to - from).normalize_or_zero();
+            let center = Vec3::new(rng.gen_range(0.0..28.0), 1.0, rng.gen_range(0.0..30.0));
+            assert_eq!(
+                ray_sphere(from, direction, center, HIT_RADIUS),
+                client_geometry::ray_sphere(from, direction, center, HIT_RADIUS)
+            );
+        }
+    }
+}
+
+#[test]
+fn spread_teams_and_positions_match_the_client() {
+    let direction = Vec3::new(0.3, 0.1, -1.0).normalize();
+    for seed in 0..20 {
+        assert_eq!(
+            apply_spread(direction, 0.05, &mut StdRng::seed_from_u64(seed)),
+            client_geometry::apply_spread(direction, 0.05, &mut StdRng::seed_from_u64(seed))
+        );
+    }
+    for id in 0..10 {
+        assert_eq!(team_of(id), client_geometry::team_of(id));
+    }
+    for value in [json!([1.0, 2.5, -3.0]), json!([1, 2]), json!("x"), json!([1, "a", 3])] {
+        assert_eq!(parse_vec3(&value), client_geometry::parse_vec3(&value));
+    }
+}
+
+#[test]
+fn client_handshakes_and_sealed_packets_work_with_the_server() {
+    assert_eq!((secure::HELLO, secure::HELLO_REPLY, secure::SEALED), (HELLO, HELLO_REPLY, SEALED));
+    let identity = ServerIdentity::generate();
+    let pinned = *identity.public.as_bytes();
+    let mut sessions = Sessions::new(identity, Encryption::Required);
+    let alice = SocketAddr::from(([127, 0, 0, 1], 4000));
+
+    let handshake = secure::ClientHandshake::new();
+    let Incoming::Reply(reply) = sessions.receive(&handshake.hello(), alice) else {
+        panic!("no handshake reply");
+    };
+    let (mut client, server_key) = handshake.finish(&reply, Some(&pinned)).unwrap();
+    assert_eq!(server_key, pinned);
+
+    // Client vers serveur, puis serveur vers client, chaque paquet une seule fois
+    let packet = client.seal(b"{\"hello\":1}");
+    assert_eq!(sessions.receive(&packet, alice), Incoming::Deliver(b"{\"hello\":1}".to_vec(), Some(1)));
+    assert!(matches!(sessions.receive(&packet, alice), Incoming::Drop(_)));
+    let answer = sessions.send(b"{\"welcome\":1}", alice);
+    assert_eq!(answer.first(), Some(&SEALED));
+    assert_eq!(client.open(&answer), Some(b"{\"welcome\":1}".to_vec()));
+    assert_eq!(client.open(&answer), None);
+
+    // Un client qui attend une autre clé refuse le serveur
+    let other = secure::ClientHandshake::new();
+    let bob = SocketAddr::from(([127, 0, 0, 1], 4001));
+    let Incoming::Reply(reply) = sessions.receive(&other.hello(), bob) else {
+        panic!("no handshake reply");
+    };
+    assert!(other.finish(&reply, Some(&[0; 32])).is_err());
+}
+
+#[test]
+fn network_conditions_match_the_client() {
+    assert_eq!(REORDER_DELAY, client_netsim::REORDER_DELAY);
+    let specs = ["", "off", "latency=80ms, jitter=20, loss=5%,dup=1%,reorder=2", "loss=150%", "latency=fast", "speed=1", "latency"];
+    for spec in specs {
+        let server = NetConditions::parse(spec);
+        let client = client_netsim::NetConditions::parse(spec);
+        assert_eq!(server.as_ref().map(ToString::to_string), client.as_ref().map(ToString::to_string), "{}", spec);
+        let (Ok(server), Ok(client)) = (server, client) else {
+            continue;
+        };
+        let mut server_rng = StdRng::seed_from_u64(3);
+        let mut client_rng = StdRng::seed_from_u64(3);
+        for _ in 0..1_000 {
+            assert_eq!(server.fate(&mut server_rng), client.fate(&mut client_rng));
+        }
+    }
+}
+
+#[tokio::test]
+async fn client_transport_talks_to_the_server_transport() {
+    let identity = ServerIdentity::generate();
+    let pinned = *identity.public.as_bytes();
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server = Arc::new(Transport::new(Arc::new(server)).with_sessions(Sessions::new(identity, Encryption::Required)));
+    let address = server.local_addr().unwrap();
+    // Le serveur renvoie chaque message reçu: en mode `Required`, seul un message chiffré arrive
+    tokio::spawn(async move {
+        let mut buf = vec![0; 65536];
+        while let Ok((len, from, _)) = server.recv_from(&mut buf).await {
+            let _ = server.send_to(&buf[..len], from).await;
+        }
+    });
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.connect(address).await.unwrap();
+    let client = client_netsim::Transport::new(Arc::new(socket));
+    assert_eq!(client.handshake(Some(pinned)).await.unwrap(), pinned);
+    client.send(b"{\"ping\":1}").await.unwrap();
+    let mut buf = vec![0; 65536];
+    let (len, from) = timeout(Duration::from_secs(2), client.recv_from(&mut buf)).await.unwrap().unwrap();
+    assert_eq!(from, address);
+    assert_eq!(&buf[..len], b"{\"ping\":1}");
+}
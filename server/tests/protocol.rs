@@ -0,0 +1,326 @@
+//! Tests d'intégration: un vrai serveur sur `127.0.0.1:0` et des clients UDP scriptés
+//! qui parlent le même JSON que le client Bevy.
+
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use server::*;
+
+const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn test_weapons() -> Vec<Weapon> {
+    vec![Weapon {
+        name: "Rail".to_string(),
+        fire_mode: FireMode::Hitscan,
+        // Cadence élevée pour enchaîner les tirs sans attendre
+        fire_rate: 20.0,
+        projectile_speed: 0.0,
+        damage: 5,
+        spread: 0.0,
+        magazine_size: 10,
+        reload_time: 2.5,
+        projectile_lifetime: 0.0,
+        ricochets: 0,
+    }]
+}
+
+/// Starts a level 1 server on an ephemeral port and returns its address.
+async fn start_server(max_players: usize) -> SocketAddr {
+    start_server_with(max_players, NetConditions::default()).await
+}
+
+/// Same, with the server's datagrams going through a simulated network.
+async fn start_server_with(max_players: usize, conditions: NetConditions) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    spawn_server(max_players, Transport::with_conditions(Arc::new(socket), conditions, 7))
+}
+
+fn spawn_server(max_players: usize, socket: Transport) -> SocketAddr {
+    let socket = Arc::new(socket);
+    let address = socket.local_addr().unwrap();
+    let mut game = Game::with_seed(1, test_weapons(), 7);
+    game.max_players = max_players;
+    let (admin_tx, admin_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        // Garder l'émetteur en vie tant que le serveur tourne
+        let _admin_tx = admin_tx;
+        serve(socket, game, admin_rx).await;
+    });
+    address
+}
+
+/// Client sans interface: envoie des messages comme le client Bevy et lit les réponses.
+struct TestClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    name: String,
+    id: usize,
+}
+
+impl TestClient {
+    async fn new(server: SocketAddr, name: &str) -> TestClient {
+        TestClient {
+            socket: UdpSocket::bind("127.0.0.1:0").await.unwrap(),
+            server,
+            name: name.to_string(),
+            id: 0,
+        }
+    }
+
+    /// Connects and waits for the answer, which is either ConnectSuccessfull or ConnectFailed.
+    async fn connect(&mut self) -> MessageSended {
+        self.send(MessageType::Connect, Value::Null).await;
+        let answer = self
+            .recv_until(|m| matches!(m.message_type, MessageType::ConnectSuccessfull | MessageType::ConnectFailed))
+            .await;
+        if answer.message_type == MessageType::ConnectSuccessfull {
+            self.id = answer.id_player;
+        }
+        answer
+    }
+
+    async fn connected(server: SocketAddr, name: &str) -> TestClient {
+        let mut client = TestClient::new(server, name).await;
+        let answer = client.connect().await;
+        assert_eq!(answer.message_type, MessageType::ConnectSuccessfull, "{} could not connect", name);
+        client
+    }
+
+    async fn send(&self, message_type: MessageType, content: Value) {
+        let message = MessageRecieve {
+            message_type,
+            player_name: self.name.clone(),
+            content,
+            id_player: self.id,
+        };
+        let data = serde_json::to_vec(&message).unwrap();
+        self.socket.send_to(&data, self.server).await.unwrap();
+    }
+
+    async fn move_to(&self, position: [f32; 3]) {
+        self.send(MessageType::Action, json!({ "movement": position })).await;
+    }
+
+    async fn recv(&self) -> Option<MessageSended> {
+        let mut buf = vec![0; 65536];
+        let (amt, _) = timeout(RECV_TIMEOUT, self.socket.recv_from(&mut buf)).await.ok()?.ok()?;
+        Some(serde_json::from_slice(&buf[..amt]).expect("invalid message from the server"))
+    }
+
+    /// Skips messages until one matches, and panics if none arrives in time.
+    async fn recv_until(&self, predicate: impl Fn(&MessageSended) -> bool) -> MessageSended {
+        loop {
+            match self.recv().await {
+                Some(message) if predicate(&message) => return message,
+                Some(_) => continue,
+                None => panic!("{}: expected message not received", self.name),
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn twelve_clients_connect_and_see_each_other_move() {
+    let server = start_server(12).await;
+    let mut clients = Vec::new();
+    for index in 0..12 {
+        clients.push(TestClient::connected(server, &format!("player{}", index)).await);
+    }
+    let mut ids = clients.iter().map(|client| client.id).collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, (0..12).collect::<Vec<_>>());
+
+    let mover = &clients[3];
+    mover.move_to([8.0, 1.0, 6.0]).await;
+    for client in clients.iter().filter(|client| client.id != mover.id) {
+        let relayed = client
+            .recv_until(|m| m.message_type == MessageType::Action && m.id_player == mover.id)
+            .await;
+        assert_eq!(relayed.content["movement"], json!([8.0, 1.0, 6.0]));
+    }
+}
+
+#[tokio::test]
+async fn hitscan_kill_sends_death_and_deletes_the_player() {
+    let server = start_server(10).await;
+    let alice = TestClient::connected(server, "alice").await;
+    let bob = TestClient::connected(server, "bob").await;
+    let carol = TestClient::connected(server, "carol").await;
+
+    // Même couloir (ligne 3 du niveau 1), sans mur entre alice et bob
+    alice.move_to([8.0, 1.0, 6.0]).await;
+    bob.move_to([16.0, 1.0, 6.0]).await;
+    alice
+        .recv_until(|m| m.message_type == MessageType::Action && m.id_player == bob.id)
+        .await;
+
+    let shot = json!({ "origin": [8.0, 1.0, 6.0], "direction": [1.0, 0.0, 0.0] });
+    alice.send(MessageType::Shoot, shot.clone()).await;
+    let damage = bob.recv_until(|m| m.message_type == MessageType::Damage).await;
+    assert_eq!(damage.player_life, 5);
+    assert_eq!(damage.content["attacker"], json!(alice.id));
+    let confirm = alice.recv_until(|m| m.message_type == MessageType::HitConfirm).await;
+    assert_eq!(confirm.content["killed"], json!(false));
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    alice.send(MessageType::Shoot, shot).await;
+    let death = bob.recv_until(|m| m.message_type == MessageType::PlayerDeath).await;
+    assert!(death.player_life <= 0);
+    let kill = carol.recv_until(|m| m.message_type == MessageType::Kill).await;
+    assert_eq!(kill.content["victim"], json!("bob"));
+    assert_eq!(kill.content["killer"], json!("alice"));
+
+    // Comme le client Bevy: le mort prévient les autres avant de quitter
+    bob.send(MessageType::OtherDeadPlayer, Value::Null).await;
+    for client in [&alice, &carol] {
+        let deleted = client.recv_until(|m| m.message_type == MessageType::DeletePlayer).await;
+        assert_eq!(deleted.id_player, bob.id);
+    }
+}
+
+#[tokio::test]
+async fn duplicate_username_is_refused() {
+    let server = start_server(10).await;
+    let alice = TestClient::connected(server, "alice").await;
+
+    let mut impostor = TestClient::new(server, "alice").await;
+    let answer = impostor.connect().await;
+    assert_eq!(answer.message_type, MessageType::ConnectFailed);
+    assert!(!answer.canconnect);
+    assert_eq!(answer.content, json!("Username already taken. Please choose another one."));
+
+    // Le nom redevient libre après la déconnexion
+    alice.send(MessageType::Disconnect, Value::Null).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(impostor.connect().await.message_type, MessageType::ConnectSuccessfull);
+}
+
+#[tokio::test]
+async fn player_cap_refuses_extra_clients() {
+    let server = start_server(3).await;
+    let mut clients = Vec::new();
+    for index in 0..3 {
+        clients.push(TestClient::connected(server, &format!("player{}", index)).await);
+    }
+
+    let mut late = TestClient::new(server, "late").await;
+    let answer = late.connect().await;
+    assert_eq!(answer.message_type, MessageType::ConnectFailed);
+    assert!(!answer.canconnect);
+    assert_eq!(answer.content, json!("The server is full."));
+
+    // Les joueurs déjà connectés ne sont pas touchés par le refus
+    clients[0].move_to([8.0, 1.0, 6.0]).await;
+    clients[1]
+        .recv_until(|m| m.message_type == MessageType::Action && m.id_player == clients[0].id)
+        .await;
+}
+
+#[tokio::test]
+async fn simulated_latency_delays_both_directions() {
+    let conditions = NetConditions::parse("latency=100ms").unwrap();
+    let server = start_server_with(10, conditions).await;
+    let started = std::time::Instant::now();
+    let _alice = TestClient::connected(server, "alice").await;
+    // 100 ms à l'arrivée sur le serveur, 100 ms au départ de la réponse
+    assert!(started.elapsed() >= Duration::from_millis(200), "answered in {:?}", started.elapsed());
+}
+
+#[tokio::test]
+async fn simulated_loss_drops_every_datagram() {
+    let conditions = NetConditions::parse("loss=100%").unwrap();
+    let server = start_server_with(10, conditions).await;
+    let alice = TestClient::new(server, "alice").await;
+    alice.send(MessageType::Connect, Value::Null).await;
+    assert!(alice.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn required_encryption_seals_the_whole_session() {
+    let identity = ServerIdentity::generate();
+    let pinned = *identity.public.as_bytes();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let transport = Transport::new(Arc::new(socket)).with_sessions(Sessions::new(identity, Encryption::Required));
+    let server = spawn_server(10, transport);
+
+    // Un client en clair n'obtient aucune réponse
+    let alice = TestClient::new(server, "alice").await;
+    alice.send(MessageType::Connect, Value::Null).await;
+    assert!(alice.recv().await.is_none());
+
+    let bob = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let handshake = ClientHandshake::new();
+    bob.send_to(&handshake.hello(), server).await.unwrap();
+    let mut buf = vec![0; 65536];
+    let (amt, _) = timeout(RECV_TIMEOUT, bob.recv_from(&mut buf)).await.unwrap().unwrap();
+    let (mut session, _) = handshake.finish(&buf[..amt], Some(&pinned)).unwrap();
+
+    let connect = MessageRecieve {
+        message_type: MessageType::Connect,
+        player_name: "bob".to_string(),
+        content: Value::Null,
+        id_player: 0,
+    };
+    bob.send_to(&session.seal(&serde_json::to_vec(&connect).unwrap()), server).await.unwrap();
+    let (amt, _) = timeout(RECV_TIMEOUT, bob.recv_from(&mut buf)).await.unwrap().unwrap();
+    assert_eq!(buf[0], SEALED);
+    assert!(!buf[..amt].windows(3).any(|w| w == b"bob"));
+    let answer: MessageSended = serde_json::from_slice(&session.open(&buf[..amt]).unwrap()).unwrap();
+    assert_eq!(answer.message_type, MessageType::ConnectSuccessfull);
+}
+
+#[tokio::test]
+async fn discovery_queries_get_the_server_info() {
+    let socket = Arc::new(Transport::new(Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap())));
+    let server = socket.local_addr().unwrap();
+    let mut game = Game::with_seed(2, test_weapons(), 7);
+    game.name = "LAN party".to_string();
+    let (admin_tx, admin_rx) = mpsc::channel(1);
+    let discovery = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let discovery_address = discovery.local_addr().unwrap();
+    tokio::spawn(answer_discovery(discovery, server, admin_tx));
+    tokio::spawn(serve(socket, game, admin_rx));
+    let _alice = TestClient::connected(server, "alice").await;
+
+    // Seule la requête exacte reçoit une réponse
+    let browser = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    browser.send_to(b"who is there?", discovery_address).await.unwrap();
+    browser.send_to(DISCOVERY_QUERY, discovery_address).await.unwrap();
+    let mut buf = vec![0; 65536];
+    let (amt, _) = timeout(RECV_TIMEOUT, browser.recv_from(&mut buf)).await.unwrap().unwrap();
+    let info: Value = serde_json::from_slice(&buf[..amt]).unwrap();
+    assert_eq!(info["name"], "LAN party");
+    assert_eq!(info["map"], "medium maze");
+    assert_eq!(info["level"], 2);
+    assert_eq!(info["players"], 1);
+    assert_eq!(info["max_players"], 10);
+    assert_eq!(info["password"], false);
+    assert_eq!(info["address"], server.to_string());
+}
+
+#[tokio::test]
+async fn discovery_replies_are_limited_per_address() {
+    let socket = Arc::new(Transport::new(Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap())));
+    let server = socket.local_addr().unwrap();
+    let (admin_tx, admin_rx) = mpsc::channel(1);
+    let discovery = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let discovery_address = discovery.local_addr().unwrap();
+    tokio::spawn(answer_discovery(discovery, server, admin_tx));
+    tokio::spawn(serve(socket, Game::with_seed(1, test_weapons(), 7), admin_rx));
+
+    let browser = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    for _ in 0..DISCOVERY_RATE_LIMIT + 3 {
+        browser.send_to(DISCOVERY_QUERY, discovery_address).await.unwrap();
+    }
+    let mut buf = vec![0; 65536];
+    let mut replies = 0;
+    while timeout(Duration::from_millis(500), browser.recv_from(&mut buf)).await.is_ok() {
+        replies += 1;
+    }
+    assert_eq!(replies, DISCOVERY_RATE_LIMIT);
+}